@@ -1,32 +1,24 @@
 extern crate alumine;
 
-use std::str::FromStr;
 use std::fs::File;
-use std::io::{BufReader,BufRead};
 
 use alumine::alg::Vector;
+use alumine::data::csv::CsvReader;
 
 use alumine::ml::Classifier;
 use alumine::ml::linear::LinearRegression;
 
 fn read_data(filename: &str) -> (Vec<Vector<f64>>, Vec<f64>) {
     let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
-
-    let mut samples = Vec::new();
-    let mut labels = Vec::new();
-
-    for line in reader.lines().filter_map(|line| line.ok()) {
-        if line.starts_with("#") { continue; }
-        let mut tokens = line.split(",");
-        let data = f64::from_str(tokens.next().unwrap()).unwrap();
-        let label = f64::from_str(tokens.next().unwrap()).unwrap();
-        // Always add a 1 as final value to allow for affine offset
-        samples.push(Vector::from_slice(&[data, 1f64]));
-        labels.push(label);
-    }
-
-    (samples,labels)
+    let dataset = match CsvReader::new().read_matrix(file) {
+        Ok(dataset) => dataset,
+        Err(err) => panic!("Could not read {}: {}", filename, err),
+    };
+
+    // Always add a 1 as final value to allow for affine offset
+    let samples = dataset.inputs().iter().map(|x| Vector::from_slice(&[x[0], 1f64])).collect();
+
+    (samples, dataset.labels().to_vec())
 }
 
 fn main() {
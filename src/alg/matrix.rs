@@ -7,6 +7,7 @@ use alg::Vector;
 
 /// Represents a simple `NxM` matrix.
 #[derive(Clone,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
 pub struct Matrix<T> {
     /// Number of rows (max Y)
     pub m: usize,
@@ -34,6 +35,25 @@ impl <T> Matrix<T> {
         }
     }
 
+    /// Creates a new matrix from its values, column after column.
+    ///
+    /// Panics if there aren't `n * m` values.
+    pub fn from_col_major(n: usize, m: usize, data: Vec<T>) -> Self {
+        if data.len() != n * m {
+            panic!("Matrix data doesn't match its dimensions.");
+        }
+        Matrix {
+            m: m,
+            n: n,
+            data: data,
+        }
+    }
+
+    /// Returns a read-only view onto the inner data, column after column.
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
     /// Only keeps a subset of consecutive columns indicated
     /// by the given range, and discard the rest.
     pub fn keep_cols(&mut self, cols: Range<usize>) {
@@ -324,6 +344,146 @@ impl <T: Clone + Float> Matrix<T> {
 
         self
     }
+
+    /// Solves `self * x = b` for `x`, assuming `self` is lower triangular.
+    ///
+    /// This is typically used with the result of `cholesky`.
+    pub fn solve_lower_triangular(&self, b: &Vector<T>) -> Vector<T> {
+        let mut x: Vec<T> = Vec::with_capacity(self.n);
+        for y in 0..self.n {
+            let sum = (0..y).map(|i| self[(i,y)] * x[i]).fold(T::zero(), |a,b| a+b);
+            x.push((b[y] - sum) / self[(y,y)]);
+        }
+        Vector::from_vec(x)
+    }
+
+    /// Solves `self * x = b` for `x`, assuming `self` is upper triangular.
+    pub fn solve_upper_triangular(&self, b: &Vector<T>) -> Vector<T> {
+        let mut x = vec![T::zero(); self.n];
+        for y in (0..self.n).rev() {
+            let sum = (y+1..self.n).map(|i| self[(i,y)] * x[i]).fold(T::zero(), |a,b| a+b);
+            x[y] = (b[y] - sum) / self[(y,y)];
+        }
+        Vector::from_vec(x)
+    }
+
+    /// Returns the eigenvalues and eigenvectors of a symmetric matrix.
+    ///
+    /// Eigenvalues are sorted in decreasing order, and the matching
+    /// eigenvectors are the columns of the returned matrix.
+    pub fn symmetric_eigen(&self) -> (Vector<T>, Matrix<T>) {
+        if !self.is_square() {
+            panic!("Attempting to diagonalize a non-square matrix.");
+        }
+
+        // Cyclic Jacobi: rotate away each off-diagonal value until none is left.
+        let n = self.n;
+        let mut a = self.clone();
+        let mut v = Matrix::identity(n);
+        let two = T::one() + T::one();
+
+        for _ in 0..100 {
+            let off = (0..n).flat_map(|x| (0..n).filter(move |&y| y != x).map(move |y| (x,y)))
+                .map(|(x,y)| a[(x,y)] * a[(x,y)]).fold(T::zero(), |s,b| s+b);
+            if off <= T::epsilon() * T::epsilon() * a.square_norm() { break; }
+
+            for p in 0..n {
+                for q in p+1..n {
+                    if a[(p,q)] == T::zero() { continue; }
+
+                    let theta = (a[(q,q)] - a[(p,p)]) / (two * a[(p,q)]);
+                    let t = theta.signum() / (theta.abs() + (theta * theta + T::one()).sqrt());
+                    let c = (t * t + T::one()).sqrt().recip();
+                    let s = t * c;
+
+                    for k in 0..n {
+                        let (akp, akq) = (a[(k,p)], a[(k,q)]);
+                        a[(k,p)] = c * akp - s * akq;
+                        a[(k,q)] = s * akp + c * akq;
+                    }
+                    for k in 0..n {
+                        let (apk, aqk) = (a[(p,k)], a[(q,k)]);
+                        a[(p,k)] = c * apk - s * aqk;
+                        a[(q,k)] = s * apk + c * aqk;
+                    }
+                    for k in 0..n {
+                        let (vkp, vkq) = (v[(p,k)], v[(q,k)]);
+                        v[(p,k)] = c * vkp - s * vkq;
+                        v[(q,k)] = s * vkp + c * vkq;
+                    }
+                }
+            }
+        }
+
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i,&j| a[(j,j)].partial_cmp(&a[(i,i)]).unwrap());
+
+        let values = Vector::new(n, |i| a[(order[i],order[i])]);
+        let vectors = Matrix::new(n, n, |x,y| v[(order[x],y)]);
+        (values, vectors)
+    }
+
+    /// Returns the thin singular value decomposition `(U, S, V)` such that
+    /// `self == U * diag(S) * V.transpose()`.
+    ///
+    /// Singular values are sorted in decreasing order. With `k = min(m, n)`,
+    /// `U` is `m x k` and `V` is `n x k`.
+    pub fn svd(&self) -> (Matrix<T>, Vector<T>, Matrix<T>) {
+        if self.m < self.n {
+            let (u, s, v) = self.transpose().svd();
+            return (v, s, u);
+        }
+
+        // One-sided Jacobi: orthogonalize the columns, accumulating the rotations in V.
+        let n = self.n;
+        let mut a = self.clone();
+        let mut v = Matrix::identity(n);
+
+        for _ in 0..100 {
+            let mut rotated = false;
+            for p in 0..n {
+                for q in p+1..n {
+                    let (mut alpha, mut beta, mut gamma) = (T::zero(), T::zero(), T::zero());
+                    for y in 0..self.m {
+                        alpha = alpha + a[(p,y)] * a[(p,y)];
+                        beta = beta + a[(q,y)] * a[(q,y)];
+                        gamma = gamma + a[(p,y)] * a[(q,y)];
+                    }
+                    if gamma.abs() <= T::epsilon() * (alpha * beta).sqrt() { continue; }
+                    rotated = true;
+
+                    let zeta = (beta - alpha) / (gamma + gamma);
+                    let t = zeta.signum() / (zeta.abs() + (zeta * zeta + T::one()).sqrt());
+                    let c = (t * t + T::one()).sqrt().recip();
+                    let s = t * c;
+
+                    for y in 0..self.m {
+                        let (ap, aq) = (a[(p,y)], a[(q,y)]);
+                        a[(p,y)] = c * ap - s * aq;
+                        a[(q,y)] = s * ap + c * aq;
+                    }
+                    for y in 0..n {
+                        let (vp, vq) = (v[(p,y)], v[(q,y)]);
+                        v[(p,y)] = c * vp - s * vq;
+                        v[(q,y)] = s * vp + c * vq;
+                    }
+                }
+            }
+            if !rotated { break; }
+        }
+
+        let norms: Vec<T> = (0..n).map(|x| a.col(x).norm_sq().sqrt()).collect();
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i,&j| norms[j].partial_cmp(&norms[i]).unwrap());
+
+        let s = Vector::new(n, |i| norms[order[i]]);
+        let u = Matrix::new(n, self.m, |x,y| {
+            let norm = norms[order[x]];
+            if norm > T::zero() { a[(order[x],y)] / norm } else { T::zero() }
+        });
+        let v = Matrix::new(n, n, |x,y| v[(order[x],y)]);
+        (u, s, v)
+    }
 }
 
 impl <T: Clone + Mul<Output=T> + Add<Output=T> + Zero> Mul for Matrix<T> {
@@ -534,3 +694,46 @@ fn test_mul() {
 
     assert_eq!(Matrix::from_col(&(&m * &v)), &m * &Matrix::from_col(&v));
 }
+
+#[test]
+fn test_cholesky_solve() {
+    let a = Matrix::new(3,3, |x,y| if x == y { 4f64 } else { 1f64 });
+    let l = a.cholesky();
+    let product = &l * &l.transpose();
+    assert!((&product - &a).square_norm() < 1e-12);
+
+    let b = Vector::from_slice(&[1f64, 2f64, 3f64]);
+    let x = l.solve_lower_triangular(&b);
+    assert!((&(&l * &x) - &b).norm_sq() < 1e-12);
+
+    let u = l.transpose();
+    let x = u.solve_upper_triangular(&b);
+    assert!((&(&u * &x) - &b).norm_sq() < 1e-12);
+}
+
+#[test]
+fn test_symmetric_eigen() {
+    let a = Matrix::new(3,3, |x,y| [[2f64, 1f64, 0f64], [1f64, 3f64, 1f64], [0f64, 1f64, 4f64]][y][x]);
+    let (values, vectors) = a.symmetric_eigen();
+
+    assert!(values[0] >= values[1] && values[1] >= values[2]);
+    for i in 0..3 {
+        let v = vectors.col(i);
+        assert!((&(&a * &v) - &(&v * values[i])).norm_sq() < 1e-18);
+        assert!((v.norm_sq() - 1f64).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_svd() {
+    let a = Matrix::new(3,4, |x,y| ((x + 2*y) % 5) as f64 + 0.5 * x as f64);
+
+    for m in [a.clone(), a.transpose()].iter() {
+        let (u, s, v) = m.svd();
+        assert!(s[0] >= s[1] && s[1] >= s[2]);
+        assert_eq!((u.n, u.m, v.n, v.m), (3, m.m, 3, m.n));
+
+        let product = &(&u * &Matrix::diagonal(s)) * &v.transpose();
+        assert!((&product - m).square_norm() < 1e-18);
+    }
+}
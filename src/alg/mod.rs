@@ -1,6 +1,8 @@
 //! Linear algebra module
 mod vector;
 mod matrix;
+mod sparse;
 
 pub use self::vector::Vector;
 pub use self::matrix::Matrix;
+pub use self::sparse::SparseVector;
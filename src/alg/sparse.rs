@@ -0,0 +1,110 @@
+use num::Zero;
+use std::iter::Zip;
+use std::ops::{Add,Mul};
+use std::slice;
+
+use alg::Vector;
+
+/// Represents a `N`-dimensional vector storing only its non-zero values.
+#[derive(Clone,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct SparseVector<T> {
+    dim: usize,
+    // Sorted, without duplicates
+    indices: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl <T> SparseVector<T> {
+    /// Creates a new vector from its `(index, value)` entries, in any order.
+    ///
+    /// Panics if an index is out of bounds or appears twice.
+    pub fn new(dim: usize, mut entries: Vec<(usize, T)>) -> Self {
+        entries.sort_by(|a,b| a.0.cmp(&b.0));
+        for i in 0..entries.len() {
+            if entries[i].0 >= dim {
+                panic!("Sparse index out of bounds.");
+            }
+            if i > 0 && entries[i].0 == entries[i-1].0 {
+                panic!("Duplicate sparse index.");
+            }
+        }
+
+        let (indices, values) = entries.into_iter().unzip();
+        SparseVector {
+            dim: dim,
+            indices: indices,
+            values: values,
+        }
+    }
+
+    /// Returns the dimension of the vector, including zeros.
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Returns the number of stored values.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns the indices of the stored values, in increasing order.
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Returns the stored values.
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Iterates over the `(index, value)` stored entries, by increasing index.
+    pub fn iter<'a>(&'a self) -> Zip<slice::Iter<'a,usize>, slice::Iter<'a,T>> {
+        self.indices.iter().zip(self.values.iter())
+    }
+}
+
+impl <T: Zero + Clone> SparseVector<T> {
+    /// Creates a sparse vector holding the non-zero values of the given vector.
+    pub fn from_dense(v: &Vector<T>) -> Self {
+        let entries = v.data().iter().cloned().enumerate().filter(|&(_,ref x)| !x.is_zero()).collect();
+        SparseVector::new(v.dim(), entries)
+    }
+
+    /// Returns the value at the given index.
+    pub fn get(&self, i: usize) -> T {
+        match self.indices.binary_search(&i) {
+            Ok(k) => self.values[k].clone(),
+            Err(_) => T::zero(),
+        }
+    }
+
+    /// Returns the equivalent dense vector.
+    pub fn to_dense(&self) -> Vector<T> {
+        let mut data = vec![T::zero(); self.dim];
+        for (&i,x) in self.iter() {
+            data[i] = x.clone();
+        }
+        Vector::from_vec(data)
+    }
+}
+
+impl <T: Clone + Mul<Output=T> + Add<Output=T> + Zero> SparseVector<T> {
+    /// Computes the dot product with a dense vector.
+    pub fn dot(&self, other: &Vector<T>) -> T {
+        self.iter().fold(T::zero(), |a,(&i,x)| a + x.clone() * other[i].clone())
+    }
+}
+
+#[test]
+fn test_sparse_vector() {
+    let v = SparseVector::new(5, vec![(3, 2f64), (0, 1f64)]);
+    assert_eq!(v.indices(), &[0, 3]);
+    assert_eq!(v.get(3), 2f64);
+    assert_eq!(v.get(4), 0f64);
+
+    let dense = Vector::from_slice(&[1f64, 0f64, 0f64, 2f64, 0f64]);
+    assert_eq!(v.to_dense(), dense);
+    assert_eq!(SparseVector::from_dense(&dense), v);
+    assert_eq!(v.dot(&Vector::from_slice(&[1f64, 1f64, 1f64, 3f64, 1f64])), 7f64);
+}
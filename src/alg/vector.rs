@@ -6,6 +6,7 @@ use alg::Matrix;
 
 /// Represents a `N`-dimensional vector.
 #[derive(Clone,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
 pub struct Vector<T> {
     data: Vec<T>,
 }
@@ -0,0 +1,452 @@
+use std::io::{Read,BufRead,BufReader,Write};
+use std::str::FromStr;
+
+use alg::Vector;
+use ml::bayes::Value;
+use data::{Dataset,Error};
+
+/// Type of an ARFF attribute.
+#[derive(Clone,PartialEq,Debug)]
+pub enum AttributeKind {
+    /// `numeric` or `real`, read as `Value::Double`.
+    Numeric,
+    /// `integer`, read as `Value::Integer`.
+    Integer,
+    /// A set of values, read as `Value::Integer` indices into it.
+    ///
+    /// The sets `{true, false}` and `{false, true}`, in any case, are read as `Value::Boolean`.
+    Nominal(Vec<String>),
+}
+
+/// An ARFF attribute: a column of the data.
+#[derive(Clone,PartialEq,Debug)]
+pub struct Attribute {
+    pub name: String,
+    pub kind: AttributeKind,
+}
+
+/// The header of an ARFF file.
+#[derive(Clone,PartialEq,Debug)]
+pub struct ArffHeader {
+    /// Name of the relation.
+    pub relation: String,
+    /// Every attribute, class included, in file order.
+    pub attributes: Vec<Attribute>,
+    /// Index of the class attribute.
+    pub class: usize,
+}
+
+// Returns `Some(levels)` if the nominal set is a boolean, with the spelling of `false` and `true`.
+fn boolean_levels(levels: &[String]) -> Option<(&str, &str)> {
+    if levels.len() != 2 { return None; }
+    let (a, b) = (levels[0].to_lowercase(), levels[1].to_lowercase());
+    if a == "false" && b == "true" {
+        Some((&levels[0], &levels[1]))
+    } else if a == "true" && b == "false" {
+        Some((&levels[1], &levels[0]))
+    } else {
+        None
+    }
+}
+
+impl Attribute {
+    // Parses a field of this attribute. `None` if it is malformed.
+    fn parse(&self, field: &str) -> Option<Value> {
+        if field == "?" { return Some(Value::Missing); }
+
+        match self.kind {
+            AttributeKind::Numeric => f32::from_str(field).ok().map(Value::Double),
+            AttributeKind::Integer => i32::from_str(field).ok().map(Value::Integer),
+            AttributeKind::Nominal(ref levels) => {
+                levels.iter().position(|l| l == field).map(|i| match boolean_levels(levels) {
+                    Some((_, yes)) => Value::Boolean(levels[i] == yes),
+                    None => Value::Integer(i as i32),
+                })
+            },
+        }
+    }
+
+    // Value of an entry left out of a sparse row.
+    fn zero(&self) -> Value {
+        match self.kind {
+            AttributeKind::Numeric => Value::Double(0f32),
+            AttributeKind::Integer => Value::Integer(0),
+            AttributeKind::Nominal(ref levels) => match boolean_levels(levels) {
+                Some((_, yes)) => Value::Boolean(levels[0] == yes),
+                None => Value::Integer(0),
+            },
+        }
+    }
+
+    // Formats a value of this attribute. `None` if it doesn't fit.
+    fn format(&self, value: &Value) -> Option<String> {
+        match (&self.kind, *value) {
+            (_, Value::Missing) => Some("?".to_string()),
+            (&AttributeKind::Numeric, Value::Double(x)) => Some(format!("{}", x)),
+            (&AttributeKind::Numeric, Value::Integer(i)) => Some(format!("{}", i)),
+            (&AttributeKind::Integer, Value::Integer(i)) => Some(format!("{}", i)),
+            (&AttributeKind::Nominal(ref levels), Value::Integer(i)) => levels.get(i as usize).map(|l| quote(l)),
+            (&AttributeKind::Nominal(ref levels), Value::Boolean(b)) => {
+                boolean_levels(levels).map(|(no, yes)| if b { yes.to_string() } else { no.to_string() })
+            },
+            _ => None,
+        }
+    }
+}
+
+// Quotes a name or value if needed.
+fn quote(text: &str) -> String {
+    if text.is_empty() || text.chars().any(|c| " ,{}%'\"\t".contains(c)) {
+        format!("'{}'", text.replace("\\", "\\\\").replace("'", "\\'"))
+    } else {
+        text.to_string()
+    }
+}
+
+// Splits text on any of the delimiters, unquoting `'` and `"` quoted parts.
+// Consecutive delimiters are merged when splitting on whitespace.
+// Returns the fields, or the index of a field with an unterminated quote.
+fn split(text: &str, delimiters: &[char]) -> Result<Vec<String>, usize> {
+    let collapse = delimiters.contains(&' ');
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim().chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\'' || c == '"' {
+            // Whitespace before the quote is not part of the value.
+            if field.trim().is_empty() { field.clear(); }
+            quoted = true;
+            let mut closed = false;
+            while let Some(d) = chars.next() {
+                if d == '\\' {
+                    if let Some(e) = chars.next() { field.push(e); }
+                } else if d == c {
+                    closed = true;
+                    break;
+                } else {
+                    field.push(d);
+                }
+            }
+            if !closed { return Err(fields.len()); }
+        } else if delimiters.contains(&c) || (collapse && c == '\t') {
+            if collapse && field.is_empty() && !quoted { continue; }
+            fields.push(if quoted { field } else { field.trim().to_string() });
+            field = String::new();
+            quoted = false;
+        } else {
+            field.push(c);
+        }
+    }
+    if quoted || !field.trim().is_empty() || !fields.is_empty() {
+        fields.push(if quoted { field } else { field.trim().to_string() });
+    }
+    Ok(fields)
+}
+
+/// Reads ARFF files, as used by Weka, in dense or sparse form.
+///
+/// `string` and `date` attributes are not supported. In errors, columns count attributes.
+pub struct ArffReader {
+    class: Option<usize>,
+}
+
+impl ArffReader {
+    /// Creates a new reader, using the last attribute as the class.
+    pub fn new() -> Self {
+        ArffReader {
+            class: None,
+        }
+    }
+
+    /// Uses the given attribute as the class, counting from 0.
+    pub fn with_class_attribute(mut self, class: usize) -> Self {
+        self.class = Some(class);
+        self
+    }
+
+    /// Reads the header and the data, with the index of the class value as label.
+    ///
+    /// The class attribute must be nominal, and never missing.
+    pub fn read<R: Read>(&self, reader: R) -> Result<(Dataset<Vec<Vector<Value>>, usize>, ArffHeader), Error> {
+        let mut relation = String::new();
+        let mut attributes = Vec::new();
+        let mut in_data = false;
+        let mut rows: Vec<(usize, Vec<Value>)> = Vec::new();
+
+        for (i,line) in BufReader::new(reader).lines().enumerate() {
+            let line = try!(line);
+            let number = i + 1;
+            let text = line.trim();
+            if text.is_empty() || text.starts_with('%') { continue; }
+
+            if in_data {
+                let values = if text.starts_with('{') {
+                    try!(parse_sparse(text, &attributes, number))
+                } else {
+                    try!(parse_dense(text, &attributes, number))
+                };
+                rows.push((number, values));
+                continue;
+            }
+
+            let lower = text.to_lowercase();
+            if lower.starts_with("@relation") {
+                relation = match split(&text[9..], &[' ']) {
+                    Ok(ref parts) if !parts.is_empty() => parts[0].clone(),
+                    _ => return Err(Error::parse(number, 0, "invalid relation name")),
+                };
+            } else if lower.starts_with("@attribute") {
+                attributes.push(try!(parse_attribute(&text[10..], number)));
+            } else if lower.starts_with("@data") {
+                in_data = true;
+            } else {
+                return Err(Error::parse(number, 0, format!("unexpected `{}`", text)));
+            }
+        }
+
+        if attributes.is_empty() {
+            return Err(Error::parse(1, 0, "no attribute"));
+        }
+        let class = self.class.unwrap_or(attributes.len() - 1);
+        if class >= attributes.len() {
+            return Err(Error::parse(1, 0, format!("class attribute {} out of {} attributes", class, attributes.len())));
+        }
+        match attributes[class].kind {
+            AttributeKind::Nominal(_) => (),
+            _ => return Err(Error::parse(1, 0, format!("class attribute `{}` is not nominal", attributes[class].name))),
+        }
+
+        let mut samples = Vec::with_capacity(rows.len());
+        let mut labels = Vec::with_capacity(rows.len());
+        for (line, mut values) in rows {
+            let label = match values.remove(class) {
+                Value::Integer(i) => i as usize,
+                Value::Boolean(b) => {
+                    let levels = match attributes[class].kind { AttributeKind::Nominal(ref levels) => levels, _ => unreachable!() };
+                    let yes = boolean_levels(levels).unwrap().1;
+                    levels.iter().position(|l| (l == yes) == b).unwrap()
+                },
+                _ => return Err(Error::parse(line, class + 1, "missing class")),
+            };
+            samples.push(Vector::from_vec(values));
+            labels.push(label);
+        }
+
+        let names = attributes.iter().enumerate().filter(|&(j,_)| j != class).map(|(_,a)| a.name.clone()).collect();
+        let header = ArffHeader {
+            relation: relation,
+            attributes: attributes,
+            class: class,
+        };
+        Ok((Dataset::new(samples, labels).with_names(names), header))
+    }
+}
+
+fn parse_attribute(text: &str, line: usize) -> Result<Attribute, Error> {
+    let text = text.trim();
+    // The name may be quoted and contain spaces.
+    let (name, rest) = if text.starts_with('\'') || text.starts_with('"') {
+        match text[1..].find(text.chars().next().unwrap()) {
+            Some(end) => (text[1..end+1].to_string(), text[end+2..].trim()),
+            None => return Err(Error::parse(line, 0, "unterminated attribute name")),
+        }
+    } else {
+        match text.find(|c: char| c.is_whitespace()) {
+            Some(end) => (text[..end].to_string(), text[end..].trim()),
+            None => return Err(Error::parse(line, 0, "missing attribute type")),
+        }
+    };
+
+    let kind = if rest.starts_with('{') && rest.ends_with('}') {
+        match split(&rest[1..rest.len()-1], &[',']) {
+            Ok(levels) => AttributeKind::Nominal(levels),
+            Err(_) => return Err(Error::parse(line, 0, "unterminated nominal value")),
+        }
+    } else {
+        match &rest.to_lowercase()[..] {
+            "numeric" | "real" => AttributeKind::Numeric,
+            "integer" => AttributeKind::Integer,
+            other => return Err(Error::parse(line, 0, format!("unsupported attribute type `{}`", other))),
+        }
+    };
+
+    Ok(Attribute {
+        name: name,
+        kind: kind,
+    })
+}
+
+fn parse_dense(text: &str, attributes: &[Attribute], line: usize) -> Result<Vec<Value>, Error> {
+    let fields = match split(text, &[',']) {
+        Ok(fields) => fields,
+        Err(j) => return Err(Error::parse(line, j + 1, "unterminated quote")),
+    };
+    if fields.len() != attributes.len() {
+        return Err(Error::parse(line, 0, format!("expected {} values, found {}", attributes.len(), fields.len())));
+    }
+
+    let mut values = Vec::with_capacity(fields.len());
+    for (j,(field,attribute)) in fields.iter().zip(attributes.iter()).enumerate() {
+        match attribute.parse(field) {
+            Some(value) => values.push(value),
+            None => return Err(Error::parse(line, j + 1, format!("invalid value `{}` for `{}`", field, attribute.name))),
+        }
+    }
+    Ok(values)
+}
+
+fn parse_sparse(text: &str, attributes: &[Attribute], line: usize) -> Result<Vec<Value>, Error> {
+    if !text.ends_with('}') {
+        return Err(Error::parse(line, 0, "unterminated sparse row"));
+    }
+
+    let mut values: Vec<Value> = attributes.iter().map(|a| a.zero()).collect();
+    let tokens = match split(&text[1..text.len()-1], &[',', ' ']) {
+        Ok(tokens) => tokens,
+        Err(_) => return Err(Error::parse(line, 0, "unterminated quote")),
+    };
+    if tokens.len() % 2 != 0 {
+        return Err(Error::parse(line, 0, "expected `index value` pairs"));
+    }
+    for pair in tokens.chunks(2) {
+        let j = match usize::from_str(&pair[0]) {
+            Ok(j) if j < attributes.len() => j,
+            _ => return Err(Error::parse(line, 0, format!("invalid attribute index `{}`", pair[0]))),
+        };
+        values[j] = match attributes[j].parse(&pair[1]) {
+            Some(value) => value,
+            None => return Err(Error::parse(line, j + 1, format!("invalid value `{}` for `{}`", pair[1], attributes[j].name))),
+        };
+    }
+    Ok(values)
+}
+
+/// Writes ARFF files.
+pub struct ArffWriter {
+    sparse: bool,
+}
+
+impl ArffWriter {
+    /// Creates a new writer for dense data.
+    pub fn new() -> Self {
+        ArffWriter {
+            sparse: false,
+        }
+    }
+
+    /// Writes the data in sparse form, leaving out zeros and first nominal values.
+    pub fn with_sparse(mut self) -> Self {
+        self.sparse = true;
+        self
+    }
+
+    /// Writes the header, then each sample with its label inserted as the class attribute.
+    ///
+    /// Panics if a value doesn't fit its attribute.
+    pub fn write<W: Write>(&self, writer: &mut W, header: &ArffHeader, dataset: &Dataset<Vec<Vector<Value>>, usize>) -> Result<(), Error> {
+        try!(writeln!(writer, "@relation {}", quote(&header.relation)));
+        try!(writeln!(writer, ""));
+        for attribute in header.attributes.iter() {
+            let kind = match attribute.kind {
+                AttributeKind::Numeric => "numeric".to_string(),
+                AttributeKind::Integer => "integer".to_string(),
+                AttributeKind::Nominal(ref levels) => {
+                    format!("{{{}}}", levels.iter().map(|l| quote(l)).collect::<Vec<String>>().join(","))
+                },
+            };
+            try!(writeln!(writer, "@attribute {} {}", quote(&attribute.name), kind));
+        }
+        try!(writeln!(writer, ""));
+        try!(writeln!(writer, "@data"));
+
+        for (x,&y) in dataset.samples().iter().zip(dataset.labels().iter()) {
+            let mut values: Vec<Value> = x.data().to_vec();
+            values.insert(header.class, Value::Integer(y as i32));
+
+            let fields: Vec<String> = values.iter().zip(header.attributes.iter()).enumerate()
+                .filter(|&(_,(v,a))| !self.sparse || *v != a.zero())
+                .map(|(j,(v,a))| {
+                    let field = match a.format(v) {
+                        Some(field) => field,
+                        None => panic!("Value {:?} doesn't fit attribute `{}`.", v, a.name),
+                    };
+                    if self.sparse { format!("{} {}", j, field) } else { field }
+                }).collect();
+
+            if self.sparse {
+                try!(writeln!(writer, "{{{}}}", fields.join(",")));
+            } else {
+                try!(writeln!(writer, "{}", fields.join(",")));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+const WEATHER: &'static str = "% Weather data\n\
+    @relation weather\n\
+    \n\
+    @attribute outlook {sunny, overcast, 'light rain'}\n\
+    @attribute temperature real\n\
+    @attribute windy {TRUE, FALSE}\n\
+    @attribute visits integer\n\
+    @attribute play {no, yes}\n\
+    \n\
+    @data\n\
+    sunny,85,FALSE,3,no\n\
+    'light rain',?,TRUE,0,no\n\
+    {1 64.5, 4 yes}\n";
+
+#[test]
+fn test_arff_read() {
+    use ml::Classifier;
+    use ml::bayes::NaiveBayes;
+
+    let (data, header) = ArffReader::new().read(WEATHER.as_bytes()).unwrap();
+    assert_eq!(header.relation, "weather");
+    assert_eq!(header.class, 4);
+    assert_eq!(header.attributes[0].kind, AttributeKind::Nominal(vec!["sunny".to_string(), "overcast".to_string(), "light rain".to_string()]));
+    assert_eq!(data.names().unwrap()[3], "visits");
+
+    assert_eq!(data.labels(), &[0, 0, 1]);
+    assert_eq!(data.sample(0), Vector::from_vec(vec![Value::Integer(0), Value::Double(85f32), Value::Boolean(false), Value::Integer(3)]));
+    assert_eq!(data.sample(1)[1], Value::Missing);
+    // Left out sparse values are zero, or the first nominal value.
+    assert_eq!(data.sample(2), Vector::from_vec(vec![Value::Integer(0), Value::Double(64.5f32), Value::Boolean(true), Value::Integer(0)]));
+
+    let mut bayes = NaiveBayes::new(2);
+    data.train(&mut bayes);
+    assert_eq!(bayes.classify(&data.sample(2)), 1);
+}
+
+#[test]
+fn test_arff_errors() {
+    let text = "@relation r\n@attribute a numeric\n@attribute c {x,y}\n@data\n1,x\n2,z\n";
+    match ArffReader::new().read(text.as_bytes()) {
+        Err(Error::Parse { line: 6, column: 2, .. }) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+
+    let text = "@relation r\n@attribute a string\n";
+    match ArffReader::new().read(text.as_bytes()) {
+        Err(Error::Parse { line: 2, .. }) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_arff_write() {
+    let (data, header) = ArffReader::new().read(WEATHER.as_bytes()).unwrap();
+
+    for writer in [ArffWriter::new(), ArffWriter::new().with_sparse()].iter() {
+        let mut output = Vec::new();
+        writer.write(&mut output, &header, &data).unwrap();
+
+        let (again, header_again) = ArffReader::new().read(&output[..]).unwrap();
+        assert_eq!(header_again, header);
+        assert_eq!(again, data);
+    }
+}
@@ -0,0 +1,371 @@
+use std::io::{Read,BufRead,BufReader};
+use std::str::FromStr;
+
+use alg::{Vector,Matrix};
+use ml::bayes::Value;
+use data::{Dataset,Error};
+
+/// Type inferred for a CSV column by `CsvReader::read_values`.
+#[derive(Clone,PartialEq,Debug)]
+pub enum ColumnType {
+    /// `true` or `false` in any case, read as `Value::Boolean`.
+    Boolean,
+    /// Integers, read as `Value::Integer`.
+    Integer,
+    /// Numbers, read as `Value::Double`.
+    Double,
+    /// Any other text, read as `Value::Integer` indices into these levels, in order of appearance.
+    Categorical(Vec<String>),
+}
+
+#[derive(Clone,Copy,PartialEq,Debug)]
+enum Label {
+    Last,
+    Column(usize),
+    Absent,
+}
+
+/// Reads delimited text files into a `Dataset`.
+///
+/// Fields may be quoted, with doubled quotes standing for a quote, but may not span several lines.
+/// Empty lines are skipped.
+pub struct CsvReader {
+    delimiter: char,
+    quote: char,
+    comment: Option<char>,
+    header: Option<bool>,
+    label: Label,
+    missing: Vec<String>,
+}
+
+impl CsvReader {
+    /// Creates a new reader for comma-separated values.
+    ///
+    /// By default, lines starting with `#` are skipped, the header is detected, the last column
+    /// holds the labels, and empty fields, `NA` and `?` are missing values.
+    pub fn new() -> Self {
+        CsvReader {
+            delimiter: ',',
+            quote: '"',
+            comment: Some('#'),
+            header: None,
+            label: Label::Last,
+            missing: vec!["".to_string(), "NA".to_string(), "?".to_string()],
+        }
+    }
+
+    /// Sets the field delimiter.
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets the quote character.
+    pub fn with_quote(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Sets the character starting comment lines, or disables comments with `None`.
+    pub fn with_comment(mut self, comment: Option<char>) -> Self {
+        self.comment = comment;
+        self
+    }
+
+    /// Sets whether the first line is a header, instead of detecting it.
+    ///
+    /// A header is detected when a field of the first line isn't a number or boolean
+    /// while the same field of the second line is.
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    /// Reads the labels from the given column, counting from 0.
+    pub fn with_label_column(mut self, column: usize) -> Self {
+        self.label = Label::Column(column);
+        self
+    }
+
+    /// Reads every column as a feature. Labels are then all missing.
+    pub fn without_label(mut self) -> Self {
+        self.label = Label::Absent;
+        self
+    }
+
+    /// Sets the fields standing for missing values. Surrounding whitespace is ignored.
+    pub fn with_missing_tokens(mut self, tokens: &[&str]) -> Self {
+        self.missing = tokens.iter().map(|t| t.to_string()).collect();
+        self
+    }
+
+    /// Reads mixed data, inferring the type of each column.
+    ///
+    /// Returns the dataset, with column names from the header if any,
+    /// and the type of each column of the file, label included.
+    pub fn read_values<R: Read>(&self, reader: R) -> Result<(Dataset<Vec<Vector<Value>>, Value>, Vec<ColumnType>), Error> {
+        let (header, rows) = try!(self.read_records(reader));
+        let n_fields = rows.first().map_or(header.as_ref().map_or(0, |h| h.len()), |&(_, ref r)| r.len());
+        let label = try!(self.label_column(n_fields));
+
+        let types: Vec<ColumnType> = (0..n_fields).map(|j| {
+            self.infer(rows.iter().map(|&(_, ref r)| &r[j][..]))
+        }).collect();
+
+        let mut samples = Vec::with_capacity(rows.len());
+        let mut labels = Vec::with_capacity(rows.len());
+        for &(_, ref row) in rows.iter() {
+            let values: Vec<Value> = row.iter().zip(types.iter()).map(|(field,t)| self.value(field, t)).collect();
+            labels.push(label.map_or(Value::Missing, |l| values[l]));
+            samples.push(Vector::from_vec(values.into_iter().enumerate()
+                .filter(|&(j,_)| Some(j) != label)
+                .map(|(_,v)| v)
+                .collect()));
+        }
+
+        let dataset = Dataset::new(samples, labels);
+        Ok((self.name(dataset, header, label), types))
+    }
+
+    /// Reads numeric data, with missing values as NaN. Labels are NaN without a label column.
+    ///
+    /// Fails on fields that are neither numbers nor missing.
+    pub fn read_matrix<R: Read>(&self, reader: R) -> Result<Dataset<Matrix<f64>, f64>, Error> {
+        let (header, rows) = try!(self.read_records(reader));
+        let n_fields = rows.first().map_or(header.as_ref().map_or(0, |h| h.len()), |&(_, ref r)| r.len());
+        let label = try!(self.label_column(n_fields));
+
+        let mut samples = Vec::with_capacity(rows.len());
+        let mut labels = Vec::with_capacity(rows.len());
+        for &(line, ref row) in rows.iter() {
+            let mut values = Vec::with_capacity(n_fields);
+            for (j,field) in row.iter().enumerate() {
+                let value = if self.is_missing(field) {
+                    ::std::f64::NAN
+                } else {
+                    match f64::from_str(field.trim()) {
+                        Ok(value) => value,
+                        Err(_) => return Err(Error::parse(line, j + 1, format!("expected a number, found `{}`", field))),
+                    }
+                };
+                if Some(j) == label {
+                    labels.push(value);
+                } else {
+                    values.push(value);
+                }
+            }
+            if label.is_none() { labels.push(::std::f64::NAN); }
+            samples.push(Vector::from_vec(values));
+        }
+
+        let dataset = Dataset::from_rows(&samples, labels);
+        Ok(self.name(dataset, header, label))
+    }
+
+    // Splits the lines into fields, with their line number, and separates the header.
+    fn read_records<R: Read>(&self, reader: R) -> Result<(Option<Vec<String>>, Vec<(usize, Vec<String>)>), Error> {
+        let mut rows: Vec<(usize, Vec<String>)> = Vec::new();
+        for (i,line) in BufReader::new(reader).lines().enumerate() {
+            let line = try!(line);
+            let trimmed = line.trim();
+            if trimmed.is_empty() { continue; }
+            if let Some(c) = self.comment {
+                if trimmed.starts_with(c) { continue; }
+            }
+
+            let fields = try!(self.split(&line, i + 1));
+            if let Some(&(_, ref first)) = rows.first() {
+                if fields.len() != first.len() {
+                    return Err(Error::parse(i + 1, 0, format!("expected {} fields, found {}", first.len(), fields.len())));
+                }
+            }
+            rows.push((i + 1, fields));
+        }
+
+        let header = match self.header {
+            Some(header) => header,
+            None => rows.len() >= 2 && (0..rows[0].1.len()).any(|j| {
+                let (first, second) = (&rows[0].1[j], &rows[1].1[j]);
+                !self.is_missing(first) && !is_typed(first) && is_typed(second)
+            }),
+        };
+
+        if header && !rows.is_empty() {
+            let names = rows.remove(0).1.into_iter().map(|name| name.trim().to_string()).collect();
+            Ok((Some(names), rows))
+        } else {
+            Ok((None, rows))
+        }
+    }
+
+    // Splits a line into fields, unquoting them.
+    fn split(&self, line: &str, line_number: usize) -> Result<Vec<String>, Error> {
+        let mut fields = Vec::new();
+        let mut chars = line.chars().peekable();
+
+        loop {
+            let column = fields.len() + 1;
+            while chars.peek() == Some(&' ') || chars.peek() == Some(&'\t') {
+                chars.next();
+            }
+
+            let mut field = String::new();
+            if chars.peek() == Some(&self.quote) {
+                chars.next();
+                loop {
+                    match chars.next() {
+                        None => return Err(Error::parse(line_number, column, "unterminated quoted field")),
+                        Some(c) if c == self.quote => {
+                            if chars.peek() == Some(&self.quote) {
+                                chars.next();
+                                field.push(c);
+                            } else {
+                                break;
+                            }
+                        },
+                        Some(c) => field.push(c),
+                    }
+                }
+                while chars.peek() == Some(&' ') || chars.peek() == Some(&'\t') {
+                    chars.next();
+                }
+                match chars.next() {
+                    None => {
+                        fields.push(field);
+                        return Ok(fields);
+                    },
+                    Some(c) if c == self.delimiter => fields.push(field),
+                    Some(c) => return Err(Error::parse(line_number, column, format!("unexpected `{}` after quoted field", c))),
+                }
+            } else {
+                loop {
+                    match chars.next() {
+                        None => {
+                            fields.push(field.trim().to_string());
+                            return Ok(fields);
+                        },
+                        Some(c) if c == self.delimiter => break,
+                        Some(c) => field.push(c),
+                    }
+                }
+                fields.push(field.trim().to_string());
+            }
+        }
+    }
+
+    fn label_column(&self, n_fields: usize) -> Result<Option<usize>, Error> {
+        match self.label {
+            Label::Absent => Ok(None),
+            Label::Last if n_fields == 0 => Ok(None),
+            Label::Last => Ok(Some(n_fields - 1)),
+            Label::Column(j) if j < n_fields => Ok(Some(j)),
+            Label::Column(j) => Err(Error::parse(1, 0, format!("label column {} out of {} columns", j, n_fields))),
+        }
+    }
+
+    fn is_missing(&self, field: &str) -> bool {
+        let field = field.trim();
+        self.missing.iter().any(|m| m == field)
+    }
+
+    fn infer<'a, I: Iterator<Item=&'a str>>(&self, fields: I) -> ColumnType {
+        let fields: Vec<&str> = fields.filter(|f| !self.is_missing(f)).collect();
+
+        if fields.iter().all(|f| parse_bool(f).is_some()) {
+            ColumnType::Boolean
+        } else if fields.iter().all(|f| i32::from_str(f).is_ok()) {
+            ColumnType::Integer
+        } else if fields.iter().all(|f| f32::from_str(f).is_ok()) {
+            ColumnType::Double
+        } else {
+            let mut levels: Vec<String> = Vec::new();
+            for f in fields {
+                if !levels.iter().any(|l| l == f) { levels.push(f.to_string()); }
+            }
+            ColumnType::Categorical(levels)
+        }
+    }
+
+    // Converts a field to a value of its column type, already checked by `infer`.
+    fn value(&self, field: &str, column_type: &ColumnType) -> Value {
+        if self.is_missing(field) { return Value::Missing; }
+
+        match *column_type {
+            ColumnType::Boolean => Value::Boolean(parse_bool(field).unwrap()),
+            ColumnType::Integer => Value::Integer(i32::from_str(field).unwrap()),
+            ColumnType::Double => Value::Double(f32::from_str(field).unwrap()),
+            ColumnType::Categorical(ref levels) => Value::Integer(levels.iter().position(|l| l == field).unwrap() as i32),
+        }
+    }
+
+    fn name<X, Y>(&self, dataset: Dataset<X,Y>, header: Option<Vec<String>>, label: Option<usize>) -> Dataset<X,Y>
+        where X: ::data::Samples, Y: Clone
+    {
+        match header {
+            None => dataset,
+            Some(header) => {
+                let names = header.into_iter().enumerate().filter(|&(j,_)| Some(j) != label).map(|(_,n)| n).collect();
+                dataset.with_names(names)
+            },
+        }
+    }
+}
+
+fn parse_bool(field: &str) -> Option<bool> {
+    bool::from_str(&field.to_lowercase()).ok()
+}
+
+// Whether the field is a number or a boolean.
+fn is_typed(field: &str) -> bool {
+    f64::from_str(field.trim()).is_ok() || parse_bool(field.trim()).is_some()
+}
+
+#[test]
+fn test_csv_values() {
+    let text = "# A comment\n\
+                name,count,ratio,flag,class\n\
+                \"Smith, J.\",3,0.5,true,cat\n\
+                \"say \"\"hi\"\"\",NA,1,FALSE,dog\n\
+                \n\
+                Doe,7,?,true,cat\n";
+
+    let (data, types) = CsvReader::new().read_values(text.as_bytes()).unwrap();
+    assert_eq!(data.len(), 3);
+    assert_eq!(data.names().unwrap(), &["name".to_string(), "count".to_string(), "ratio".to_string(), "flag".to_string()]);
+    assert_eq!(types[0], ColumnType::Categorical(vec!["Smith, J.".to_string(), "say \"hi\"".to_string(), "Doe".to_string()]));
+    assert_eq!(&types[1..4], &[ColumnType::Integer, ColumnType::Double, ColumnType::Boolean]);
+    assert_eq!(types[4], ColumnType::Categorical(vec!["cat".to_string(), "dog".to_string()]));
+
+    assert_eq!(data.sample(1), Vector::from_vec(vec![Value::Integer(1), Value::Missing, Value::Double(1f32), Value::Boolean(false)]));
+    assert_eq!(data.sample(2)[2], Value::Missing);
+    assert_eq!(data.labels(), &[Value::Integer(0), Value::Integer(1), Value::Integer(0)]);
+}
+
+#[test]
+fn test_csv_matrix() {
+    let text = "1;10;0.5\n2;NA;1.5\n";
+
+    let data = CsvReader::new().with_delimiter(';').with_label_column(0).read_matrix(text.as_bytes()).unwrap();
+    assert!(data.names().is_none());
+    assert_eq!(data.labels(), &[1f64, 2f64]);
+    assert_eq!(data.sample(0), Vector::from_slice(&[10f64, 0.5f64]));
+    assert!(data.sample(1)[0].is_nan());
+}
+
+#[test]
+fn test_csv_errors() {
+    let reader = CsvReader::new();
+
+    match reader.read_matrix("1,2\n3,x\n".as_bytes()) {
+        Err(Error::Parse { line: 2, column: 2, .. }) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    match reader.read_values("1,2\n3,4,5\n".as_bytes()) {
+        Err(Error::Parse { line: 2, column: 0, .. }) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    match reader.read_values("1,\"2\n".as_bytes()) {
+        Err(Error::Parse { line: 1, column: 2, .. }) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+}
@@ -0,0 +1,294 @@
+use rand::Rng;
+
+use alg::{Vector,Matrix};
+use ml::Classifier;
+
+/// A collection of samples, as stored in a `Dataset`.
+///
+/// Implemented for `Vec` of any sample type, and for dense `Matrix` data with one sample per row.
+pub trait Samples: Sized {
+    /// Type of a single sample.
+    type Item;
+
+    /// Returns the number of samples.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if there is no sample.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a copy of the `i`-th sample.
+    fn sample(&self, i: usize) -> Self::Item;
+
+    /// Returns the samples at the given indices, in order.
+    fn select(&self, indices: &[usize]) -> Self;
+}
+
+impl <X: Clone> Samples for Vec<X> {
+    type Item = X;
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn sample(&self, i: usize) -> X {
+        self[i].clone()
+    }
+
+    fn select(&self, indices: &[usize]) -> Vec<X> {
+        indices.iter().map(|&i| self[i].clone()).collect()
+    }
+}
+
+impl <T: Clone> Samples for Matrix<T> {
+    type Item = Vector<T>;
+
+    fn len(&self) -> usize {
+        self.m
+    }
+
+    fn sample(&self, i: usize) -> Vector<T> {
+        self.row(i)
+    }
+
+    fn select(&self, indices: &[usize]) -> Matrix<T> {
+        Matrix::new(self.n, indices.len(), |x,y| self[(x,indices[y])].clone())
+    }
+}
+
+/// Samples with their labels, and optionally per-sample weights and feature names.
+#[derive(Clone,PartialEq,Debug)]
+pub struct Dataset<X, Y> {
+    samples: X,
+    labels: Vec<Y>,
+    weights: Option<Vec<f64>>,
+    names: Option<Vec<String>>,
+}
+
+impl <X: Samples, Y: Clone> Dataset<X,Y> {
+    /// Creates a new dataset.
+    ///
+    /// Panics if there isn't one label per sample.
+    pub fn new(samples: X, labels: Vec<Y>) -> Self {
+        if samples.len() != labels.len() {
+            panic!("Dataset needs one label per sample.");
+        }
+
+        Dataset {
+            samples: samples,
+            labels: labels,
+            weights: None,
+            names: None,
+        }
+    }
+
+    /// Sets the weight of each sample.
+    ///
+    /// Panics if there isn't one weight per sample.
+    pub fn with_weights(mut self, weights: Vec<f64>) -> Self {
+        if weights.len() != self.len() {
+            panic!("Dataset needs one weight per sample.");
+        }
+        self.weights = Some(weights);
+        self
+    }
+
+    /// Sets the name of each feature.
+    pub fn with_names(mut self, names: Vec<String>) -> Self {
+        self.names = Some(names);
+        self
+    }
+
+    /// Returns the number of samples.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Returns `true` if there is no sample.
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Returns the samples.
+    pub fn samples(&self) -> &X {
+        &self.samples
+    }
+
+    /// Returns a copy of the `i`-th sample.
+    pub fn sample(&self, i: usize) -> X::Item {
+        self.samples.sample(i)
+    }
+
+    /// Returns a copy of every sample, as expected by `Classifier::train`.
+    pub fn inputs(&self) -> Vec<X::Item> {
+        (0..self.len()).map(|i| self.samples.sample(i)).collect()
+    }
+
+    /// Returns the labels.
+    pub fn labels(&self) -> &[Y] {
+        &self.labels
+    }
+
+    /// Returns the weight of each sample, if any were given.
+    pub fn weights(&self) -> Option<&[f64]> {
+        self.weights.as_ref().map(|w| &w[..])
+    }
+
+    /// Returns the name of each feature, if any were given.
+    pub fn names(&self) -> Option<&[String]> {
+        self.names.as_ref().map(|n| &n[..])
+    }
+
+    /// Trains the classifier on the whole dataset.
+    pub fn train<C>(&self, classifier: &mut C)
+        where C: Classifier<Input=X::Item, Label=Y>
+    {
+        classifier.train(&self.inputs(), &self.labels);
+    }
+
+    /// Returns the samples at the given indices, in order. Indices may repeat.
+    pub fn subset(&self, indices: &[usize]) -> Self {
+        Dataset {
+            samples: self.samples.select(indices),
+            labels: indices.iter().map(|&i| self.labels[i].clone()).collect(),
+            weights: self.weights.as_ref().map(|w| indices.iter().map(|&i| w[i]).collect()),
+            names: self.names.clone(),
+        }
+    }
+
+    /// Shuffles the samples in place.
+    pub fn shuffle<R: Rng>(&mut self, rng: &mut R) {
+        let mut indices: Vec<usize> = (0..self.len()).collect();
+        rng.shuffle(&mut indices);
+        *self = self.subset(&indices);
+    }
+
+    /// Splits the dataset in two: the first `fraction` of the samples, and the rest.
+    ///
+    /// Shuffle first for a random split.
+    pub fn split(&self, fraction: f64) -> (Self, Self) {
+        let n = ((self.len() as f64 * fraction).round() as usize).min(self.len());
+        let indices: Vec<usize> = (0..self.len()).collect();
+        let (first, second) = indices.split_at(n);
+        (self.subset(first), self.subset(second))
+    }
+
+    /// Randomly splits the dataset in two, keeping about `fraction` of each label in the first part.
+    ///
+    /// Samples keep their order within each part.
+    pub fn stratified_split<R: Rng>(&self, fraction: f64, rng: &mut R) -> (Self, Self)
+        where Y: PartialEq
+    {
+        // Indices of the samples of each distinct label
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for i in 0..self.len() {
+            match groups.iter().position(|g| self.labels[g[0]] == self.labels[i]) {
+                Some(g) => groups[g].push(i),
+                None => groups.push(vec![i]),
+            }
+        }
+
+        let mut first = Vec::new();
+        let mut second = Vec::new();
+        for mut group in groups {
+            rng.shuffle(&mut group);
+            let n = ((group.len() as f64 * fraction).round() as usize).min(group.len());
+            first.extend_from_slice(&group[..n]);
+            second.extend_from_slice(&group[n..]);
+        }
+        first.sort();
+        second.sort();
+
+        (self.subset(&first), self.subset(&second))
+    }
+
+    /// Iterates over consecutive batches of `size` samples. The last one may be smaller.
+    pub fn batches<'a>(&'a self, size: usize) -> Batches<'a,X,Y> {
+        Batches {
+            dataset: self,
+            size: size.max(1),
+            position: 0,
+        }
+    }
+}
+
+impl <T: Clone, Y: Clone> Dataset<Matrix<T>,Y> {
+    /// Creates a new dataset from dense data, with one sample per row.
+    pub fn from_rows(rows: &[Vector<T>], labels: Vec<Y>) -> Self {
+        Dataset::new(Matrix::from_rows(rows), labels)
+    }
+}
+
+/// Iterator over the mini-batches of a `Dataset`.
+pub struct Batches<'a, X: 'a, Y: 'a> {
+    dataset: &'a Dataset<X,Y>,
+    size: usize,
+    position: usize,
+}
+
+impl <'a, X: Samples, Y: Clone> Iterator for Batches<'a,X,Y> {
+    type Item = Dataset<X,Y>;
+
+    fn next(&mut self) -> Option<Dataset<X,Y>> {
+        if self.position >= self.dataset.len() { return None; }
+
+        let end = (self.position + self.size).min(self.dataset.len());
+        let indices: Vec<usize> = (self.position..end).collect();
+        self.position = end;
+        Some(self.dataset.subset(&indices))
+    }
+}
+
+#[cfg(test)]
+fn dataset() -> Dataset<Matrix<f64>, usize> {
+    let rows: Vec<Vector<f64>> = (0..10).map(|i| Vector::from_slice(&[i as f64, -(i as f64)])).collect();
+    let labels = (0..10).map(|i| if i < 6 { 0 } else { 1 }).collect();
+    Dataset::from_rows(&rows, labels)
+        .with_weights((0..10).map(|i| i as f64).collect())
+        .with_names(vec!["x".to_string(), "y".to_string()])
+}
+
+#[test]
+fn test_dataset() {
+    let data = dataset();
+    assert_eq!(data.len(), 10);
+    assert_eq!(data.sample(3), Vector::from_slice(&[3f64, -3f64]));
+
+    let subset = data.subset(&[7, 2, 2]);
+    assert_eq!(subset.samples().col(0), Vector::from_slice(&[7f64, 2f64, 2f64]));
+    assert_eq!(subset.labels(), &[1, 0, 0]);
+    assert_eq!(subset.weights(), Some(&[7f64, 2f64, 2f64][..]));
+    assert_eq!(subset.names().unwrap()[1], "y");
+
+    let (train, test) = data.split(0.8);
+    assert_eq!((train.len(), test.len()), (8, 2));
+    assert_eq!(test.sample(0)[0], 8f64);
+
+    let sizes: Vec<usize> = data.batches(4).map(|b| b.len()).collect();
+    assert_eq!(sizes, vec![4, 4, 2]);
+}
+
+#[test]
+fn test_dataset_shuffle() {
+    use rand::{StdRng,SeedableRng};
+
+    let mut rng: StdRng = SeedableRng::from_seed(&[42usize][..]);
+    let mut data = dataset();
+    data.shuffle(&mut rng);
+
+    // Samples move with their labels and weights.
+    let mut seen: Vec<usize> = (0..10).map(|i| {
+        let x = data.sample(i)[0];
+        assert_eq!(data.labels()[i], if x < 6f64 { 0 } else { 1 });
+        assert_eq!(data.weights().unwrap()[i], x);
+        x as usize
+    }).collect();
+    seen.sort();
+    assert_eq!(seen, (0..10).collect::<Vec<usize>>());
+
+    let (train, test) = dataset().stratified_split(0.5, &mut rng);
+    assert_eq!(train.labels().iter().filter(|&&y| y == 0).count(), 3);
+    assert_eq!(train.labels().iter().filter(|&&y| y == 1).count(), 2);
+    assert_eq!(test.len(), 5);
+}
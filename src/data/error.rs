@@ -0,0 +1,48 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+/// Error returned when reading or writing data files.
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying reader or writer failed.
+    Io(io::Error),
+    /// The content is malformed. Lines and columns start at 1; column 0 means the whole line.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// Binary content is malformed or unsupported.
+    Format(String),
+}
+
+impl Error {
+    /// Creates a new parse error.
+    pub fn parse<S: Into<String>>(line: usize, column: usize, message: S) -> Self {
+        Error::Parse {
+            line: line,
+            column: column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "{}", err),
+            Error::Parse { line, column: 0, ref message } => write!(f, "line {}: {}", line, message),
+            Error::Parse { line, column, ref message } => write!(f, "line {}, column {}: {}", line, column, message),
+            Error::Format(ref message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
@@ -0,0 +1,252 @@
+use std::fmt::Display;
+use std::io::{Read,BufRead,BufReader,Write};
+use std::str::FromStr;
+
+use num::Zero;
+
+use alg::{Vector,SparseVector};
+use data::{Dataset,Error};
+
+/// How feature indices are numbered in a LIBSVM file.
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Indexing {
+    /// The first feature is `0`.
+    ZeroBased,
+    /// The first feature is `1`, as written by LIBSVM itself.
+    OneBased,
+    /// Zero-based if any index is `0`, one-based otherwise.
+    Auto,
+}
+
+/// Reads sparse data in the LIBSVM/SVMlight format: `label [qid:n] index:value ...` on each line.
+///
+/// Anything after a `#` is a comment. In errors, columns count whitespace-separated tokens.
+pub struct LibsvmReader {
+    indexing: Indexing,
+    n_features: Option<usize>,
+}
+
+// A parsed line: line number, label, query id, and (column, index, value) entries.
+struct Line {
+    line: usize,
+    label: f64,
+    qid: Option<usize>,
+    entries: Vec<(usize, usize, f64)>,
+}
+
+impl LibsvmReader {
+    /// Creates a new reader, detecting the indexing and the number of features.
+    pub fn new() -> Self {
+        LibsvmReader {
+            indexing: Indexing::Auto,
+            n_features: None,
+        }
+    }
+
+    /// Sets how indices are numbered.
+    pub fn with_indexing(mut self, indexing: Indexing) -> Self {
+        self.indexing = indexing;
+        self
+    }
+
+    /// Sets the number of features, instead of using the largest index.
+    pub fn with_n_features(mut self, n_features: usize) -> Self {
+        self.n_features = Some(n_features);
+        self
+    }
+
+    /// Reads the samples and their labels, with the query ids if the file has any.
+    pub fn read<R: Read>(&self, reader: R) -> Result<(Dataset<Vec<SparseVector<f64>>, f64>, Option<Vec<usize>>), Error> {
+        let mut lines = Vec::new();
+        for (i,text) in BufReader::new(reader).lines().enumerate() {
+            let text = try!(text);
+            let content = text.split('#').next().unwrap().trim();
+            if content.is_empty() { continue; }
+
+            let line = try!(parse_line(content, i + 1));
+            if let Some(first) = lines.first().map(|l: &Line| l.qid.is_some()) {
+                if line.qid.is_some() != first {
+                    return Err(Error::parse(line.line, 2, "query ids must be given on every line or none"));
+                }
+            }
+            lines.push(line);
+        }
+
+        let zero_based = match self.indexing {
+            Indexing::ZeroBased => true,
+            Indexing::OneBased => false,
+            Indexing::Auto => lines.iter().any(|l| l.entries.iter().any(|&(_,index,_)| index == 0)),
+        };
+        let offset = if zero_based { 0 } else { 1 };
+
+        let largest = lines.iter().flat_map(|l| l.entries.iter()).map(|&(_,index,_)| index + 1 - offset).max().unwrap_or(0);
+        let dim = self.n_features.unwrap_or(largest);
+
+        let mut samples = Vec::with_capacity(lines.len());
+        for line in lines.iter() {
+            let mut entries = Vec::with_capacity(line.entries.len());
+            for &(column, index, value) in line.entries.iter() {
+                if index < offset {
+                    return Err(Error::parse(line.line, column, "index 0 in one-based data"));
+                }
+                if index - offset >= dim {
+                    return Err(Error::parse(line.line, column, format!("index {} out of {} features", index, dim)));
+                }
+                if entries.iter().any(|&(i,_)| i == index - offset) {
+                    return Err(Error::parse(line.line, column, format!("duplicate index {}", index)));
+                }
+                entries.push((index - offset, value));
+            }
+            samples.push(SparseVector::new(dim, entries));
+        }
+
+        let labels = lines.iter().map(|l| l.label).collect();
+        let qids = if lines.first().map_or(false, |l| l.qid.is_some()) {
+            Some(lines.iter().map(|l| l.qid.unwrap()).collect())
+        } else {
+            None
+        };
+        Ok((Dataset::new(samples, labels), qids))
+    }
+}
+
+fn parse_line(content: &str, line: usize) -> Result<Line, Error> {
+    let mut tokens = content.split_whitespace().enumerate().peekable();
+
+    let label = tokens.next().unwrap().1;
+    let label = match f64::from_str(label) {
+        Ok(label) => label,
+        Err(_) => return Err(Error::parse(line, 1, format!("invalid label `{}`", label))),
+    };
+
+    let mut qid = None;
+    if let Some(&(_, token)) = tokens.peek() {
+        if token.starts_with("qid:") {
+            match usize::from_str(&token[4..]) {
+                Ok(id) => qid = Some(id),
+                Err(_) => return Err(Error::parse(line, 2, format!("invalid query id `{}`", token))),
+            }
+            tokens.next();
+        }
+    }
+
+    let mut entries = Vec::new();
+    for (k,token) in tokens {
+        let mut parts = token.splitn(2, ':');
+        let index = parts.next().and_then(|i| usize::from_str(i).ok());
+        let value = parts.next().and_then(|v| f64::from_str(v).ok());
+        match (index, value) {
+            (Some(index), Some(value)) => entries.push((k + 1, index, value)),
+            _ => return Err(Error::parse(line, k + 1, format!("expected `index:value`, found `{}`", token))),
+        }
+    }
+
+    Ok(Line {
+        line: line,
+        label: label,
+        qid: qid,
+        entries: entries,
+    })
+}
+
+/// Writes samples in the LIBSVM/SVMlight format.
+pub struct LibsvmWriter {
+    zero_based: bool,
+}
+
+impl LibsvmWriter {
+    /// Creates a new writer, numbering features from `1`.
+    pub fn new() -> Self {
+        LibsvmWriter {
+            zero_based: false,
+        }
+    }
+
+    /// Numbers features from `0`.
+    pub fn with_zero_based(mut self) -> Self {
+        self.zero_based = true;
+        self
+    }
+
+    /// Writes sparse samples with their labels, and optionally a query id for each.
+    pub fn write_sparse<W, T, L>(&self, writer: &mut W, samples: &[SparseVector<T>], labels: &[L], qids: Option<&[usize]>) -> Result<(), Error>
+        where W: Write, T: Display, L: Display
+    {
+        let offset = if self.zero_based { 0 } else { 1 };
+        for (i,(x,y)) in samples.iter().zip(labels.iter()).enumerate() {
+            try!(write!(writer, "{}", y));
+            if let Some(qids) = qids {
+                try!(write!(writer, " qid:{}", qids[i]));
+            }
+            for (&index,value) in x.iter() {
+                try!(write!(writer, " {}:{}", index + offset, value));
+            }
+            try!(writeln!(writer, ""));
+        }
+        Ok(())
+    }
+
+    /// Writes dense samples, leaving out zeros.
+    pub fn write_dense<W, T, L>(&self, writer: &mut W, samples: &[Vector<T>], labels: &[L], qids: Option<&[usize]>) -> Result<(), Error>
+        where W: Write, T: Display + Zero + Clone, L: Display
+    {
+        let sparse: Vec<SparseVector<T>> = samples.iter().map(SparseVector::from_dense).collect();
+        self.write_sparse(writer, &sparse, labels, qids)
+    }
+}
+
+#[test]
+fn test_libsvm_read() {
+    let text = "# Ranking data\n\
+                1 qid:3 1:0.5 4:-2 # first\n\
+                \n\
+                -1 qid:3 2:1\n";
+
+    let (data, qids) = LibsvmReader::new().read(text.as_bytes()).unwrap();
+    assert_eq!(data.labels(), &[1f64, -1f64]);
+    assert_eq!(qids, Some(vec![3, 3]));
+    assert_eq!(data.sample(0).dim(), 4);
+    assert_eq!(data.sample(0).to_dense(), Vector::from_slice(&[0.5f64, 0f64, 0f64, -2f64]));
+
+    // Zero-based indices are detected.
+    let (data, qids) = LibsvmReader::new().with_n_features(6).read("2 0:1 5:3\n".as_bytes()).unwrap();
+    assert_eq!(qids, None);
+    assert_eq!(data.sample(0).indices(), &[0, 5]);
+}
+
+#[test]
+fn test_libsvm_errors() {
+    let reader = LibsvmReader::new().with_indexing(Indexing::OneBased);
+
+    match reader.read("1 1:2\n0 3:x\n".as_bytes()) {
+        Err(Error::Parse { line: 2, column: 2, .. }) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    match reader.read("1 1:2 0:1\n".as_bytes()) {
+        Err(Error::Parse { line: 1, column: 3, .. }) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    match reader.read("1 qid:1 1:2\n1 1:2\n".as_bytes()) {
+        Err(Error::Parse { line: 2, .. }) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_libsvm_write() {
+    let samples = vec![Vector::from_slice(&[0f64, 1.5f64, 0f64]), Vector::from_slice(&[2f64, 0f64, 0f64])];
+    let labels = [1, 0];
+
+    let mut output = Vec::new();
+    LibsvmWriter::new().write_dense(&mut output, &samples, &labels, Some(&[7, 7])).unwrap();
+    assert_eq!(String::from_utf8(output.clone()).unwrap(), "1 qid:7 2:1.5\n0 qid:7 1:2\n");
+
+    // Reading back gives the same data.
+    let (data, _) = LibsvmReader::new().with_n_features(3).read(&output[..]).unwrap();
+    assert_eq!(data.sample(0).to_dense(), samples[0]);
+    assert_eq!(data.sample(1).to_dense(), samples[1]);
+
+    let mut output = Vec::new();
+    LibsvmWriter::new().with_zero_based().write_dense(&mut output, &samples, &labels, None).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "1 1:1.5\n0 0:2\n");
+}
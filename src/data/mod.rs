@@ -0,0 +1,17 @@
+//! Data module
+//!
+//! The `Dataset` bundles samples with their labels, weights and feature names.
+//! Loaders for common file formats return a `Dataset`, and report an `Error` for malformed files.
+mod dataset;
+mod error;
+mod crc;
+
+pub mod csv;
+pub mod libsvm;
+pub mod arff;
+pub mod npy;
+pub mod mtx;
+pub mod model;
+
+pub use self::dataset::{Dataset,Samples,Batches};
+pub use self::error::Error;
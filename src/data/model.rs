@@ -0,0 +1,340 @@
+use std::io::{Read,Write};
+use std::mem;
+use std::time::{SystemTime,UNIX_EPOCH};
+
+use num::Float;
+
+use alg::{Vector,Matrix};
+use data::Error;
+use data::crc::crc32;
+use data::npy::Element;
+
+/// A scalar type models can be saved with: `f32` or `f64`.
+pub trait Scalar: Float + Element {
+    /// Returns the name of the type, as stored in model files.
+    fn name() -> &'static str;
+
+    /// Reads a value from its little-endian bytes.
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+fn read_le(bytes: &[u8]) -> u64 {
+    (0..bytes.len()).fold(0, |a,k| a | (bytes[k] as u64) << (8 * k))
+}
+
+impl Scalar for f32 {
+    fn name() -> &'static str { "f32" }
+    fn read_le(bytes: &[u8]) -> Self { f32::from_bits(read_le(bytes) as u32) }
+}
+
+impl Scalar for f64 {
+    fn name() -> &'static str { "f64" }
+    fn read_le(bytes: &[u8]) -> Self { f64::from_bits(read_le(bytes)) }
+}
+
+/// A trained model that can be saved with `save_model` and loaded with `load_model`.
+pub trait Model: Sized {
+    /// Scalar type of the model parameters.
+    type Scalar: Scalar;
+
+    /// Returns the name identifying the model type in files.
+    fn model_type() -> &'static str;
+
+    /// Writes the parameters of the model.
+    fn write_payload(&self, payload: &mut PayloadWriter);
+
+    /// Reads back the parameters written by `write_payload`.
+    fn read_payload(payload: &mut PayloadReader) -> Result<Self, Error>;
+}
+
+/// Metadata stored along with a model.
+#[derive(Clone,PartialEq,Debug)]
+pub struct ModelInfo {
+    /// Name of the model type, as given by `Model::model_type`.
+    pub model_type: String,
+    /// Name of the scalar type, as given by `Scalar::name`.
+    pub scalar: String,
+    /// Version of the crate that saved the model.
+    pub crate_version: String,
+    /// When the model was saved, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Names of the features the model was trained on, possibly empty.
+    pub feature_names: Vec<String>,
+}
+
+const MAGIC: &'static [u8] = b"ALUMINE\0";
+const VERSION: u32 = 1;
+
+/// Appends little-endian values to a model file.
+pub struct PayloadWriter {
+    bytes: Vec<u8>,
+}
+
+impl PayloadWriter {
+    fn write_le(&mut self, bits: u64, size: usize) {
+        for k in 0..size {
+            self.bytes.push((bits >> (8 * k)) as u8);
+        }
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_usize(s.len());
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    /// Writes a size or a count.
+    pub fn write_usize(&mut self, value: usize) {
+        self.write_le(value as u64, 8);
+    }
+
+    /// Writes an optional size or count.
+    pub fn write_option(&mut self, value: Option<usize>) {
+        match value {
+            Some(value) => {
+                self.write_bool(true);
+                self.write_usize(value);
+            },
+            None => self.write_bool(false),
+        }
+    }
+
+    /// Writes a flag.
+    pub fn write_bool(&mut self, value: bool) {
+        self.bytes.push(value as u8);
+    }
+
+    /// Writes a scalar.
+    pub fn write_scalar<T: Scalar>(&mut self, value: T) {
+        value.write_le(&mut self.bytes);
+    }
+
+    /// Writes a vector, with its dimension.
+    pub fn write_vector<T: Scalar>(&mut self, v: &Vector<T>) {
+        self.write_usize(v.dim());
+        for &x in v.data() {
+            self.write_scalar(x);
+        }
+    }
+
+    /// Writes a matrix, with its dimensions.
+    pub fn write_matrix<T: Scalar>(&mut self, m: &Matrix<T>) {
+        self.write_usize(m.m);
+        self.write_usize(m.n);
+        for &x in m.data() {
+            self.write_scalar(x);
+        }
+    }
+}
+
+/// Reads the values written by a `PayloadWriter`, failing on truncated data.
+pub struct PayloadReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl <'a> PayloadReader<'a> {
+    fn take(&mut self, size: usize) -> Result<&'a [u8], Error> {
+        if size > self.bytes.len() {
+            return Err(Error::Format("truncated model file".to_string()));
+        }
+        let (taken, rest) = self.bytes.split_at(size);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn read_str(&mut self) -> Result<String, Error> {
+        let len = try!(self.read_usize());
+        let bytes = try!(self.take(len));
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(s) => Ok(s),
+            Err(_) => Err(Error::Format("model file text is not UTF-8".to_string())),
+        }
+    }
+
+    /// Reads a size or a count.
+    pub fn read_usize(&mut self) -> Result<usize, Error> {
+        let value = read_le(try!(self.take(8)));
+        if value > usize::max_value() as u64 {
+            return Err(Error::Format(format!("size {} is too large", value)));
+        }
+        Ok(value as usize)
+    }
+
+    /// Reads an optional size or count.
+    pub fn read_option(&mut self) -> Result<Option<usize>, Error> {
+        if try!(self.read_bool()) {
+            Ok(Some(try!(self.read_usize())))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Reads a flag.
+    pub fn read_bool(&mut self) -> Result<bool, Error> {
+        match try!(self.take(1))[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(Error::Format(format!("invalid flag {}", other))),
+        }
+    }
+
+    /// Reads a scalar.
+    pub fn read_scalar<T: Scalar>(&mut self) -> Result<T, Error> {
+        Ok(T::read_le(try!(self.take(mem::size_of::<T>()))))
+    }
+
+    fn read_values<T: Scalar>(&mut self, count: usize) -> Result<Vec<T>, Error> {
+        let size = mem::size_of::<T>();
+        if count.checked_mul(size).map_or(true, |len| len > self.bytes.len()) {
+            return Err(Error::Format("truncated model file".to_string()));
+        }
+        let bytes = try!(self.take(count * size));
+        Ok(bytes.chunks(size).map(T::read_le).collect())
+    }
+
+    /// Reads a vector written by `PayloadWriter::write_vector`.
+    pub fn read_vector<T: Scalar>(&mut self) -> Result<Vector<T>, Error> {
+        let dim = try!(self.read_usize());
+        Ok(Vector::from_vec(try!(self.read_values(dim))))
+    }
+
+    /// Reads a matrix written by `PayloadWriter::write_matrix`.
+    pub fn read_matrix<T: Scalar>(&mut self) -> Result<Matrix<T>, Error> {
+        let m = try!(self.read_usize());
+        let n = try!(self.read_usize());
+        let count = match m.checked_mul(n) {
+            Some(count) => count,
+            None => return Err(Error::Format(format!("invalid matrix dimensions {}x{}", m, n))),
+        };
+        Ok(Matrix::from_col_major(n, m, try!(self.read_values(count))))
+    }
+}
+
+/// Saves a model with its metadata, stamped with the current time.
+///
+/// The file holds a magic header, the metadata, the model parameters in little-endian,
+/// and a CRC-32 of everything before it.
+pub fn save_model<M: Model, W: Write>(writer: &mut W, model: &M, feature_names: &[String]) -> Result<(), Error> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+    let mut payload = PayloadWriter { bytes: MAGIC.to_vec() };
+    payload.write_le(VERSION as u64, 4);
+    payload.write_str(M::model_type());
+    payload.write_str(M::Scalar::name());
+    payload.write_str(env!("CARGO_PKG_VERSION"));
+    payload.write_le(timestamp, 8);
+    payload.write_usize(feature_names.len());
+    for name in feature_names {
+        payload.write_str(name);
+    }
+    model.write_payload(&mut payload);
+
+    let crc = crc32(&payload.bytes);
+    payload.write_le(crc as u64, 4);
+    try!(writer.write_all(&payload.bytes));
+    Ok(())
+}
+
+// Checks the magic header, the version and the checksum, and reads the metadata.
+fn read_header<'a>(bytes: &'a [u8]) -> Result<(ModelInfo, PayloadReader<'a>), Error> {
+    if bytes.len() < MAGIC.len() + 8 || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(Error::Format("not a model file".to_string()));
+    }
+    let (content, crc) = bytes.split_at(bytes.len() - 4);
+    if crc32(content) as u64 != read_le(crc) {
+        return Err(Error::Format("model file is corrupted: checksum mismatch".to_string()));
+    }
+
+    let mut reader = PayloadReader { bytes: &content[MAGIC.len()..] };
+    let version = read_le(try!(reader.take(4)));
+    if version != VERSION as u64 {
+        return Err(Error::Format(format!("model file version {} is not supported, expected {}", version, VERSION)));
+    }
+
+    let model_type = try!(reader.read_str());
+    let scalar = try!(reader.read_str());
+    let crate_version = try!(reader.read_str());
+    let timestamp = read_le(try!(reader.take(8)));
+    let n_features = try!(reader.read_usize());
+    let mut feature_names = Vec::new();
+    for _ in 0..n_features {
+        feature_names.push(try!(reader.read_str()));
+    }
+
+    let info = ModelInfo {
+        model_type: model_type,
+        scalar: scalar,
+        crate_version: crate_version,
+        timestamp: timestamp,
+        feature_names: feature_names,
+    };
+    Ok((info, reader))
+}
+
+/// Reads the metadata of a saved model, whatever its type.
+pub fn read_model_info<R: Read>(mut reader: R) -> Result<ModelInfo, Error> {
+    let mut bytes = Vec::new();
+    try!(reader.read_to_end(&mut bytes));
+    read_header(&bytes).map(|(info, _)| info)
+}
+
+/// Loads a model saved by `save_model`, with its metadata.
+///
+/// Returns an error if the file is corrupted, or holds another model type or scalar type.
+pub fn load_model<M: Model, R: Read>(mut reader: R) -> Result<(M, ModelInfo), Error> {
+    let mut bytes = Vec::new();
+    try!(reader.read_to_end(&mut bytes));
+    let (info, mut payload) = try!(read_header(&bytes));
+
+    if info.model_type != M::model_type() {
+        return Err(Error::Format(format!("expected a `{}` model, found `{}`", M::model_type(), info.model_type)));
+    }
+    if info.scalar != M::Scalar::name() {
+        return Err(Error::Format(format!("expected `{}` parameters, found `{}`", M::Scalar::name(), info.scalar)));
+    }
+
+    let model = try!(M::read_payload(&mut payload));
+    if !payload.bytes.is_empty() {
+        return Err(Error::Format(format!("{} unexpected bytes after the model", payload.bytes.len())));
+    }
+    Ok((model, info))
+}
+
+#[test]
+fn test_model_file() {
+    use ml::Classifier;
+    use ml::linear::LinearRegression;
+    use ml::kmeans::KMeans;
+
+    let samples = vec![Vector::from_slice(&[1f64, 0f64]), Vector::from_slice(&[1f64, 1f64]), Vector::from_slice(&[1f64, 2f64])];
+    let mut model = LinearRegression::new();
+    model.train(&samples, &[1f64, 3f64, 5f64]);
+
+    let names = vec!["bias".to_string(), "x".to_string()];
+    let mut bytes = Vec::new();
+    save_model(&mut bytes, &model, &names).unwrap();
+
+    let (loaded, info) = load_model::<LinearRegression<f64>, _>(&bytes[..]).unwrap();
+    assert_eq!(loaded.classify(&Vector::from_slice(&[1f64, 3f64])), model.classify(&Vector::from_slice(&[1f64, 3f64])));
+    assert_eq!(info.model_type, "LinearRegression");
+    assert_eq!(info.scalar, "f64");
+    assert_eq!(info.feature_names, names);
+    assert_eq!(read_model_info(&bytes[..]).unwrap(), info);
+
+    // Other model and scalar types are refused.
+    match load_model::<KMeans<f64>, _>(&bytes[..]) {
+        Err(Error::Format(ref message)) if message.contains("KMeans") => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    match load_model::<LinearRegression<f32>, _>(&bytes[..]) {
+        Err(Error::Format(ref message)) if message.contains("f32") => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+
+    // So are corrupted files.
+    let last = bytes.len() - 5;
+    bytes[last] ^= 1;
+    match load_model::<LinearRegression<f64>, _>(&bytes[..]) {
+        Err(Error::Format(ref message)) if message.contains("checksum") => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+}
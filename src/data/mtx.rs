@@ -0,0 +1,507 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::io::{Read,BufRead,BufReader,Write};
+use std::ops::Neg;
+use std::str::FromStr;
+
+use num::{NumCast,Zero};
+
+use alg::{Matrix,SparseVector};
+use data::Error;
+
+/// How the values of a Matrix Market file are laid out.
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Layout {
+    /// `array`: every value, in column-major order.
+    Array,
+    /// `coordinate`: `row column value` for each stored value.
+    Coordinate,
+}
+
+/// Type of the values of a Matrix Market file.
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Field {
+    /// `real` values.
+    Real,
+    /// `integer` values.
+    Integer,
+    /// `pattern`: coordinates only, read as ones. Not allowed with `Layout::Array`.
+    Pattern,
+}
+
+/// Which part of the matrix a Matrix Market file stores.
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Symmetry {
+    /// `general`: the whole matrix.
+    General,
+    /// `symmetric`: the lower triangle, diagonal included.
+    Symmetric,
+    /// `skew-symmetric`: the lower triangle, diagonal excluded.
+    SkewSymmetric,
+}
+
+/// The header of a Matrix Market file.
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub struct MtxHeader {
+    pub layout: Layout,
+    pub field: Field,
+    pub symmetry: Symmetry,
+    /// Number of rows.
+    pub rows: usize,
+    /// Number of columns.
+    pub cols: usize,
+}
+
+/// Reads a Matrix Market file as a dense matrix.
+///
+/// Symmetric and skew-symmetric matrices are expanded to the whole matrix.
+/// In errors, columns count whitespace-separated tokens.
+pub fn read_matrix<T, R>(reader: R) -> Result<(Matrix<T>, MtxHeader), Error>
+    where T: NumCast + Zero + Clone, R: Read
+{
+    let (header, entries) = try!(read_entries(reader));
+    let count = match header.rows.checked_mul(header.cols) {
+        Some(count) => count,
+        None => return Err(Error::Format(format!("invalid matrix dimensions {}x{}", header.rows, header.cols))),
+    };
+    let mut data = vec![T::zero(); count];
+    for (row, col, value) in entries {
+        data[col * header.rows + row] = try!(cast(value));
+    }
+    Ok((Matrix::from_col_major(header.cols, header.rows, data), header))
+}
+
+/// Reads a Matrix Market file as sparse rows.
+///
+/// Symmetric and skew-symmetric matrices are expanded to the whole matrix.
+/// In errors, columns count whitespace-separated tokens.
+pub fn read_sparse<T, R>(reader: R) -> Result<(Vec<SparseVector<T>>, MtxHeader), Error>
+    where T: NumCast + Zero, R: Read
+{
+    let (header, entries) = try!(read_entries(reader));
+    let mut rows: Vec<Vec<(usize, T)>> = (0..header.rows).map(|_| Vec::new()).collect();
+    for (row, col, value) in entries {
+        // Array files store zeros too.
+        if value != 0f64 {
+            rows[row].push((col, try!(cast(value))));
+        }
+    }
+    let rows = rows.into_iter().map(|entries| SparseVector::new(header.cols, entries)).collect();
+    Ok((rows, header))
+}
+
+fn cast<T: NumCast>(value: f64) -> Result<T, Error> {
+    match T::from(value) {
+        Some(value) => Ok(value),
+        None => Err(Error::Format(format!("value {} doesn't fit the element type", value))),
+    }
+}
+
+fn parse_banner(text: &str) -> Result<(Layout, Field, Symmetry), Error> {
+    let text = text.to_lowercase();
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    if tokens.len() != 5 || tokens[0] != "%%matrixmarket" {
+        return Err(Error::parse(1, 0, "expected `%%MatrixMarket matrix <layout> <field> <symmetry>`"));
+    }
+    if tokens[1] != "matrix" {
+        return Err(Error::parse(1, 2, format!("unsupported object `{}`", tokens[1])));
+    }
+    let layout = match tokens[2] {
+        "array" => Layout::Array,
+        "coordinate" => Layout::Coordinate,
+        other => return Err(Error::parse(1, 3, format!("unsupported layout `{}`", other))),
+    };
+    let field = match tokens[3] {
+        "real" | "double" => Field::Real,
+        "integer" => Field::Integer,
+        "pattern" if layout == Layout::Coordinate => Field::Pattern,
+        other => return Err(Error::parse(1, 4, format!("unsupported field `{}`", other))),
+    };
+    let symmetry = match tokens[4] {
+        "general" => Symmetry::General,
+        "symmetric" => Symmetry::Symmetric,
+        "skew-symmetric" => Symmetry::SkewSymmetric,
+        other => return Err(Error::parse(1, 5, format!("unsupported symmetry `{}`", other))),
+    };
+    Ok((layout, field, symmetry))
+}
+
+fn parse_value(field: Field, token: &str, line: usize, column: usize) -> Result<f64, Error> {
+    let value = match field {
+        Field::Real => f64::from_str(token).ok(),
+        Field::Integer => i64::from_str(token).ok().map(|v| v as f64),
+        Field::Pattern => Some(1f64),
+    };
+    match value {
+        Some(value) => Ok(value),
+        None => Err(Error::parse(line, column, format!("invalid value `{}`", token))),
+    }
+}
+
+fn parse_index(token: &str, bound: usize, line: usize, column: usize) -> Result<usize, Error> {
+    match usize::from_str(token) {
+        Ok(index) if index >= 1 && index <= bound => Ok(index - 1),
+        _ => Err(Error::parse(line, column, format!("index `{}` out of 1..{}", token, bound))),
+    }
+}
+
+// Positions stored by an array file, in order.
+fn array_positions(rows: usize, cols: usize, symmetry: Symmetry) -> Vec<(usize, usize)> {
+    let mut positions = Vec::new();
+    for col in 0..cols {
+        let start = match symmetry {
+            Symmetry::General => 0,
+            Symmetry::Symmetric => col,
+            Symmetry::SkewSymmetric => col + 1,
+        };
+        for row in start..rows {
+            positions.push((row, col));
+        }
+    }
+    positions
+}
+
+// Reads the header and every `(row, column, value)` entry, zero-based, with symmetric entries mirrored.
+fn read_entries<R: Read>(reader: R) -> Result<(MtxHeader, Vec<(usize, usize, f64)>), Error> {
+    let mut lines = BufReader::new(reader).lines().enumerate();
+    let (layout, field, symmetry) = match lines.next() {
+        Some((_, text)) => try!(parse_banner(&try!(text))),
+        None => return Err(Error::parse(1, 0, "empty file")),
+    };
+
+    let mut header = None;
+    let mut positions = Vec::new();
+    let mut expected = 0;
+    let mut stored = HashSet::new();
+    let mut entries = Vec::new();
+    let mut count = 0;
+
+    for (i,text) in lines {
+        let text = try!(text);
+        let line = i + 1;
+        let content = text.trim();
+        if content.is_empty() || content.starts_with('%') { continue; }
+        let tokens: Vec<&str> = content.split_whitespace().collect();
+
+        let (rows, cols) = match header {
+            Some(MtxHeader { rows, cols, .. }) => (rows, cols),
+            None => {
+                let size_len = if layout == Layout::Array { 2 } else { 3 };
+                if tokens.len() != size_len {
+                    return Err(Error::parse(line, 0, format!("expected {} sizes", size_len)));
+                }
+                let mut sizes = Vec::with_capacity(size_len);
+                for (k,token) in tokens.iter().enumerate() {
+                    match usize::from_str(token) {
+                        Ok(size) => sizes.push(size),
+                        Err(_) => return Err(Error::parse(line, k + 1, format!("invalid size `{}`", token))),
+                    }
+                }
+                if symmetry != Symmetry::General && sizes[0] != sizes[1] {
+                    return Err(Error::parse(line, 0, "symmetric matrices must be square"));
+                }
+                expected = match layout {
+                    Layout::Array => {
+                        positions = array_positions(sizes[0], sizes[1], symmetry);
+                        positions.len()
+                    },
+                    Layout::Coordinate => sizes[2],
+                };
+                header = Some(MtxHeader {
+                    layout: layout,
+                    field: field,
+                    symmetry: symmetry,
+                    rows: sizes[0],
+                    cols: sizes[1],
+                });
+                continue;
+            },
+        };
+
+        let mut values = Vec::new();
+        match layout {
+            Layout::Array => {
+                for (k,token) in tokens.iter().enumerate() {
+                    if count + values.len() >= expected {
+                        return Err(Error::parse(line, k + 1, format!("more than {} values", expected)));
+                    }
+                    let (row, col) = positions[count + values.len()];
+                    values.push((row, col, try!(parse_value(field, token, line, k + 1))));
+                }
+            },
+            Layout::Coordinate => {
+                let len = if field == Field::Pattern { 2 } else { 3 };
+                if tokens.len() != len {
+                    return Err(Error::parse(line, 0, format!("expected {} tokens", len)));
+                }
+                if count >= expected {
+                    return Err(Error::parse(line, 0, format!("more than {} entries", expected)));
+                }
+                let row = try!(parse_index(tokens[0], rows, line, 1));
+                let col = try!(parse_index(tokens[1], cols, line, 2));
+                let value = if len == 3 { try!(parse_value(field, tokens[2], line, 3)) } else { 1f64 };
+                let valid = match symmetry {
+                    Symmetry::General => true,
+                    Symmetry::Symmetric => row >= col,
+                    Symmetry::SkewSymmetric => row > col,
+                };
+                if !valid {
+                    return Err(Error::parse(line, 0, "entry outside the stored triangle"));
+                }
+                if !stored.insert((row, col)) {
+                    return Err(Error::parse(line, 0, format!("duplicate entry ({}, {})", row + 1, col + 1)));
+                }
+                values.push((row, col, value));
+            },
+        }
+
+        count += values.len();
+        for (row, col, value) in values {
+            entries.push((row, col, value));
+            if row != col {
+                match symmetry {
+                    Symmetry::General => (),
+                    Symmetry::Symmetric => entries.push((col, row, value)),
+                    Symmetry::SkewSymmetric => entries.push((col, row, -value)),
+                }
+            }
+        }
+    }
+
+    match header {
+        Some(header) if count == expected => Ok((header, entries)),
+        Some(_) => Err(Error::Format(format!("expected {} entries, found {}", expected, count))),
+        None => Err(Error::Format("missing the size line".to_string())),
+    }
+}
+
+/// Writes matrices in the Matrix Market format.
+pub struct MtxWriter {
+    field: Field,
+    symmetry: Symmetry,
+}
+
+impl MtxWriter {
+    /// Creates a new writer of `real` `general` matrices.
+    pub fn new() -> Self {
+        MtxWriter {
+            field: Field::Real,
+            symmetry: Symmetry::General,
+        }
+    }
+
+    /// Sets the field written in the header.
+    ///
+    /// Values are written as they are displayed, `Field::Pattern` leaves them out.
+    pub fn with_field(mut self, field: Field) -> Self {
+        self.field = field;
+        self
+    }
+
+    /// Writes only the lower triangle, for symmetric or skew-symmetric matrices.
+    pub fn with_symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    fn write_banner<W: Write>(&self, writer: &mut W, layout: Layout) -> Result<(), Error> {
+        let layout = match layout {
+            Layout::Array => "array",
+            Layout::Coordinate => "coordinate",
+        };
+        let field = match self.field {
+            Field::Real => "real",
+            Field::Integer => "integer",
+            Field::Pattern => "pattern",
+        };
+        let symmetry = match self.symmetry {
+            Symmetry::General => "general",
+            Symmetry::Symmetric => "symmetric",
+            Symmetry::SkewSymmetric => "skew-symmetric",
+        };
+        try!(writeln!(writer, "%%MatrixMarket matrix {} {} {}", layout, field, symmetry));
+        Ok(())
+    }
+
+    // Checks that the value at `(row, col)` is implied by the one at `(col, row)`.
+    fn check<T>(&self, value: &T, mirror: &T, row: usize, col: usize) -> Result<(), Error>
+        where T: Clone + PartialEq + Neg<Output=T>
+    {
+        let valid = match self.symmetry {
+            Symmetry::General => true,
+            Symmetry::Symmetric => value == mirror,
+            Symmetry::SkewSymmetric => *value == -mirror.clone(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::Format(format!("matrix doesn't match its symmetry at ({}, {})", row + 1, col + 1)))
+        }
+    }
+
+    /// Writes a dense matrix in the `array` layout.
+    ///
+    /// Returns an error for the `pattern` field, or if the matrix doesn't have the writer's symmetry.
+    pub fn write_matrix<W, T>(&self, writer: &mut W, matrix: &Matrix<T>) -> Result<(), Error>
+        where W: Write, T: Display + Clone + PartialEq + Neg<Output=T>
+    {
+        if self.field == Field::Pattern {
+            return Err(Error::Format("the array layout can't have a pattern field".to_string()));
+        }
+        if self.symmetry != Symmetry::General && !matrix.is_square() {
+            return Err(Error::Format("symmetric matrices must be square".to_string()));
+        }
+
+        try!(self.write_banner(writer, Layout::Array));
+        try!(writeln!(writer, "{} {}", matrix.m, matrix.n));
+        if self.symmetry != Symmetry::General {
+            for x in 0..matrix.n {
+                for y in 0..matrix.m {
+                    try!(self.check(&matrix[(x, y)], &matrix[(y, x)], y, x));
+                }
+            }
+        }
+        for (row, col) in array_positions(matrix.m, matrix.n, self.symmetry) {
+            try!(writeln!(writer, "{}", matrix[(col, row)]));
+        }
+        Ok(())
+    }
+
+    /// Writes sparse rows in the `coordinate` layout. Every row must have the same dimension.
+    ///
+    /// Returns an error if the matrix doesn't have the writer's symmetry.
+    pub fn write_sparse<W, T>(&self, writer: &mut W, rows: &[SparseVector<T>]) -> Result<(), Error>
+        where W: Write, T: Display + Clone + Zero + PartialEq + Neg<Output=T>
+    {
+        let cols = rows.first().map_or(0, |r| r.dim());
+        if rows.iter().any(|r| r.dim() != cols) {
+            return Err(Error::Format("rows have different dimensions".to_string()));
+        }
+        if self.symmetry != Symmetry::General && rows.len() != cols {
+            return Err(Error::Format("symmetric matrices must be square".to_string()));
+        }
+
+        let mut entries = Vec::new();
+        for (row, r) in rows.iter().enumerate() {
+            for (&col, value) in r.iter() {
+                // Squareness was checked, so the mirror entry exists.
+                if self.symmetry != Symmetry::General {
+                    try!(self.check(value, &rows[col].get(row), row, col));
+                }
+                let stored = match self.symmetry {
+                    Symmetry::General => true,
+                    Symmetry::Symmetric => row >= col,
+                    Symmetry::SkewSymmetric => row > col,
+                };
+                if stored {
+                    entries.push((row, col, value));
+                }
+            }
+        }
+        if self.symmetry == Symmetry::SkewSymmetric && entries.len() * 2 != rows.iter().map(|r| r.nnz()).sum::<usize>() {
+            return Err(Error::Format("skew-symmetric matrices must have a zero diagonal".to_string()));
+        }
+
+        try!(self.write_banner(writer, Layout::Coordinate));
+        try!(writeln!(writer, "{} {} {}", rows.len(), cols, entries.len()));
+        for (row, col, value) in entries {
+            if self.field == Field::Pattern {
+                try!(writeln!(writer, "{} {}", row + 1, col + 1));
+            } else {
+                try!(writeln!(writer, "{} {} {}", row + 1, col + 1, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_mtx_read() {
+    let text = "%%MatrixMarket matrix coordinate real symmetric\n\
+                % A comment\n\
+                3 3 3\n\
+                1 1 2.5\n\
+                3 1 -1\n\
+                2 2 4\n";
+    let (matrix, header) = read_matrix::<f64, _>(text.as_bytes()).unwrap();
+    assert_eq!(header.symmetry, Symmetry::Symmetric);
+    assert_eq!(matrix.row(0).data(), &[2.5f64, 0f64, -1f64]);
+    assert_eq!(matrix.row(2).data(), &[-1f64, 0f64, 0f64]);
+
+    let (rows, _) = read_sparse::<f64, _>(text.as_bytes()).unwrap();
+    assert_eq!(rows[0].indices(), &[0, 2]);
+    assert_eq!(rows[1].indices(), &[1]);
+
+    // Arrays are column-major, skew-symmetric ones without the diagonal.
+    let text = "%%MatrixMarket matrix array integer skew-symmetric\n3 3\n1\n2\n3\n";
+    let (matrix, _) = read_matrix::<i32, _>(text.as_bytes()).unwrap();
+    assert_eq!(matrix.row(0).data(), &[0, -1, -2]);
+    assert_eq!(matrix.row(1).data(), &[1, 0, -3]);
+    assert_eq!(matrix.row(2).data(), &[2, 3, 0]);
+
+    let text = "%%MatrixMarket matrix coordinate pattern general\n2 3 2\n1 3\n2 1\n";
+    let (matrix, _) = read_matrix::<f32, _>(text.as_bytes()).unwrap();
+    assert_eq!((matrix.n, matrix.m), (3, 2));
+    assert_eq!(matrix.row(0).data(), &[0f32, 0f32, 1f32]);
+    assert_eq!(matrix.row(1).data(), &[1f32, 0f32, 0f32]);
+}
+
+#[test]
+fn test_mtx_errors() {
+    match read_matrix::<f64, _>("%%MatrixMarket matrix array pattern general\n1 1\n".as_bytes()) {
+        Err(Error::Parse { line: 1, column: 4, .. }) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    match read_matrix::<f64, _>("%%MatrixMarket matrix coordinate real general\n2 2 2\n1 1 1\n3 1 1\n".as_bytes()) {
+        Err(Error::Parse { line: 4, column: 1, .. }) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    match read_matrix::<f64, _>("%%MatrixMarket matrix coordinate real symmetric\n2 2 1\n1 2 1\n".as_bytes()) {
+        Err(Error::Parse { line: 3, column: 0, .. }) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    match read_matrix::<f64, _>("%%MatrixMarket matrix array real general\n2 2\n1\n2\n3\n".as_bytes()) {
+        Err(Error::Format(_)) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    match read_matrix::<f64, _>("%%MatrixMarket matrix coordinate real general\n4294967296 4294967296 0\n".as_bytes()) {
+        Err(Error::Format(_)) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_mtx_write() {
+    let matrix = Matrix::from_rows(&[
+        ::alg::Vector::from_slice(&[1f64, 2f64]),
+        ::alg::Vector::from_slice(&[2f64, 0.5f64]),
+    ]);
+
+    let mut output = Vec::new();
+    MtxWriter::new().with_symmetry(Symmetry::Symmetric).write_matrix(&mut output, &matrix).unwrap();
+    assert_eq!(String::from_utf8(output.clone()).unwrap(), "%%MatrixMarket matrix array real symmetric\n2 2\n1\n2\n0.5\n");
+    let (read, _) = read_matrix::<f64, _>(&output[..]).unwrap();
+    assert_eq!(read.data(), matrix.data());
+
+    let rows: Vec<SparseVector<f64>> = (0..2).map(|y| SparseVector::from_dense(&matrix.row(y))).collect();
+    let mut output = Vec::new();
+    MtxWriter::new().write_sparse(&mut output, &rows).unwrap();
+    let (read, header) = read_sparse::<f64, _>(&output[..]).unwrap();
+    assert_eq!(header.layout, Layout::Coordinate);
+    assert_eq!(read, rows);
+
+    // General matrices need not be square.
+    let wide: Vec<SparseVector<f64>> = vec![
+        SparseVector::from_dense(&::alg::Vector::from_slice(&[0f64, 1f64, 0f64])),
+        SparseVector::from_dense(&::alg::Vector::from_slice(&[2f64, 0f64, 3f64])),
+    ];
+    let mut output = Vec::new();
+    MtxWriter::new().write_sparse(&mut output, &wide).unwrap();
+    let (read, header) = read_sparse::<f64, _>(&output[..]).unwrap();
+    assert_eq!((header.rows, header.cols), (2, 3));
+    assert_eq!(read, wide);
+
+    // The matrix isn't skew-symmetric.
+    let writer = MtxWriter::new().with_symmetry(Symmetry::SkewSymmetric);
+    assert!(writer.write_sparse(&mut Vec::new(), &rows).is_err());
+}
@@ -0,0 +1,451 @@
+use std::io::{Read,Write};
+
+use num::NumCast;
+
+use alg::{Vector,Matrix};
+use data::Error;
+use data::crc::crc32;
+
+/// A type that can be written to `.npy` files.
+///
+/// Reading converts from any supported type: `f32`, `f64`, `i32` and `i64`.
+pub trait Element: Copy + NumCast {
+    /// Returns the NumPy type code, without byte order.
+    fn descr() -> &'static str;
+
+    /// Appends the little-endian bytes of the value.
+    fn write_le(self, out: &mut Vec<u8>);
+}
+
+fn write_le(bits: u64, size: usize, out: &mut Vec<u8>) {
+    for k in 0..size {
+        out.push((bits >> (8 * k)) as u8);
+    }
+}
+
+impl Element for f32 {
+    fn descr() -> &'static str { "f4" }
+    fn write_le(self, out: &mut Vec<u8>) { write_le(self.to_bits() as u64, 4, out) }
+}
+
+impl Element for f64 {
+    fn descr() -> &'static str { "f8" }
+    fn write_le(self, out: &mut Vec<u8>) { write_le(self.to_bits(), 8, out) }
+}
+
+impl Element for i32 {
+    fn descr() -> &'static str { "i4" }
+    fn write_le(self, out: &mut Vec<u8>) { write_le(self as u32 as u64, 4, out) }
+}
+
+impl Element for i64 {
+    fn descr() -> &'static str { "i8" }
+    fn write_le(self, out: &mut Vec<u8>) { write_le(self as u64, 8, out) }
+}
+
+const MAGIC: &'static [u8] = b"\x93NUMPY";
+
+// Parsed `.npy` header.
+struct Header {
+    descr: String,
+    big_endian: bool,
+    fortran_order: bool,
+    shape: Vec<usize>,
+}
+
+fn format_error<S: Into<String>>(message: S) -> Error {
+    Error::Format(message.into())
+}
+
+// Returns the text after `'key':` in the header dictionary.
+fn field<'a>(dict: &'a str, key: &str) -> Result<&'a str, Error> {
+    let pattern = format!("'{}':", key);
+    match dict.find(&pattern[..]) {
+        Some(start) => Ok(dict[start + pattern.len()..].trim_start()),
+        None => Err(format_error(format!("npy header has no `{}`", key))),
+    }
+}
+
+fn parse_header(dict: &str) -> Result<Header, Error> {
+    let descr = try!(field(dict, "descr"));
+    if !descr.starts_with('\'') && !descr.starts_with('"') {
+        return Err(format_error("invalid npy descr"));
+    }
+    let descr = match descr[1..].find(|c| c == '\'' || c == '"') {
+        Some(end) => &descr[1..end+1],
+        None => return Err(format_error("invalid npy descr")),
+    };
+    let (big_endian, code) = match descr.chars().next() {
+        Some('>') => (true, &descr[1..]),
+        Some('<') | Some('=') | Some('|') => (false, &descr[1..]),
+        _ => (false, descr),
+    };
+
+    let fortran_order = try!(field(dict, "fortran_order")).starts_with("True");
+
+    let shape = try!(field(dict, "shape"));
+    let shape = match shape.find(')') {
+        Some(end) if shape.starts_with('(') => &shape[1..end],
+        _ => return Err(format_error("invalid npy shape")),
+    };
+    let mut dims = Vec::new();
+    for dim in shape.split(',').map(|d| d.trim()).filter(|d| !d.is_empty()) {
+        match dim.parse() {
+            Ok(dim) => dims.push(dim),
+            Err(_) => return Err(format_error(format!("invalid npy dimension `{}`", dim))),
+        }
+    }
+
+    Ok(Header {
+        descr: code.to_string(),
+        big_endian: big_endian,
+        fortran_order: fortran_order,
+        shape: dims,
+    })
+}
+
+// Reads `len` bytes, only allocating as they arrive, so that a bogus length fails on truncation.
+fn read_bytes<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    try!(reader.take(len as u64).read_to_end(&mut bytes));
+    if bytes.len() < len {
+        return Err(format_error("truncated npy file"));
+    }
+    Ok(bytes)
+}
+
+// Reads the header and the values, in file order.
+fn read_array<T: Element, R: Read>(mut reader: R) -> Result<(Header, Vec<T>), Error> {
+    let mut prefix = [0u8; 8];
+    try!(reader.read_exact(&mut prefix));
+    if &prefix[..6] != MAGIC {
+        return Err(format_error("not an npy file"));
+    }
+
+    let header_len = if prefix[6] == 1 {
+        let mut len = [0u8; 2];
+        try!(reader.read_exact(&mut len));
+        len[0] as usize | (len[1] as usize) << 8
+    } else {
+        let mut len = [0u8; 4];
+        try!(reader.read_exact(&mut len));
+        (0..4).fold(0, |a,k| a | (len[k] as usize) << (8 * k))
+    };
+    let dict = try!(read_bytes(&mut reader, header_len));
+    let dict = match String::from_utf8(dict) {
+        Ok(dict) => dict,
+        Err(_) => return Err(format_error("npy header is not text")),
+    };
+    let header = try!(parse_header(&dict));
+
+    let size = match &header.descr[..] {
+        "f4" | "i4" => 4,
+        "f8" | "i8" => 8,
+        other => return Err(format_error(format!("unsupported npy type `{}`", other))),
+    };
+    let count = match header.shape.iter().fold(Some(1usize), |a,&b| a.and_then(|a| a.checked_mul(b))) {
+        Some(count) => count,
+        None => return Err(format_error("npy shape is too large")),
+    };
+    let len = match count.checked_mul(size) {
+        Some(len) => len,
+        None => return Err(format_error("npy shape is too large")),
+    };
+    let bytes = try!(read_bytes(&mut reader, len));
+
+    let mut values = Vec::with_capacity(count);
+    for chunk in bytes.chunks(size) {
+        let bits = (0..size).fold(0u64, |a,k| {
+            let byte = if header.big_endian { chunk[size - 1 - k] } else { chunk[k] };
+            a | (byte as u64) << (8 * k)
+        });
+        let value = match &header.descr[..] {
+            "f4" => T::from(f32::from_bits(bits as u32)),
+            "f8" => T::from(f64::from_bits(bits)),
+            "i4" => T::from(bits as u32 as i32),
+            _ => T::from(bits as i64),
+        };
+        match value {
+            Some(value) => values.push(value),
+            None => return Err(format_error("npy value out of range")),
+        }
+    }
+    Ok((header, values))
+}
+
+fn write_array<T: Element, W: Write>(writer: &mut W, shape: &[usize], fortran_order: bool, values: &[T]) -> Result<(), Error> {
+    let shape = match shape.len() {
+        1 => format!("({},)", shape[0]),
+        _ => format!("({})", shape.iter().map(|d| d.to_string()).collect::<Vec<String>>().join(", ")),
+    };
+    let mut dict = format!("{{'descr': '<{}', 'fortran_order': {}, 'shape': {}, }}",
+                           T::descr(), if fortran_order { "True" } else { "False" }, shape);
+    // The data starts on a multiple of 64 bytes.
+    while (MAGIC.len() + 4 + dict.len() + 1) % 64 != 0 {
+        dict.push(' ');
+    }
+    dict.push('\n');
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 4 + dict.len() + values.len() * 8);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&[1, 0]);
+    write_le(dict.len() as u64, 2, &mut bytes);
+    bytes.extend_from_slice(dict.as_bytes());
+    for &value in values {
+        value.write_le(&mut bytes);
+    }
+    try!(writer.write_all(&bytes));
+    Ok(())
+}
+
+/// Reads a matrix from a `.npy` file holding a 2-dimensional array, or a 1-dimensional one as a column.
+///
+/// Arrays in Fortran order map directly to the matrix storage.
+pub fn read_matrix<T: Element, R: Read>(reader: R) -> Result<Matrix<T>, Error> {
+    let (header, values) = try!(read_array(reader));
+    let (m, n) = match header.shape.len() {
+        1 => (header.shape[0], 1),
+        2 => (header.shape[0], header.shape[1]),
+        d => return Err(format_error(format!("expected a 2-dimensional array, found {} dimensions", d))),
+    };
+
+    if header.fortran_order || n == 1 {
+        Ok(Matrix::from_col_major(n, m, values))
+    } else {
+        Ok(Matrix::new(n, m, |x,y| values[y * n + x]))
+    }
+}
+
+/// Reads a vector from a `.npy` file holding a 1-dimensional array.
+pub fn read_vector<T: Element, R: Read>(reader: R) -> Result<Vector<T>, Error> {
+    let (header, values) = try!(read_array(reader));
+    if header.shape.len() != 1 {
+        return Err(format_error(format!("expected a 1-dimensional array, found {} dimensions", header.shape.len())));
+    }
+    Ok(Vector::from_vec(values))
+}
+
+/// Writes a matrix as a 2-dimensional array in Fortran order, straight from the matrix storage.
+pub fn write_matrix<T: Element, W: Write>(writer: &mut W, matrix: &Matrix<T>) -> Result<(), Error> {
+    write_array(writer, &[matrix.m, matrix.n], true, matrix.data())
+}
+
+/// Writes a vector as a 1-dimensional array.
+pub fn write_vector<T: Element, W: Write>(writer: &mut W, vector: &Vector<T>) -> Result<(), Error> {
+    write_array(writer, &[vector.dim()], false, vector.data())
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> usize {
+    bytes[at] as usize | (bytes[at + 1] as usize) << 8
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> usize {
+    (0..4).fold(0, |a,k| a | (bytes[at + k] as usize) << (8 * k))
+}
+
+/// Reads every array of a `.npz` archive, as matrices, with their name.
+///
+/// Only archives written by `numpy.savez` are supported, not compressed ones.
+pub fn read_npz<T: Element, R: Read>(mut reader: R) -> Result<Vec<(String, Matrix<T>)>, Error> {
+    let mut bytes = Vec::new();
+    try!(reader.read_to_end(&mut bytes));
+
+    // The end of central directory record is last, followed by a comment of at most 64kB.
+    let end = match (0..bytes.len().saturating_sub(21)).rev().take(65536 + 22).find(|&i| &bytes[i..i+4] == b"PK\x05\x06") {
+        Some(end) => end,
+        None => return Err(format_error("not an npz archive")),
+    };
+    let n_entries = read_u16(&bytes, end + 10);
+    let mut at = read_u32(&bytes, end + 16);
+
+    let mut arrays = Vec::with_capacity(n_entries);
+    for _ in 0..n_entries {
+        if at + 46 > bytes.len() || &bytes[at..at+4] != b"PK\x01\x02" {
+            return Err(format_error("corrupt npz directory"));
+        }
+        let method = read_u16(&bytes, at + 10);
+        let size = read_u32(&bytes, at + 20);
+        let name_len = read_u16(&bytes, at + 28);
+        let extra_len = read_u16(&bytes, at + 30);
+        let comment_len = read_u16(&bytes, at + 32);
+        let offset = read_u32(&bytes, at + 42);
+        if at + 46 + name_len > bytes.len() {
+            return Err(format_error("corrupt npz directory"));
+        }
+        let name = String::from_utf8_lossy(&bytes[at+46..at+46+name_len]).into_owned();
+        at += 46 + name_len + extra_len + comment_len;
+
+        if method != 0 {
+            return Err(format_error(format!("compressed npz entry `{}` is not supported", name)));
+        }
+        if offset + 30 > bytes.len() || &bytes[offset..offset+4] != b"PK\x03\x04" {
+            return Err(format_error(format!("corrupt npz entry `{}`", name)));
+        }
+        let start = offset + 30 + read_u16(&bytes, offset + 26) + read_u16(&bytes, offset + 28);
+        if start + size > bytes.len() {
+            return Err(format_error(format!("truncated npz entry `{}`", name)));
+        }
+
+        let matrix = try!(read_matrix(&bytes[start..start+size]));
+        let name = if name.ends_with(".npy") { name[..name.len()-4].to_string() } else { name };
+        arrays.push((name, matrix));
+    }
+    Ok(arrays)
+}
+
+/// Writes named matrices to an uncompressed `.npz` archive, readable by `numpy.load`.
+pub fn write_npz<T: Element, W: Write>(writer: &mut W, arrays: &[(&str, &Matrix<T>)]) -> Result<(), Error> {
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+
+    for &(name, matrix) in arrays {
+        let mut data = Vec::new();
+        try!(write_matrix(&mut data, matrix));
+        let name = format!("{}.npy", name);
+        let crc = crc32(&data) as u64;
+        let offset = archive.len() as u64;
+
+        // Local file header
+        write_le(0x04034b50, 4, &mut archive);
+        write_le(20, 2, &mut archive);
+        write_le(0, 2, &mut archive);
+        write_le(0, 2, &mut archive);
+        write_le(0, 2, &mut archive);
+        write_le(0x21, 2, &mut archive);
+        write_le(crc, 4, &mut archive);
+        write_le(data.len() as u64, 4, &mut archive);
+        write_le(data.len() as u64, 4, &mut archive);
+        write_le(name.len() as u64, 2, &mut archive);
+        write_le(0, 2, &mut archive);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(&data);
+
+        // Central directory entry
+        write_le(0x02014b50, 4, &mut directory);
+        write_le(20, 2, &mut directory);
+        write_le(20, 2, &mut directory);
+        write_le(0, 2, &mut directory);
+        write_le(0, 2, &mut directory);
+        write_le(0, 2, &mut directory);
+        write_le(0x21, 2, &mut directory);
+        write_le(crc, 4, &mut directory);
+        write_le(data.len() as u64, 4, &mut directory);
+        write_le(data.len() as u64, 4, &mut directory);
+        write_le(name.len() as u64, 2, &mut directory);
+        for _ in 0..4 {
+            write_le(0, 2, &mut directory);
+        }
+        write_le(0, 4, &mut directory);
+        write_le(offset, 4, &mut directory);
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = archive.len() as u64;
+    let directory_len = directory.len() as u64;
+    archive.extend_from_slice(&directory);
+    write_le(0x06054b50, 4, &mut archive);
+    write_le(0, 2, &mut archive);
+    write_le(0, 2, &mut archive);
+    write_le(arrays.len() as u64, 2, &mut archive);
+    write_le(arrays.len() as u64, 2, &mut archive);
+    write_le(directory_len, 4, &mut archive);
+    write_le(directory_offset, 4, &mut archive);
+    write_le(0, 2, &mut archive);
+
+    try!(writer.write_all(&archive));
+    Ok(())
+}
+
+#[test]
+fn test_npy_matrix() {
+    let matrix = Matrix::new(3, 2, |x,y| (10 * y + x) as f64);
+
+    let mut bytes = Vec::new();
+    write_matrix(&mut bytes, &matrix).unwrap();
+    // The data starts on a multiple of 64 bytes.
+    assert_eq!((bytes.len() - 6 * 8) % 64, 0);
+    assert_eq!(read_matrix::<f64,_>(&bytes[..]).unwrap(), matrix);
+    // Converted on reading.
+    assert_eq!(read_matrix::<f32,_>(&bytes[..]).unwrap(), Matrix::new(3, 2, |x,y| (10 * y + x) as f32));
+}
+
+#[test]
+fn test_npy_formats() {
+    // Big-endian int32 in C order, as written by NumPy: [[1, 2, 3], [4, 5, -6]]
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"\x93NUMPY\x01\x00\x76\x00");
+    let mut dict = "{'descr': '>i4', 'fortran_order': False, 'shape': (2, 3), }".to_string();
+    while dict.len() < 0x75 { dict.push(' '); }
+    dict.push('\n');
+    bytes.extend_from_slice(dict.as_bytes());
+    for &v in [1i32, 2, 3, 4, 5, -6].iter() {
+        let v = v as u32;
+        bytes.extend_from_slice(&[(v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8]);
+    }
+
+    let matrix: Matrix<i64> = read_matrix(&bytes[..]).unwrap();
+    assert_eq!((matrix.n, matrix.m), (3, 2));
+    assert_eq!(matrix.row(1), Vector::from_slice(&[4i64, 5, -6]));
+
+    match read_vector::<f64,_>(&bytes[..]) {
+        Err(Error::Format(_)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let vector = Vector::from_slice(&[1.5f32, -2f32]);
+    let mut bytes = Vec::new();
+    write_vector(&mut bytes, &vector).unwrap();
+    assert_eq!(read_vector::<f32,_>(&bytes[..]).unwrap(), vector);
+}
+
+#[test]
+fn test_npz() {
+    let a = Matrix::new(2, 3, |x,y| (x + y) as f64);
+    let b = Matrix::from_col(&Vector::from_slice(&[1f64, 2f64]));
+
+    let mut bytes = Vec::new();
+    write_npz(&mut bytes, &[("a", &a), ("b", &b)]).unwrap();
+
+    let arrays = read_npz::<f64,_>(&bytes[..]).unwrap();
+    assert_eq!(arrays, vec![("a".to_string(), a), ("b".to_string(), b)]);
+}
+
+#[test]
+fn test_npy_malformed() {
+    // Header ending right after the descr key
+    let dict = b"{'descr':";
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+    bytes.extend_from_slice(&[dict.len() as u8, 0]);
+    bytes.extend_from_slice(dict);
+    match read_matrix::<f64,_>(&bytes[..]) {
+        Err(Error::Format(_)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // Shapes whose size overflows, or which is much larger than the data
+    for shape in ["(4611686018427387904, 4)", "(1099511627776, 1)"].iter() {
+        let dict = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': {}, }}", shape);
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+        bytes.extend_from_slice(&[dict.len() as u8, 0]);
+        bytes.extend_from_slice(dict.as_bytes());
+        bytes.extend_from_slice(&[0u8; 16]);
+        match read_matrix::<f64,_>(&bytes[..]) {
+            Err(Error::Format(_)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    // Directory entry whose name runs past the end of the archive
+    let mut bytes = Vec::new();
+    write_npz(&mut bytes, &[("a", &Matrix::new(1, 1, |_,_| 1f64))]).unwrap();
+    let end = bytes.len() - 22;
+    let at = read_u32(&bytes, end + 16);
+    bytes[at + 28] = 0xff;
+    bytes[at + 29] = 0xff;
+    match read_npz::<f64,_>(&bytes[..]) {
+        Err(Error::Format(_)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
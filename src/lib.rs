@@ -3,6 +3,12 @@
 
 extern crate num;
 extern crate rand;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
 pub mod alg;
 pub mod ml;
+pub mod data;
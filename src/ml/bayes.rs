@@ -1,22 +1,138 @@
+use std::f64::consts::PI;
+
 use alg::Vector;
 use ml::Classifier;
+use ml::encoding::Category;
 
+/// Naive Bayes classifier over mixed features.
+///
+/// `Value::Double` features are modelled by a normal distribution per class, and
+/// `Value::Integer` and `Value::Boolean` features by a categorical distribution
+/// with Laplace smoothing. Missing values are left out of the likelihood.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
 pub struct NaiveBayes {
     k: usize,
+
+    log_priors: Vec<f64>,
+    features: Vec<Feature>,
 }
 
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
 pub enum Value {
     Double(f32),
     Integer(i32),
     Boolean(bool),
+    /// A value that was not observed.
+    Missing,
+}
+
+// Model of a single feature for each class.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+enum Feature {
+    // Not observed in any training sample
+    Unused,
+    Gaussian {
+        means: Vec<f64>,
+        variances: Vec<f64>,
+    },
+    Categorical {
+        categories: Vec<Category>,
+        // Smoothed log-probability of each category, for each class
+        log_probabilities: Vec<Vec<f64>>,
+    },
+}
+
+impl Feature {
+    fn fit(samples: &[Vector<Value>], labels: &[usize], k: usize, j: usize) -> Feature {
+        let observed: Vec<(&Value, usize)> = samples.iter().zip(labels.iter())
+            .map(|(x,&y)| (&x[j], y))
+            .filter(|&(v,_)| *v != Value::Missing)
+            .collect();
+
+        match observed.first() {
+            None => Feature::Unused,
+            Some(&(&Value::Double(_), _)) => {
+                let mut counts = vec![0f64; k];
+                let mut sums = vec![0f64; k];
+                let mut sums_sq = vec![0f64; k];
+                for &(v,y) in observed.iter() {
+                    if let Value::Double(x) = *v {
+                        let x = x as f64;
+                        counts[y] += 1f64;
+                        sums[y] += x;
+                        sums_sq[y] += x * x;
+                    }
+                }
+
+                let means: Vec<f64> = (0..k).map(|c| if counts[c] > 0f64 { sums[c] / counts[c] } else { 0f64 }).collect();
+                // Keep variances away from zero for constant features.
+                let variances = (0..k).map(|c| {
+                    let variance = if counts[c] > 0f64 { sums_sq[c] / counts[c] - means[c] * means[c] } else { 1f64 };
+                    variance.max(0f64) + 1e-9
+                }).collect();
+                Feature::Gaussian { means: means, variances: variances }
+            },
+            Some(_) => {
+                let mut categories: Vec<Category> = observed.iter().filter_map(|&(v,_)| Category::of(v)).collect();
+                categories.sort();
+                categories.dedup();
+
+                let mut counts = vec![vec![0f64; categories.len()]; k];
+                for &(v,y) in observed.iter() {
+                    if let Some(i) = Category::of(v).and_then(|c| categories.binary_search(&c).ok()) {
+                        counts[y][i] += 1f64;
+                    }
+                }
+
+                let log_probabilities = counts.iter().map(|counts| {
+                    let total = counts.iter().fold(0f64, |a,b| a+b) + counts.len() as f64;
+                    counts.iter().map(|&c| ((c + 1f64) / total).ln()).collect()
+                }).collect();
+                Feature::Categorical { categories: categories, log_probabilities: log_probabilities }
+            },
+        }
+    }
+
+    // Log-likelihood of the value for the given class, zero if it can't be scored.
+    fn log_likelihood(&self, value: &Value, class: usize) -> f64 {
+        match (self, *value) {
+            (&Feature::Gaussian { ref means, ref variances }, Value::Double(x)) => {
+                let d = x as f64 - means[class];
+                -0.5f64 * ((2f64 * PI * variances[class]).ln() + d * d / variances[class])
+            },
+            (&Feature::Categorical { ref categories, ref log_probabilities }, _) => {
+                // Unknown categories are skipped like missing values.
+                match Category::of(value).and_then(|c| categories.binary_search(&c).ok()) {
+                    Some(i) => log_probabilities[class][i],
+                    None => 0f64,
+                }
+            },
+            _ => 0f64,
+        }
+    }
 }
 
 impl NaiveBayes {
     pub fn new(k: usize) -> Self {
         NaiveBayes {
             k: k,
+
+            log_priors: Vec::new(),
+            features: Vec::new(),
         }
     }
+
+    /// Returns the log-probability of each class given the input, up to a constant.
+    pub fn joint_log_likelihood(&self, input: &Vector<Value>) -> Vec<f64> {
+        (0..self.k).map(|c| {
+            self.features.iter().enumerate()
+                .map(|(j,feature)| feature.log_likelihood(&input[j], c))
+                .fold(self.log_priors[c], |a,b| a+b)
+        }).collect()
+    }
 }
 
 impl Classifier for NaiveBayes {
@@ -24,9 +140,40 @@ impl Classifier for NaiveBayes {
     type Label = usize;
 
     fn train(&mut self, samples: &[Vector<Value>], labels: &[usize]) {
+        if samples.is_empty() { return; }
+
+        let mut counts = vec![0f64; self.k];
+        for &y in labels.iter() {
+            counts[y] += 1f64;
+        }
+        let n = labels.len() as f64;
+        self.log_priors = counts.iter().map(|&c| (c / n).ln()).collect();
+        self.features = (0..samples[0].dim()).map(|j| Feature::fit(samples, labels, self.k, j)).collect();
     }
 
     fn classify(&self, input: &Vector<Value>) -> usize {
-        0
+        let scores = self.joint_log_likelihood(input);
+        (0..self.k).fold(0, |a,c| if scores[c] > scores[a] { c } else { a })
     }
 }
+
+#[test]
+fn test_naive_bayes() {
+    let samples: Vec<Vector<Value>> = vec![
+        Vector::from_vec(vec![Value::Double(1.0f32), Value::Boolean(true)]),
+        Vector::from_vec(vec![Value::Double(1.2f32), Value::Missing]),
+        Vector::from_vec(vec![Value::Missing, Value::Boolean(true)]),
+        Vector::from_vec(vec![Value::Double(5.0f32), Value::Boolean(false)]),
+        Vector::from_vec(vec![Value::Double(5.4f32), Value::Boolean(false)]),
+        Vector::from_vec(vec![Value::Double(4.8f32), Value::Missing]),
+    ];
+    let labels = [0, 0, 0, 1, 1, 1];
+
+    let mut bayes = NaiveBayes::new(2);
+    bayes.train(&samples, &labels);
+
+    assert_eq!(bayes.classify(&Vector::from_vec(vec![Value::Double(1.1f32), Value::Boolean(true)])), 0);
+    assert_eq!(bayes.classify(&Vector::from_vec(vec![Value::Double(5.1f32), Value::Missing])), 1);
+    assert_eq!(bayes.classify(&Vector::from_vec(vec![Value::Missing, Value::Boolean(true)])), 0);
+    assert_eq!(bayes.classify(&Vector::from_vec(vec![Value::Missing, Value::Boolean(false)])), 1);
+}
@@ -2,6 +2,8 @@ use ml::Classifier;
 
 use num::Float;
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
 pub struct Binary<T:Float, C: Classifier<Label=T>>
 {
     inner: C,
@@ -0,0 +1,635 @@
+use std::cmp::Ordering;
+
+use num::Float;
+use rand::{thread_rng,Rng,SeedableRng,StdRng};
+
+use ml::Classifier;
+use ml::tree::DecisionTree;
+use alg::Vector;
+
+/// A differentiable loss function for gradient boosting.
+///
+/// The model predicts `n_outputs()` raw scores per sample, which the loss
+/// compares to the label.
+pub trait Loss {
+    type Label;
+
+    /// Number of raw scores per sample. One tree is trained per score at each stage.
+    fn n_outputs(&self) -> usize;
+
+    /// Returns the constant initial scores minimizing the loss over the labels.
+    fn init(&self, labels: &[Self::Label]) -> Vec<f64>;
+
+    /// Returns the loss for the given scores.
+    fn loss(&self, scores: &[f64], label: &Self::Label) -> f64;
+
+    /// Returns the negative gradient of the loss relative to the score `k`.
+    fn negative_gradient(&self, scores: &[f64], label: &Self::Label, k: usize) -> f64;
+
+    /// Returns the update to apply to the score `k` of every sample in a leaf.
+    fn leaf_value(&self, samples: &[(&[f64], &Self::Label)], k: usize) -> f64;
+
+    /// Converts raw scores to a label.
+    fn predict(&self, scores: &[f64]) -> Self::Label;
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() { return 0f64; }
+    values.iter().fold(0f64, |a,&b| a+b) / values.len() as f64
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    if values.is_empty() { return 0f64; }
+    // NaN values rank last, keeping the order total
+    values.sort_by(|a,b| match (a.is_nan(), b.is_nan()) {
+        (false, false) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+        (x, y) => x.cmp(&y),
+    });
+    let n = values.len();
+    if n % 2 == 1 {
+        values[n/2]
+    } else {
+        (values[n/2 - 1] + values[n/2]) / 2f64
+    }
+}
+
+fn sigmoid(t: f64) -> f64 {
+    1f64 / (1f64 + (-t).exp())
+}
+
+/// Least squares regression loss: `(y - f)² / 2`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct SquaredLoss;
+
+impl Loss for SquaredLoss {
+    type Label = f64;
+
+    fn n_outputs(&self) -> usize { 1 }
+
+    fn init(&self, labels: &[f64]) -> Vec<f64> {
+        vec![mean(labels)]
+    }
+
+    fn loss(&self, scores: &[f64], label: &f64) -> f64 {
+        let d = label - scores[0];
+        d * d / 2f64
+    }
+
+    fn negative_gradient(&self, scores: &[f64], label: &f64, _: usize) -> f64 {
+        label - scores[0]
+    }
+
+    fn leaf_value(&self, samples: &[(&[f64], &f64)], _: usize) -> f64 {
+        let residuals: Vec<f64> = samples.iter().map(|&(s,y)| y - s[0]).collect();
+        mean(&residuals)
+    }
+
+    fn predict(&self, scores: &[f64]) -> f64 {
+        scores[0]
+    }
+}
+
+/// Least absolute deviation regression loss: `|y - f|`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct AbsoluteLoss;
+
+impl Loss for AbsoluteLoss {
+    type Label = f64;
+
+    fn n_outputs(&self) -> usize { 1 }
+
+    fn init(&self, labels: &[f64]) -> Vec<f64> {
+        vec![median(Vec::from(labels))]
+    }
+
+    fn loss(&self, scores: &[f64], label: &f64) -> f64 {
+        (label - scores[0]).abs()
+    }
+
+    fn negative_gradient(&self, scores: &[f64], label: &f64, _: usize) -> f64 {
+        (label - scores[0]).signum()
+    }
+
+    fn leaf_value(&self, samples: &[(&[f64], &f64)], _: usize) -> f64 {
+        median(samples.iter().map(|&(s,y)| y - s[0]).collect())
+    }
+
+    fn predict(&self, scores: &[f64]) -> f64 {
+        scores[0]
+    }
+}
+
+/// Huber regression loss: quadratic for residuals below `delta`, linear above.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct HuberLoss {
+    delta: f64,
+}
+
+impl HuberLoss {
+    /// Creates a new Huber loss with the given transition point.
+    pub fn new(delta: f64) -> Self {
+        HuberLoss {
+            delta: delta,
+        }
+    }
+}
+
+impl Loss for HuberLoss {
+    type Label = f64;
+
+    fn n_outputs(&self) -> usize { 1 }
+
+    fn init(&self, labels: &[f64]) -> Vec<f64> {
+        vec![median(Vec::from(labels))]
+    }
+
+    fn loss(&self, scores: &[f64], label: &f64) -> f64 {
+        let d = (label - scores[0]).abs();
+        if d <= self.delta {
+            d * d / 2f64
+        } else {
+            self.delta * (d - self.delta / 2f64)
+        }
+    }
+
+    fn negative_gradient(&self, scores: &[f64], label: &f64, _: usize) -> f64 {
+        let d = label - scores[0];
+        if d.abs() <= self.delta { d } else { self.delta * d.signum() }
+    }
+
+    fn leaf_value(&self, samples: &[(&[f64], &f64)], _: usize) -> f64 {
+        // One step from the median towards the Huber M-estimate (Friedman, 2001).
+        let residuals: Vec<f64> = samples.iter().map(|&(s,y)| y - s[0]).collect();
+        let med = median(residuals.clone());
+        let corrections: Vec<f64> = residuals.iter()
+            .map(|r| (r - med).signum() * (r - med).abs().min(self.delta))
+            .collect();
+        med + mean(&corrections)
+    }
+
+    fn predict(&self, scores: &[f64]) -> f64 {
+        scores[0]
+    }
+}
+
+/// Binary classification log-loss, on the log-odds of the positive class.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct LogLoss;
+
+impl Loss for LogLoss {
+    type Label = bool;
+
+    fn n_outputs(&self) -> usize { 1 }
+
+    fn init(&self, labels: &[bool]) -> Vec<f64> {
+        let n = labels.len() as f64;
+        let positives = labels.iter().filter(|&&b| b).count() as f64;
+        // Smooth the prior so it stays finite for single-class data
+        let p = (positives + 0.5) / (n + 1f64);
+        vec![(p / (1f64 - p)).ln()]
+    }
+
+    fn loss(&self, scores: &[f64], label: &bool) -> f64 {
+        // log(1 + exp(-f)) for positives, log(1 + exp(f)) for negatives
+        let f = if *label { -scores[0] } else { scores[0] };
+        f.max(0f64) + (-f.abs()).exp().ln_1p()
+    }
+
+    fn negative_gradient(&self, scores: &[f64], label: &bool, _: usize) -> f64 {
+        let y = if *label { 1f64 } else { 0f64 };
+        y - sigmoid(scores[0])
+    }
+
+    fn leaf_value(&self, samples: &[(&[f64], &bool)], _: usize) -> f64 {
+        // Single Newton-Raphson step
+        let mut num = 0f64;
+        let mut den = 0f64;
+        for &(s,&y) in samples {
+            let p = sigmoid(s[0]);
+            num += if y { 1f64 } else { 0f64 } - p;
+            den += p * (1f64 - p);
+        }
+        if den < 1e-12 { 0f64 } else { num / den }
+    }
+
+    fn predict(&self, scores: &[f64]) -> bool {
+        scores[0] >= 0f64
+    }
+}
+
+/// Multiclass log-loss on the softmax of one score per class.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct MultinomialLoss {
+    n_classes: usize,
+}
+
+impl MultinomialLoss {
+    /// Creates a new multiclass loss. Labels must be in `0..n_classes`.
+    pub fn new(n_classes: usize) -> Self {
+        MultinomialLoss {
+            n_classes: n_classes,
+        }
+    }
+
+    fn softmax(scores: &[f64]) -> Vec<f64> {
+        let max = scores.iter().fold(::std::f64::NEG_INFINITY, |a,&b| a.max(b));
+        let exps: Vec<f64> = scores.iter().map(|s| (s - max).exp()).collect();
+        let sum = exps.iter().fold(0f64, |a,b| a+b);
+        exps.into_iter().map(|e| e / sum).collect()
+    }
+}
+
+impl Loss for MultinomialLoss {
+    type Label = usize;
+
+    fn n_outputs(&self) -> usize { self.n_classes }
+
+    fn init(&self, labels: &[usize]) -> Vec<f64> {
+        let mut counts = vec![0.5f64; self.n_classes];
+        for &y in labels {
+            counts[y] += 1f64;
+        }
+        let total = counts.iter().fold(0f64, |a,b| a+b);
+        counts.into_iter().map(|c| (c / total).ln()).collect()
+    }
+
+    fn loss(&self, scores: &[f64], label: &usize) -> f64 {
+        -MultinomialLoss::softmax(scores)[*label].max(1e-300).ln()
+    }
+
+    fn negative_gradient(&self, scores: &[f64], label: &usize, k: usize) -> f64 {
+        let y = if *label == k { 1f64 } else { 0f64 };
+        y - MultinomialLoss::softmax(scores)[k]
+    }
+
+    fn leaf_value(&self, samples: &[(&[f64], &usize)], k: usize) -> f64 {
+        let mut num = 0f64;
+        let mut den = 0f64;
+        for &(s,&y) in samples {
+            let p = MultinomialLoss::softmax(s)[k];
+            num += if y == k { 1f64 } else { 0f64 } - p;
+            den += p * (1f64 - p);
+        }
+        if den < 1e-12 { return 0f64; }
+        let k = self.n_classes as f64;
+        (k - 1f64) / k * num / den
+    }
+
+    fn predict(&self, scores: &[f64]) -> usize {
+        let mut best = 0;
+        for (i,&s) in scores.iter().enumerate() {
+            if s > scores[best] { best = i; }
+        }
+        best
+    }
+}
+
+/// Gradient-boosted regression trees.
+///
+/// Each stage fits one shallow tree per output to the negative gradient of
+/// the loss, then sets each leaf to the loss-specific optimal update.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct GradientBoosting<T, L: Loss> {
+    loss: L,
+    n_estimators: usize,
+    learning_rate: f64,
+    max_depth: usize,
+    min_samples_split: usize,
+    subsample: f64,
+    // (validation fraction, patience)
+    early_stopping: Option<(f64, usize)>,
+    seed: Option<usize>,
+
+    init: Vec<f64>,
+    // One tree per output at each stage
+    stages: Vec<Vec<DecisionTree<T,f64>>>,
+    train_loss: Vec<f64>,
+    validation_loss: Vec<f64>,
+}
+
+impl <T: Float, L: Loss> GradientBoosting<T,L> {
+    /// Creates a new untrained model.
+    ///
+    /// - `loss`: the loss to minimize.
+    /// - `n_estimators`: maximum number of boosting stages.
+    /// - `learning_rate`: shrinkage applied to each stage.
+    pub fn new(loss: L, n_estimators: usize, learning_rate: f64) -> Self {
+        GradientBoosting {
+            loss: loss,
+            n_estimators: n_estimators,
+            learning_rate: learning_rate,
+            max_depth: 3,
+            min_samples_split: 2,
+            subsample: 1f64,
+            early_stopping: None,
+            seed: None,
+
+            init: Vec::new(),
+            stages: Vec::new(),
+            train_loss: Vec::new(),
+            validation_loss: Vec::new(),
+        }
+    }
+
+    /// Sets the depth of each tree. Defaults to 3.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the minimum number of samples required to split a node.
+    pub fn with_min_samples_split(mut self, n: usize) -> Self {
+        self.min_samples_split = n;
+        self
+    }
+
+    /// Trains each stage on a random fraction of the samples, drawn without replacement.
+    ///
+    /// Defaults to 1, using every sample.
+    pub fn with_subsample(mut self, fraction: f64) -> Self {
+        self.subsample = fraction;
+        self
+    }
+
+    /// Holds out a random `fraction` of the training samples as a validation set,
+    /// and stops when the validation loss did not improve for `patience` stages.
+    ///
+    /// Only the stages up to the best validation loss are kept.
+    /// Panics unless `0 < fraction < 1`.
+    pub fn with_early_stopping(mut self, fraction: f64, patience: usize) -> Self {
+        if !(fraction > 0f64 && fraction < 1f64) {
+            panic!("GradientBoosting early stopping needs a validation fraction between 0 and 1.");
+        }
+        self.early_stopping = Some((fraction, patience));
+        self
+    }
+
+    /// Seeds the random number generator, making training reproducible.
+    pub fn with_seed(mut self, seed: usize) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns the number of stages in the trained model.
+    pub fn n_stages(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Returns the mean training loss after each stage.
+    pub fn train_loss(&self) -> &[f64] {
+        &self.train_loss
+    }
+
+    /// Returns the mean validation loss after each stage.
+    ///
+    /// Empty unless a validation set was used.
+    pub fn validation_loss(&self) -> &[f64] {
+        &self.validation_loss
+    }
+
+    /// Returns the raw scores for the given input.
+    pub fn decision_function(&self, input: &Vector<T>) -> Vec<f64> {
+        let mut scores = self.init.clone();
+        for stage in self.stages.iter() {
+            self.apply_stage(stage, input, &mut scores);
+        }
+        scores
+    }
+
+    fn apply_stage(&self, stage: &[DecisionTree<T,f64>], input: &Vector<T>, scores: &mut [f64]) {
+        for (score,tree) in scores.iter_mut().zip(stage.iter()) {
+            *score += self.learning_rate * tree.classify(input);
+        }
+    }
+
+    fn mean_loss(&self, scores: &[Vec<f64>], labels: &[L::Label]) -> f64 {
+        let total = scores.iter().zip(labels.iter()).map(|(s,y)| self.loss.loss(s, y)).fold(0f64, |a,b| a+b);
+        total / labels.len() as f64
+    }
+
+    /// Trains the model, using the given validation set for early stopping.
+    ///
+    /// Stops when the validation loss did not improve for `patience` stages,
+    /// and only keeps the stages up to the best validation loss.
+    /// Panics if the validation set is empty, or doesn't have one label per sample.
+    pub fn train_with_validation(&mut self, samples: &[Vector<T>], labels: &[L::Label],
+                                 valid_samples: &[Vector<T>], valid_labels: &[L::Label], patience: usize) {
+        if valid_samples.is_empty() {
+            panic!("GradientBoosting needs at least one validation sample.");
+        }
+        if valid_samples.len() != valid_labels.len() {
+            panic!("GradientBoosting needs one label per validation sample.");
+        }
+        let seed = match self.seed {
+            Some(seed) => seed,
+            None => thread_rng().gen(),
+        };
+        let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+        self.fit(samples, labels, Some((valid_samples, valid_labels, patience)), &mut rng);
+    }
+
+    fn fit<R: Rng>(&mut self, samples: &[Vector<T>], labels: &[L::Label],
+                   validation: Option<(&[Vector<T>], &[L::Label], usize)>, rng: &mut R) {
+        self.stages.clear();
+        self.train_loss.clear();
+        self.validation_loss.clear();
+        if samples.is_empty() { return; }
+
+        let n = samples.len();
+        let n_outputs = self.loss.n_outputs();
+        self.init = self.loss.init(labels);
+
+        let mut scores: Vec<Vec<f64>> = vec![self.init.clone(); n];
+        let mut valid_scores: Vec<Vec<f64>> = match validation {
+            None => Vec::new(),
+            Some((ref valid_samples, _, _)) => vec![self.init.clone(); valid_samples.len()],
+        };
+        let mut best = (::std::f64::INFINITY, 0);
+
+        let n_sub = ((n as f64 * self.subsample).round() as usize).max(1).min(n);
+        let mut indices: Vec<usize> = (0..n).collect();
+
+        for _ in 0..self.n_estimators {
+            if n_sub < n {
+                rng.shuffle(&mut indices);
+            }
+            let subset = &indices[..n_sub];
+
+            let mut stage = Vec::with_capacity(n_outputs);
+            for k in 0..n_outputs {
+                let residuals: Vec<f64> = scores.iter().zip(labels.iter())
+                    .map(|(s,y)| self.loss.negative_gradient(s, y, k))
+                    .collect();
+
+                let mut tree = DecisionTree::new(self.max_depth, self.min_samples_split);
+                tree.train_on(samples, &residuals, subset, rng);
+
+                // Replace the mean residual in each leaf with the loss-specific update.
+                let members: Vec<Vec<(&[f64], &L::Label)>> = tree.leaf_members(samples, subset).into_iter()
+                    .map(|leaf| leaf.into_iter().map(|i| (&scores[i][..], &labels[i])).collect())
+                    .collect();
+                for (leaf,samples) in tree.leaves_mut().into_iter().zip(members.iter()) {
+                    *leaf = self.loss.leaf_value(samples, k);
+                }
+
+                stage.push(tree);
+            }
+
+            // Update the scores only once every output's tree is trained.
+            for (sample,s) in samples.iter().zip(scores.iter_mut()) {
+                self.apply_stage(&stage, sample, s);
+            }
+            let train_loss = self.mean_loss(&scores, labels);
+            self.train_loss.push(train_loss);
+
+            if let Some((valid_samples, valid_labels, patience)) = validation {
+                for (sample,s) in valid_samples.iter().zip(valid_scores.iter_mut()) {
+                    self.apply_stage(&stage, sample, s);
+                }
+                self.stages.push(stage);
+
+                let valid_loss = self.mean_loss(&valid_scores, valid_labels);
+                self.validation_loss.push(valid_loss);
+                if valid_loss < best.0 {
+                    best = (valid_loss, self.stages.len());
+                } else if self.stages.len() - best.1 >= patience {
+                    break;
+                }
+            } else {
+                self.stages.push(stage);
+            }
+        }
+
+        if validation.is_some() {
+            self.stages.truncate(best.1);
+        }
+    }
+}
+
+impl <T: Float, L: Loss> Classifier for GradientBoosting<T,L>
+    where L::Label: Clone
+{
+    type Input = Vector<T>;
+    type Label = L::Label;
+
+    fn train(&mut self, samples: &[Vector<T>], labels: &[L::Label]) {
+        let seed = match self.seed {
+            Some(seed) => seed,
+            None => thread_rng().gen(),
+        };
+        let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+
+        match self.early_stopping {
+            None => self.fit(samples, labels, None, &mut rng),
+            Some((fraction, patience)) => {
+                if samples.len() < 2 {
+                    panic!("GradientBoosting early stopping needs at least 2 samples.");
+                }
+                let mut indices: Vec<usize> = (0..samples.len()).collect();
+                rng.shuffle(&mut indices);
+                // Keep at least one sample on each side
+                let n_valid = ((samples.len() as f64 * fraction).round() as usize).max(1).min(samples.len() - 1);
+                let (valid, train) = indices.split_at(n_valid);
+
+                let train_samples: Vec<Vector<T>> = train.iter().map(|&i| samples[i].clone()).collect();
+                let train_labels: Vec<L::Label> = train.iter().map(|&i| labels[i].clone()).collect();
+                let valid_samples: Vec<Vector<T>> = valid.iter().map(|&i| samples[i].clone()).collect();
+                let valid_labels: Vec<L::Label> = valid.iter().map(|&i| labels[i].clone()).collect();
+
+                self.fit(&train_samples, &train_labels, Some((&valid_samples, &valid_labels, patience)), &mut rng);
+            }
+        }
+    }
+
+    fn classify(&self, input: &Vector<T>) -> L::Label {
+        self.loss.predict(&self.decision_function(input))
+    }
+}
+
+#[test]
+fn test_boosting_regression() {
+    let samples: Vec<Vector<f64>> = (0..20).map(|i| Vector::from_slice(&[i as f64])).collect();
+    let labels: Vec<f64> = (0..20).map(|i| if i < 10 { 1f64 } else { 5f64 }).collect();
+
+    let mut squared = GradientBoosting::new(SquaredLoss, 50, 0.3).with_max_depth(1).with_seed(1);
+    squared.train(&samples, &labels);
+    assert!((squared.classify(&Vector::from_slice(&[2f64])) - 1f64).abs() < 0.01);
+    assert!((squared.classify(&Vector::from_slice(&[15f64])) - 5f64).abs() < 0.01);
+
+    let mut absolute = GradientBoosting::new(AbsoluteLoss, 50, 0.3).with_max_depth(1).with_seed(1);
+    absolute.train(&samples, &labels);
+    assert!((absolute.classify(&Vector::from_slice(&[15f64])) - 5f64).abs() < 0.01);
+
+    let mut huber = GradientBoosting::new(HuberLoss::new(1f64), 50, 0.3).with_max_depth(1).with_seed(1);
+    huber.train(&samples, &labels);
+    assert!((huber.classify(&Vector::from_slice(&[2f64])) - 1f64).abs() < 0.01);
+
+    // The training loss should never increase for least squares.
+    for pair in squared.train_loss().windows(2) {
+        assert!(pair[1] <= pair[0] + 1e-12);
+    }
+}
+
+#[test]
+fn test_boosting_classification() {
+    let samples: Vec<Vector<f64>> = (0..30).map(|i| Vector::from_slice(&[i as f64, (i % 4) as f64])).collect();
+
+    let binary: Vec<bool> = (0..30).map(|i| i >= 15).collect();
+    let mut model = GradientBoosting::new(LogLoss, 20, 0.5).with_subsample(0.8).with_seed(3);
+    model.train(&samples, &binary);
+    assert_eq!(model.classify(&Vector::from_slice(&[3f64, 1f64])), false);
+    assert_eq!(model.classify(&Vector::from_slice(&[25f64, 1f64])), true);
+
+    let classes: Vec<usize> = (0..30).map(|i| i / 10).collect();
+    let mut model = GradientBoosting::new(MultinomialLoss::new(3), 20, 0.5).with_seed(3);
+    model.train(&samples, &classes);
+    assert_eq!(model.decision_function(&Vector::from_slice(&[0f64, 0f64])).len(), 3);
+    for i in 0..30 {
+        assert_eq!(model.classify(&samples[i]), classes[i]);
+    }
+}
+
+#[test]
+fn test_boosting_early_stopping() {
+    let samples: Vec<Vector<f64>> = (0..40).map(|i| Vector::from_slice(&[i as f64])).collect();
+    let labels: Vec<f64> = (0..40).map(|i| (i / 10) as f64).collect();
+
+    let mut model = GradientBoosting::new(SquaredLoss, 500, 0.5).with_early_stopping(0.25, 5).with_seed(5);
+    model.train(&samples, &labels);
+
+    assert!(model.n_stages() < 500);
+    assert!(model.validation_loss().len() < 500);
+}
+
+#[test]
+fn test_boosting_early_stopping_small_fraction() {
+    let samples: Vec<Vector<f64>> = (0..10).map(|i| Vector::from_slice(&[i as f64])).collect();
+    let labels: Vec<f64> = (0..10).map(|i| (i / 5) as f64).collect();
+
+    // Rounds to no validation sample, so one is held out anyway.
+    let mut model = GradientBoosting::new(SquaredLoss, 20, 0.5).with_early_stopping(0.01, 3).with_seed(2);
+    model.train(&samples, &labels);
+
+    assert!(model.n_stages() > 0);
+    assert!(model.validation_loss().iter().all(|l| l.is_finite()));
+}
+
+#[test]
+#[should_panic]
+fn test_boosting_early_stopping_fraction() {
+    GradientBoosting::<f64,_>::new(SquaredLoss, 20, 0.5).with_early_stopping(1.5, 3);
+}
+
+#[test]
+#[should_panic]
+fn test_boosting_empty_validation() {
+    let samples: Vec<Vector<f64>> = (0..4).map(|i| Vector::from_slice(&[i as f64])).collect();
+    let mut model = GradientBoosting::new(SquaredLoss, 5, 0.5);
+    model.train_with_validation(&samples, &[0f64, 0f64, 1f64, 1f64], &[], &[], 2);
+}
@@ -0,0 +1,6 @@
+pub trait Clusterer {
+    type Input;
+
+    fn fit(&mut self, samples: &[Self::Input]);
+    fn predict(&self, input: &Self::Input) -> usize;
+}
@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::marker::PhantomData;
 
 use rand::distributions::IndependentSample;
@@ -10,6 +11,8 @@ use ml::Optimizer;
 
 /// Covariance Matrix Adaptation Evolution Strategy is a general purpose
 /// black-box optimization algorithm.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
 pub struct CmaEs<T: Float> {
     // population size
     pop: usize,
@@ -53,96 +56,162 @@ struct CmaEsSlave<T: Float> {
     n: usize,
     pop: usize,
     weights: Vec<T>,
-
-    sigma: T,
+    // Variance effective selection mass
+    mueff: T,
+    // Learning rates of the paths, the covariance and the step size
+    cc: T,
+    cs: T,
+    c1: T,
+    cmu: T,
+    damps: T,
+    // Expected norm of a standard normal sample
+    chi_n: T,
 
     // These vary
-    population: Matrix<T>,
-    covariance: Matrix<T>,
+    sigma: T,
     mean: Vector<T>,
+    covariance: Matrix<T>,
+    // Evolution paths of the covariance and the step size
+    pc: Vector<T>,
+    ps: Vector<T>,
+    // Best sample so far with a defined score, with its score
+    best: Option<(Vector<T>, T)>,
 }
 
 impl <T: Float> CmaEsSlave<T> {
 
     fn new(n: usize, pop: usize) -> Self {
+        let weights = CmaEsSlave::<T>::compute_weights(pop);
+        let mueff = T::one() / weights.iter().fold(T::zero(), |a,&w| a + w*w);
+
+        let f = |x: f64| T::from(x).unwrap();
+        let nf = f(n as f64);
+        let two = f(2.0);
+        let cc = (f(4.0) + mueff / nf) / (nf + f(4.0) + two * mueff / nf);
+        let cs = (mueff + two) / (nf + mueff + f(5.0));
+        let c1 = two / ((nf + f(1.3)) * (nf + f(1.3)) + mueff);
+        let cmu = (T::one() - c1).min(two * (mueff - two + mueff.recip()) / ((nf + two) * (nf + two) + mueff));
+        let damps = T::one() + two * T::zero().max(((mueff - T::one()) / (nf + T::one())).sqrt() - T::one()) + cs;
+        let chi_n = nf.sqrt() * (T::one() - (f(4.0) * nf).recip() + (f(21.0) * nf * nf).recip());
+
         CmaEsSlave {
             n: n,
             pop: pop,
-            weights: Vec::new(),
-            sigma: T::one(),
+            weights: weights,
+            mueff: mueff,
+            cc: cc,
+            cs: cs,
+            c1: c1,
+            cmu: cmu,
+            damps: damps,
+            chi_n: chi_n,
 
-            population: Matrix::zero(pop, n),
-            covariance: Matrix::identity(n),
+            sigma: T::one(),
             mean: Vector::zero(n),
+            covariance: Matrix::identity(n),
+            pc: Vector::zero(n),
+            ps: Vector::zero(n),
+            best: None,
         }
     }
 
     fn optimize<F>(mut self, f: F, t: usize) -> Vector<T>
         where F: Fn(Vector<T>) -> T
     {
-        self.compute_weights();
+        let mut r = thread_rng();
+        let d = Normal::new(0f64, 1f64);
 
-        for _ in 0..t {
-            // Generate population
-            self.generate_population();
+        for generation in 0..t {
+            // Generate and score the population, best first
+            let population = self.generate_population(&mut r, &d);
+            let mut scored: Vec<(Vector<T>, Vector<T>, T)> = population.into_iter().map(|(z,y)| {
+                let x = &self.mean + &(&y * self.sigma);
+                let score = f(x.clone());
+                if !score.is_nan() && self.best.as_ref().map_or(true, |b| score < b.1) {
+                    self.best = Some((x, score));
+                }
+                (z, y, score)
+            }).collect();
+            // NaN scores rank last, keeping the order total
+            scored.sort_by(|a,b| match (a.2.is_nan(), b.2.is_nan()) {
+                (false, false) => a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal),
+                (x, y) => x.cmp(&y),
+            });
 
             // Adapt covariance and mean
-            self.adapt_covariance(&f);
+            self.adapt(&scored, generation);
         }
 
-        self.mean
-    }
-
-    fn compute_weights(&mut self) {
-        let mu = self.n / 2;
-        self.weights.reserve(mu);
-
-        let mut sum = T::zero();
-        let logmu = T::from(mu as f64 + 0.5).unwrap().log10();
-        for i in 0..mu {
-            let w = logmu - T::from(i+1).unwrap().log10();
-            self.weights.push(w);
-            sum = sum + w;
+        match self.best {
+            Some((x,_)) => x,
+            None => self.mean,
         }
+    }
 
-        for w in self.weights.iter_mut() {
-            *w = *w / sum;
-        }
+    // Log-decreasing weights of the best half of the population, summing to 1.
+    fn compute_weights(pop: usize) -> Vec<T> {
+        let mu = (pop / 2).max(1);
+        let logmu = T::from(mu as f64 + 0.5).unwrap().ln();
+        let weights: Vec<T> = (0..mu).map(|i| logmu - T::from(i+1).unwrap().ln()).collect();
+        let sum = weights.iter().fold(T::zero(), |a,&b| a+b);
+        weights.into_iter().map(|w| w / sum).collect()
     }
 
-    fn adapt_covariance<F>(&mut self, f: &F)
-        where F: Fn(Vector<T>) -> T
-    {
-        let scores: Vec<T> = (0..self.pop).map(|i| self.population.row(i)).map(|sample| f(sample)).collect();
-        // Maybe sort the population by its fitness?
+    fn adapt(&mut self, scored: &[(Vector<T>, Vector<T>, T)], generation: usize) {
+        let n = self.n;
+        let one = T::one();
+        let two = T::from(2).unwrap();
 
         // The mean is easy...
-        let mean = (0..self.pop)
-            .map(|i| self.population.row(i))
-            .zip(self.weights.iter())
-            .fold(Vector::zero(self.n), |a,(b,&w)| a+b*w);
-        let mean = mean / T::from(self.pop).unwrap();
-        self.mean = mean;
-
-        // Now, for the covariance...
-        
+        let yw = scored.iter().zip(self.weights.iter()).fold(Vector::zero(n), |a,(s,&w)| a + &s.1 * w);
+        let zw = scored.iter().zip(self.weights.iter()).fold(Vector::zero(n), |a,(s,&w)| a + &s.0 * w);
+        self.mean = &self.mean + &(&yw * self.sigma);
+
+        // Evolution paths, stalling the covariance one while the step size grows quickly
+        self.ps = &(&self.ps * (one - self.cs)) + &(&zw * (self.cs * (two - self.cs) * self.mueff).sqrt());
+        let ps_norm = self.ps.norm_sq().sqrt();
+        let decay = (one - (one - self.cs).powi(2 * (generation as i32 + 1))).sqrt();
+        let hsig = ps_norm / decay / self.chi_n < T::from(1.4).unwrap() + two / T::from(n + 1).unwrap();
+        let hsig = if hsig { one } else { T::zero() };
+        self.pc = &(&self.pc * (one - self.cc)) + &(&yw * (hsig * (self.cc * (two - self.cc) * self.mueff).sqrt()));
+
+        // Now, for the covariance: rank-one update from the path, rank-mu update from the best samples
+        let old = self.covariance.clone();
+        let (c1, cmu, cc) = (self.c1, self.cmu, self.cc);
+        let pc = &self.pc;
+        let weights = &self.weights;
+        self.covariance = Matrix::new(n, n, |x,y| {
+            let rank_mu = scored.iter().zip(weights.iter()).fold(T::zero(), |a,(s,&w)| a + w * s.1[x] * s.1[y]);
+            let rank_one = pc[x] * pc[y] + (one - hsig) * cc * (two - cc) * old[(x,y)];
+            (one - c1 - cmu) * old[(x,y)] + c1 * rank_one + cmu * rank_mu
+        });
+
+        self.sigma = self.sigma * ((self.cs / self.damps) * (ps_norm / self.chi_n - one)).exp();
     }
 
-    fn generate_population(&mut self) {
+    // Samples the population, as standard normal samples `z` and their correlated versions `y`.
+    fn generate_population<R: Rng, D: IndependentSample<f64>>(&self, r: &mut R, d: &D) -> Vec<(Vector<T>, Vector<T>)> {
         // Lower triangular cholesky decomposition
-        let L = self.covariance.cholesky();
+        let l = self.covariance.cholesky();
 
-        let mut r = thread_rng();
-        let d = Normal::new(0f64, 1f64);
-
-        for i in 0..self.pop {
-            self.population.set_col(i, CmaEsSlave::generate_sample(&self.mean, self.sigma, &L, &mut r, &d));
-        }
+        (0..self.pop).map(|_| {
+            let z = Vector::new(self.n, |_| T::from(d.ind_sample(r)).unwrap());
+            let y = &l * &z;
+            (z, y)
+        }).collect()
     }
+}
 
-    fn generate_sample<R: Rng, D: IndependentSample<f64>>(mean: &Vector<T>, sigma: T, L: &Matrix<T>, r: &mut R, d: &D) -> Vector<T> {
-        // Multivariate sampling from covariance matrix
-        let normal = Vector::new(mean.dim(), |_| T::from(d.ind_sample(r)).unwrap());
-        mean + (L * normal) * sigma
-    }
+#[test]
+fn test_cmaes() {
+    // Shifted and stretched sphere, minimal at (1, -2)
+    let cmaes = CmaEs::<f64>::new(2, 10, 100);
+    let best = cmaes.optimize(|x| (x[0] - 1f64).powi(2) + 10f64 * (x[1] + 2f64).powi(2));
+    assert_eq!(best.dim(), 2);
+    assert!((best[0] - 1f64).abs() < 1e-3);
+    assert!((best[1] + 2f64).abs() < 1e-3);
+
+    // Undefined scores are never kept as the best.
+    let best = cmaes.optimize(|x| if x[0] < 0f64 { ::std::f64::NAN } else { (x[0] - 1f64).powi(2) + (x[1] + 2f64).powi(2) });
+    assert!((best[0] - 1f64).abs() < 1e-3);
 }
@@ -0,0 +1,354 @@
+use rand::{thread_rng,Rng,SeedableRng,StdRng};
+
+use ml::Classifier;
+
+/// Indices of the training and test samples of a cross-validation fold.
+#[derive(Clone,PartialEq,Debug)]
+pub struct Fold {
+    /// Training samples, in increasing order.
+    pub train: Vec<usize>,
+    /// Test samples, in increasing order.
+    pub test: Vec<usize>,
+}
+
+impl Fold {
+    // Builds the fold testing on `test`, and training on every other sample.
+    fn complement(n_samples: usize, mut test: Vec<usize>) -> Self {
+        test.sort();
+        let mut in_test = vec![false; n_samples];
+        for &i in test.iter() {
+            in_test[i] = true;
+        }
+        Fold {
+            train: (0..n_samples).filter(|&i| !in_test[i]).collect(),
+            test: test,
+        }
+    }
+}
+
+fn shuffled_rng(seed: Option<usize>) -> StdRng {
+    let seed = match seed {
+        Some(seed) => seed,
+        None => thread_rng().gen(),
+    };
+    SeedableRng::from_seed(&[seed][..])
+}
+
+// Sizes of `k` consecutive parts of `n` samples, the first ones one larger.
+fn part_sizes(n: usize, k: usize) -> Vec<usize> {
+    (0..k).map(|i| n / k + if i < n % k { 1 } else { 0 }).collect()
+}
+
+/// Splits the samples into `k` consecutive folds, each used once as test set.
+pub struct KFold {
+    k: usize,
+    shuffle: bool,
+    seed: Option<usize>,
+}
+
+impl KFold {
+    /// Creates a new splitter with `k` folds, without shuffling.
+    ///
+    /// Panics if `k < 2`.
+    pub fn new(k: usize) -> Self {
+        if k < 2 {
+            panic!("KFold needs at least 2 folds.");
+        }
+        KFold {
+            k: k,
+            shuffle: false,
+            seed: None,
+        }
+    }
+
+    /// Shuffles the samples before splitting them.
+    pub fn with_shuffle(mut self) -> Self {
+        self.shuffle = true;
+        self
+    }
+
+    /// Sets the seed of the shuffling, for reproducible folds.
+    pub fn with_seed(mut self, seed: usize) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns the folds of `n_samples` samples.
+    ///
+    /// Panics if there are fewer samples than folds.
+    pub fn split(&self, n_samples: usize) -> Vec<Fold> {
+        if n_samples < self.k {
+            panic!("KFold needs at least one sample per fold.");
+        }
+
+        let mut indices: Vec<usize> = (0..n_samples).collect();
+        if self.shuffle {
+            shuffled_rng(self.seed).shuffle(&mut indices);
+        }
+
+        let mut start = 0;
+        part_sizes(n_samples, self.k).into_iter().map(|size| {
+            let test = indices[start..start + size].to_vec();
+            start += size;
+            Fold::complement(n_samples, test)
+        }).collect()
+    }
+}
+
+/// Splits the samples into `k` folds, keeping the proportion of each label in every fold.
+pub struct StratifiedKFold {
+    k: usize,
+    shuffle: bool,
+    seed: Option<usize>,
+}
+
+impl StratifiedKFold {
+    /// Creates a new splitter with `k` folds, without shuffling.
+    ///
+    /// Panics if `k < 2`.
+    pub fn new(k: usize) -> Self {
+        if k < 2 {
+            panic!("StratifiedKFold needs at least 2 folds.");
+        }
+        StratifiedKFold {
+            k: k,
+            shuffle: false,
+            seed: None,
+        }
+    }
+
+    /// Shuffles the samples of each label before splitting them.
+    pub fn with_shuffle(mut self) -> Self {
+        self.shuffle = true;
+        self
+    }
+
+    /// Sets the seed of the shuffling, for reproducible folds.
+    pub fn with_seed(mut self, seed: usize) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns the folds of the samples with the given labels.
+    ///
+    /// Panics if there are fewer samples than folds.
+    pub fn split<Y: PartialEq>(&self, labels: &[Y]) -> Vec<Fold> {
+        if labels.len() < self.k {
+            panic!("StratifiedKFold needs at least one sample per fold.");
+        }
+
+        // Indices of the samples of each distinct label
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for i in 0..labels.len() {
+            match groups.iter().position(|g| labels[g[0]] == labels[i]) {
+                Some(g) => groups[g].push(i),
+                None => groups.push(vec![i]),
+            }
+        }
+
+        let mut rng = shuffled_rng(self.seed);
+        let mut tests = vec![Vec::new(); self.k];
+        // Dealing each label in turn, carrying on from the previous one, balances the fold sizes.
+        let mut fold = 0;
+        for mut group in groups {
+            if self.shuffle {
+                rng.shuffle(&mut group);
+            }
+            for i in group {
+                tests[fold].push(i);
+                fold = (fold + 1) % self.k;
+            }
+        }
+
+        tests.into_iter().map(|test| Fold::complement(labels.len(), test)).collect()
+    }
+}
+
+/// Splits the samples into `k` folds, keeping the samples of each group in the same fold.
+pub struct GroupKFold {
+    k: usize,
+}
+
+impl GroupKFold {
+    /// Creates a new splitter with `k` folds.
+    ///
+    /// Panics if `k < 2`.
+    pub fn new(k: usize) -> Self {
+        if k < 2 {
+            panic!("GroupKFold needs at least 2 folds.");
+        }
+        GroupKFold {
+            k: k,
+        }
+    }
+
+    /// Returns the folds of the samples with the given groups.
+    ///
+    /// Larger groups are assigned first, each to the fold with the fewest samples.
+    /// Panics if there are fewer groups than folds.
+    pub fn split<G: PartialEq>(&self, groups: &[G]) -> Vec<Fold> {
+        let mut members: Vec<Vec<usize>> = Vec::new();
+        for i in 0..groups.len() {
+            match members.iter().position(|m| groups[m[0]] == groups[i]) {
+                Some(m) => members[m].push(i),
+                None => members.push(vec![i]),
+            }
+        }
+        if members.len() < self.k {
+            panic!("GroupKFold needs at least one group per fold.");
+        }
+
+        // Stable sort, so that equal groups keep their order
+        members.sort_by(|a,b| b.len().cmp(&a.len()));
+        let mut tests: Vec<Vec<usize>> = vec![Vec::new(); self.k];
+        for group in members {
+            let smallest = (0..self.k).fold(0, |a,f| if tests[f].len() < tests[a].len() { f } else { a });
+            tests[smallest].extend(group);
+        }
+
+        tests.into_iter().map(|test| Fold::complement(groups.len(), test)).collect()
+    }
+}
+
+/// Tests on each sample in turn, training on all the others.
+pub struct LeaveOneOut;
+
+impl LeaveOneOut {
+    /// Returns the `n_samples` folds.
+    pub fn split(&self, n_samples: usize) -> Vec<Fold> {
+        (0..n_samples).map(|i| Fold::complement(n_samples, vec![i])).collect()
+    }
+}
+
+/// Splits ordered samples by forward chaining: each fold tests on the samples following its training set.
+///
+/// With `n_splits` splits, the `n` samples are cut into `n_splits + 1` parts, the first one taking the remainder.
+/// Fold `i` trains on the first `i + 1` parts and tests on the next one.
+pub struct TimeSeriesSplit {
+    n_splits: usize,
+    max_train_size: Option<usize>,
+}
+
+impl TimeSeriesSplit {
+    /// Creates a new splitter with `n_splits` folds.
+    ///
+    /// Panics if `n_splits < 1`.
+    pub fn new(n_splits: usize) -> Self {
+        if n_splits < 1 {
+            panic!("TimeSeriesSplit needs at least 1 split.");
+        }
+        TimeSeriesSplit {
+            n_splits: n_splits,
+            max_train_size: None,
+        }
+    }
+
+    /// Trains on at most the `size` latest samples before each test set.
+    pub fn with_max_train_size(mut self, size: usize) -> Self {
+        self.max_train_size = Some(size);
+        self
+    }
+
+    /// Returns the folds of `n_samples` ordered samples.
+    ///
+    /// Panics if there are fewer samples than `n_splits + 1`.
+    pub fn split(&self, n_samples: usize) -> Vec<Fold> {
+        if n_samples < self.n_splits + 1 {
+            panic!("TimeSeriesSplit needs more samples than splits.");
+        }
+
+        let test_size = n_samples / (self.n_splits + 1);
+        (0..self.n_splits).map(|i| {
+            let start = n_samples - (self.n_splits - i) * test_size;
+            let train_start = match self.max_train_size {
+                Some(size) if size < start => start - size,
+                _ => 0,
+            };
+            Fold {
+                train: (train_start..start).collect(),
+                test: (start..start + test_size).collect(),
+            }
+        }).collect()
+    }
+}
+
+/// Trains a copy of the classifier on each fold, and scores its predictions on the test samples.
+///
+/// The scoring function takes the true labels and the predicted ones, and its results are returned in fold order.
+pub fn cross_validate<C, S>(classifier: &C, samples: &[C::Input], labels: &[C::Label], folds: &[Fold], score: S) -> Vec<f64>
+    where C: Classifier + Clone, C::Input: Clone, C::Label: Clone, S: Fn(&[C::Label], &[C::Label]) -> f64
+{
+    if samples.len() != labels.len() {
+        panic!("Cross-validation needs one label per sample.");
+    }
+
+    folds.iter().map(|fold| {
+        let train_samples: Vec<C::Input> = fold.train.iter().map(|&i| samples[i].clone()).collect();
+        let train_labels: Vec<C::Label> = fold.train.iter().map(|&i| labels[i].clone()).collect();
+
+        let mut model = classifier.clone();
+        model.train(&train_samples, &train_labels);
+
+        let truth: Vec<C::Label> = fold.test.iter().map(|&i| labels[i].clone()).collect();
+        let predictions: Vec<C::Label> = fold.test.iter().map(|&i| model.classify(&samples[i])).collect();
+        score(&truth, &predictions)
+    }).collect()
+}
+
+#[test]
+fn test_k_fold() {
+    let folds = KFold::new(3).split(7);
+    assert_eq!(folds[0], Fold { train: vec![3, 4, 5, 6], test: vec![0, 1, 2] });
+    assert_eq!(folds[2].test, vec![5, 6]);
+
+    // Shuffled folds still test on every sample exactly once.
+    let folds = KFold::new(3).with_shuffle().with_seed(4).split(7);
+    let mut tested: Vec<usize> = folds.iter().flat_map(|f| f.test.iter().cloned()).collect();
+    tested.sort();
+    assert_eq!(tested, (0..7).collect::<Vec<usize>>());
+    assert_eq!(folds, KFold::new(3).with_shuffle().with_seed(4).split(7));
+
+    assert_eq!(LeaveOneOut.split(3)[1], Fold { train: vec![0, 2], test: vec![1] });
+}
+
+#[test]
+fn test_stratified_and_group_k_fold() {
+    let labels = [0, 0, 0, 0, 1, 1, 0, 0, 1, 0];
+    for fold in StratifiedKFold::new(2).with_shuffle().split(&labels) {
+        assert_eq!(fold.test.len(), 5);
+        let ones = fold.test.iter().filter(|&&i| labels[i] == 1).count();
+        assert!(ones == 1 || ones == 2);
+    }
+
+    let groups = ["a", "b", "b", "c", "a", "b", "d"];
+    let folds = GroupKFold::new(2).split(&groups);
+    assert_eq!(folds[0].test, vec![1, 2, 5, 6]);
+    assert_eq!(folds[1].test, vec![0, 3, 4]);
+}
+
+#[test]
+fn test_time_series_split() {
+    let folds = TimeSeriesSplit::new(3).split(10);
+    assert_eq!(folds[0], Fold { train: (0..4).collect(), test: vec![4, 5] });
+    assert_eq!(folds[2], Fold { train: (0..8).collect(), test: vec![8, 9] });
+
+    let folds = TimeSeriesSplit::new(3).with_max_train_size(3).split(10);
+    assert_eq!(folds[2].train, vec![5, 6, 7]);
+}
+
+#[test]
+fn test_cross_validate() {
+    use alg::Vector;
+    use ml::linear::LinearRegression;
+
+    let samples: Vec<Vector<f64>> = (0..8).map(|i| Vector::from_slice(&[1f64, i as f64])).collect();
+    let labels: Vec<f64> = (0..8).map(|i| 2f64 * i as f64 + 1f64).collect();
+
+    let folds = KFold::new(4).split(samples.len());
+    let max_error = |truth: &[f64], predictions: &[f64]| {
+        truth.iter().zip(predictions.iter()).fold(0f64, |a,(t,p)| a.max((t - p).abs()))
+    };
+    let scores = cross_validate(&LinearRegression::new(), &samples, &labels, &folds, max_error);
+    assert_eq!(scores.len(), 4);
+    assert!(scores.iter().all(|&s| s < 1e-9));
+}
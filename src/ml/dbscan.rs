@@ -0,0 +1,417 @@
+use num::Float;
+
+use ml::Clusterer;
+use ml::neighbors::{Distance,NeighborSearch};
+use alg::Vector;
+
+/// Label returned by `Clusterer::predict` for samples that belong to no cluster.
+pub const NOISE: usize = ::std::usize::MAX;
+
+/// Density-based spatial clustering of applications with noise.
+///
+/// Clusters are groups of core samples, having at least `min_samples` neighbors
+/// within `eps`, along with the samples in their neighborhood.
+/// Other samples are labeled as noise.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Dbscan<T, D> {
+    eps: T,
+    min_samples: usize,
+    distance: D,
+
+    labels: Vec<Option<usize>>,
+    n_clusters: usize,
+    core_samples: Vec<Vector<T>>,
+    core_labels: Vec<usize>,
+}
+
+impl <T: Float, D: Distance<T>> Dbscan<T,D> {
+    /// Creates a new DBSCAN clusterer.
+    ///
+    /// - `eps`: maximum distance between two neighbors.
+    /// - `min_samples`: number of neighbors, including itself, for a sample to be a core sample.
+    /// - `distance`: the distance to use, for instance `Euclidean` or `Haversine`.
+    pub fn new(eps: T, min_samples: usize, distance: D) -> Self {
+        Dbscan {
+            eps: eps,
+            min_samples: min_samples,
+            distance: distance,
+
+            labels: Vec::new(),
+            n_clusters: 0,
+            core_samples: Vec::new(),
+            core_labels: Vec::new(),
+        }
+    }
+
+    /// Returns the cluster of each training sample, or `None` for noise.
+    pub fn labels(&self) -> &[Option<usize>] {
+        &self.labels
+    }
+
+    /// Returns the number of clusters found.
+    pub fn n_clusters(&self) -> usize {
+        self.n_clusters
+    }
+
+    /// Returns the core samples.
+    pub fn core_samples(&self) -> &[Vector<T>] {
+        &self.core_samples
+    }
+}
+
+impl <T: Float, D: Distance<T>> Clusterer for Dbscan<T,D> {
+    type Input = Vector<T>;
+
+    fn fit(&mut self, samples: &[Vector<T>]) {
+        let n = samples.len();
+        let search = NeighborSearch::new(samples, &self.distance);
+        let neighbors: Vec<Vec<usize>> = samples.iter().map(|x| search.within(x, self.eps)).collect();
+        let is_core: Vec<bool> = neighbors.iter().map(|nb| nb.len() >= self.min_samples).collect();
+
+        let mut labels = vec![None; n];
+        let mut n_clusters = 0;
+        for start in 0..n {
+            if !is_core[start] || labels[start].is_some() { continue; }
+
+            // Expand a new cluster from this core sample
+            let cluster = n_clusters;
+            n_clusters += 1;
+            labels[start] = Some(cluster);
+            let mut stack = vec![start];
+            while let Some(i) = stack.pop() {
+                for &j in neighbors[i].iter() {
+                    if labels[j].is_some() { continue; }
+                    labels[j] = Some(cluster);
+                    // Border samples join the cluster but don't expand it.
+                    if is_core[j] { stack.push(j); }
+                }
+            }
+        }
+
+        self.core_samples = (0..n).filter(|&i| is_core[i]).map(|i| samples[i].clone()).collect();
+        self.core_labels = (0..n).filter(|&i| is_core[i]).map(|i| labels[i].unwrap()).collect();
+        self.labels = labels;
+        self.n_clusters = n_clusters;
+    }
+
+    /// Returns the cluster of the closest core sample within `eps`, or `NOISE`.
+    fn predict(&self, input: &Vector<T>) -> usize {
+        let mut best = (NOISE, self.eps);
+        for (x,&label) in self.core_samples.iter().zip(self.core_labels.iter()) {
+            let d = self.distance.distance(x, input);
+            if d <= best.1 { best = (label, d); }
+        }
+        best.0
+    }
+}
+
+/// Hierarchical DBSCAN: finds clusters of varying densities.
+///
+/// Builds the single-linkage hierarchy of the samples under the mutual
+/// reachability distance, and keeps the most stable clusters of at least
+/// `min_cluster_size` samples.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Hdbscan<T, D> {
+    min_cluster_size: usize,
+    min_samples: Option<usize>,
+    distance: D,
+
+    samples: Vec<Vector<T>>,
+    labels: Vec<Option<usize>>,
+    n_clusters: usize,
+}
+
+// Cluster of the condensed tree
+struct Condensed<T> {
+    parent: Option<usize>,
+    // Lambda (inverse distance) at which the cluster appears
+    birth: T,
+    stability: T,
+    children: Vec<usize>,
+}
+
+// Union-find with path compression, tracking the dendrogram node of each set.
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            let grand = self.parent[self.parent[i]];
+            self.parent[i] = grand;
+            i = grand;
+        }
+        i
+    }
+}
+
+fn persistence<T: Float>(lambda: T, birth: T) -> T {
+    // Avoids `inf - inf` for duplicate samples
+    if lambda == birth { T::zero() } else { lambda - birth }
+}
+
+impl <T: Float, D: Distance<T>> Hdbscan<T,D> {
+    /// Creates a new HDBSCAN clusterer.
+    ///
+    /// - `min_cluster_size`: smallest group of samples considered a cluster, at least 2.
+    /// - `distance`: the distance to use, for instance `Euclidean` or `Haversine`.
+    pub fn new(min_cluster_size: usize, distance: D) -> Self {
+        Hdbscan {
+            min_cluster_size: min_cluster_size.max(2),
+            min_samples: None,
+            distance: distance,
+
+            samples: Vec::new(),
+            labels: Vec::new(),
+            n_clusters: 0,
+        }
+    }
+
+    /// Sets the number of neighbors, including itself, defining the core distance of a sample.
+    ///
+    /// Larger values make the clustering more conservative. Defaults to `min_cluster_size`.
+    pub fn with_min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = Some(min_samples.max(1));
+        self
+    }
+
+    /// Returns the cluster of each training sample, or `None` for noise.
+    pub fn labels(&self) -> &[Option<usize>] {
+        &self.labels
+    }
+
+    /// Returns the number of clusters found.
+    pub fn n_clusters(&self) -> usize {
+        self.n_clusters
+    }
+
+    // Minimum spanning tree under the mutual reachability distance (Prim's algorithm),
+    // as (a, b, distance) edges sorted by distance.
+    fn spanning_tree(&self, samples: &[Vector<T>]) -> Vec<(usize, usize, T)> {
+        let n = samples.len();
+        let k = self.min_samples.unwrap_or(self.min_cluster_size).min(n);
+        let search = NeighborSearch::new(samples, &self.distance);
+        let core: Vec<T> = samples.iter().map(|x| search.nearest(x, k)[k - 1].1).collect();
+
+        let reachability = |a: usize, b: usize| {
+            self.distance.distance(&samples[a], &samples[b]).max(core[a]).max(core[b])
+        };
+
+        let mut in_tree = vec![false; n];
+        let mut best: Vec<(T, usize)> = vec![(T::infinity(), 0); n];
+        let mut edges = Vec::with_capacity(n - 1);
+        let mut current = 0;
+        in_tree[0] = true;
+
+        for _ in 1..n {
+            let mut next = None;
+            for j in 0..n {
+                if in_tree[j] { continue; }
+                let d = reachability(current, j);
+                if d < best[j].0 { best[j] = (d, current); }
+                if next.map_or(true, |m: usize| best[j].0 < best[m].0) { next = Some(j); }
+            }
+
+            let j = next.unwrap();
+            in_tree[j] = true;
+            edges.push((best[j].1, j, best[j].0));
+            current = j;
+        }
+
+        edges.sort_by(|a,b| a.2.partial_cmp(&b.2).unwrap());
+        edges
+    }
+}
+
+impl <T: Float, D: Distance<T>> Clusterer for Hdbscan<T,D> {
+    type Input = Vector<T>;
+
+    fn fit(&mut self, samples: &[Vector<T>]) {
+        let n = samples.len();
+        self.samples = Vec::from(samples);
+        self.labels = vec![None; n];
+        self.n_clusters = 0;
+        if n < self.min_cluster_size { return; }
+
+        // Single-linkage dendrogram: leaves are 0..n, merges are n..2n-1.
+        let mut merges: Vec<(usize, usize, T, usize)> = Vec::with_capacity(n - 1);
+        let mut sets = UnionFind::new(2 * n - 1);
+        for (a, b, d) in self.spanning_tree(samples) {
+            let (ra, rb) = (sets.find(a), sets.find(b));
+            let size_a = if ra < n { 1 } else { merges[ra - n].3 };
+            let size_b = if rb < n { 1 } else { merges[rb - n].3 };
+            let node = n + merges.len();
+            sets.parent[ra] = node;
+            sets.parent[rb] = node;
+            merges.push((ra, rb, d, size_a + size_b));
+        }
+
+        let size = |node: usize| if node < n { 1 } else { merges[node - n].3 };
+
+        // Condense the dendrogram: splits smaller than min_cluster_size are
+        // samples falling out of the cluster rather than new clusters.
+        let mut clusters = vec![Condensed { parent: None, birth: T::zero(), stability: T::zero(), children: Vec::new() }];
+        // Cluster each sample fell out of, and at which lambda
+        let mut fall_out: Vec<(usize, T)> = vec![(0, T::zero()); n];
+        // (dendrogram node, its cluster)
+        let mut stack = vec![(2 * n - 2, 0)];
+
+        while let Some((node, cluster)) = stack.pop() {
+            if node < n {
+                // Single sample, only reached when min_cluster_size allows it
+                fall_out[node] = (cluster, T::infinity());
+                continue;
+            }
+
+            let (left, right, d, _) = merges[node - n];
+            let lambda = if d > T::zero() { d.recip() } else { T::infinity() };
+            let big_left = size(left) >= self.min_cluster_size;
+            let big_right = size(right) >= self.min_cluster_size;
+
+            if big_left && big_right {
+                for &child in [left, right].iter() {
+                    let id = clusters.len();
+                    let birth = clusters[cluster].birth;
+                    clusters[cluster].stability = clusters[cluster].stability + persistence(lambda, birth) * T::from(size(child)).unwrap();
+                    clusters[cluster].children.push(id);
+                    clusters.push(Condensed { parent: Some(cluster), birth: lambda, stability: T::zero(), children: Vec::new() });
+                    stack.push((child, id));
+                }
+            } else {
+                for &(child, big) in [(left, big_left), (right, big_right)].iter() {
+                    if big {
+                        // The cluster goes on, only losing the small side.
+                        stack.push((child, cluster));
+                        continue;
+                    }
+
+                    // Every sample under this node leaves the cluster at this lambda.
+                    let mut leaves = vec![child];
+                    while let Some(l) = leaves.pop() {
+                        if l < n {
+                            fall_out[l] = (cluster, lambda);
+                            let birth = clusters[cluster].birth;
+                            clusters[cluster].stability = clusters[cluster].stability + persistence(lambda, birth);
+                        } else {
+                            leaves.push(merges[l - n].0);
+                            leaves.push(merges[l - n].1);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Select the most stable clusters bottom-up: children are always created after their parent.
+        // The root is never selected.
+        let mut selected = vec![false; clusters.len()];
+        let mut subtree = vec![T::zero(); clusters.len()];
+        for c in (1..clusters.len()).rev() {
+            let children = clusters[c].children.iter().fold(T::zero(), |a,&ch| a + subtree[ch]);
+            if clusters[c].children.is_empty() || clusters[c].stability >= children {
+                selected[c] = true;
+                subtree[c] = clusters[c].stability;
+
+                let mut descendants = clusters[c].children.clone();
+                while let Some(d) = descendants.pop() {
+                    selected[d] = false;
+                    descendants.extend(clusters[d].children.iter().cloned());
+                }
+            } else {
+                subtree[c] = children;
+            }
+        }
+
+        let mut ids = vec![None; clusters.len()];
+        for c in 0..clusters.len() {
+            if selected[c] {
+                ids[c] = Some(self.n_clusters);
+                self.n_clusters += 1;
+            }
+        }
+
+        for (i,&(cluster,_)) in fall_out.iter().enumerate() {
+            let mut c = Some(cluster);
+            while let Some(current) = c {
+                if selected[current] {
+                    self.labels[i] = ids[current];
+                    break;
+                }
+                c = clusters[current].parent;
+            }
+        }
+    }
+
+    /// Returns the cluster of the closest training sample, or `NOISE`.
+    fn predict(&self, input: &Vector<T>) -> usize {
+        let mut best = (NOISE, T::infinity());
+        for (x,label) in self.samples.iter().zip(self.labels.iter()) {
+            let d = self.distance.distance(x, input);
+            if d < best.1 { best = (label.unwrap_or(NOISE), d); }
+        }
+        best.0
+    }
+}
+
+#[cfg(test)]
+fn groups() -> Vec<Vector<f64>> {
+    // A tight group, a looser group, and an outlier
+    let mut samples = Vec::new();
+    for i in 0..10 {
+        samples.push(Vector::from_slice(&[(i % 3) as f64 * 0.1, (i / 3) as f64 * 0.1]));
+    }
+    for i in 0..10 {
+        samples.push(Vector::from_slice(&[10f64 + (i % 3) as f64 * 0.5, (i / 3) as f64 * 0.5]));
+    }
+    samples.push(Vector::from_slice(&[5f64, 20f64]));
+    samples
+}
+
+#[test]
+fn test_dbscan() {
+    use ml::neighbors::Euclidean;
+
+    let samples = groups();
+    let mut dbscan = Dbscan::new(0.6f64, 3, Euclidean);
+    dbscan.fit(&samples);
+
+    assert_eq!(dbscan.n_clusters(), 2);
+    let labels = dbscan.labels();
+    assert!(labels[..10].iter().all(|&l| l == labels[0]));
+    assert!(labels[10..20].iter().all(|&l| l == labels[10]));
+    assert!(labels[0] != labels[10]);
+    assert_eq!(labels[20], None);
+
+    assert_eq!(dbscan.predict(&Vector::from_slice(&[10.2f64, 0.2f64])), labels[10].unwrap());
+    assert_eq!(dbscan.predict(&Vector::from_slice(&[-5f64, -5f64])), NOISE);
+
+    // A smaller eps only keeps the tight group.
+    let mut dbscan = Dbscan::new(0.2f64, 3, Euclidean);
+    dbscan.fit(&samples);
+    assert_eq!(dbscan.n_clusters(), 1);
+    assert_eq!(dbscan.labels()[15], None);
+}
+
+#[test]
+fn test_hdbscan() {
+    use ml::neighbors::Euclidean;
+
+    let samples = groups();
+    let mut hdbscan = Hdbscan::new(5, Euclidean).with_min_samples(3);
+    hdbscan.fit(&samples);
+
+    assert_eq!(hdbscan.n_clusters(), 2);
+    let labels = hdbscan.labels();
+    assert!(labels[..10].iter().all(|&l| l.is_some() && l == labels[0]));
+    assert!(labels[10..20].iter().all(|&l| l.is_some() && l == labels[10]));
+    assert!(labels[0] != labels[10]);
+    assert_eq!(labels[20], None);
+}
@@ -0,0 +1,351 @@
+use num::Float;
+
+use ml::Classifier;
+use alg::{Vector,Matrix};
+
+// Per-class statistics shared by LDA and QDA.
+struct ClassStats<T> {
+    counts: Vec<usize>,
+    means: Vec<Vector<T>>,
+    // Scatter matrix of each class around its mean
+    scatters: Vec<Matrix<T>>,
+}
+
+impl <T: Float> ClassStats<T> {
+    fn new(samples: &[Vector<T>], labels: &[usize]) -> Self {
+        let d = samples[0].dim();
+        let k = labels.iter().fold(0, |a,&b| a.max(b + 1));
+
+        let mut counts = vec![0; k];
+        let mut sums = vec![Vector::zero(d); k];
+        for (x,&y) in samples.iter().zip(labels.iter()) {
+            counts[y] += 1;
+            sums[y].add_in_place(x);
+        }
+        let means: Vec<Vector<T>> = sums.into_iter().zip(counts.iter())
+            .map(|(s,&c)| s / T::from(c.max(1)).unwrap())
+            .collect();
+
+        let mut scatters = vec![Matrix::zero(d, d); k];
+        for (x,&y) in samples.iter().zip(labels.iter()) {
+            let centered = x - &means[y];
+            scatters[y] = &scatters[y] + &centered.outer_product(&centered);
+        }
+
+        ClassStats {
+            counts: counts,
+            means: means,
+            scatters: scatters,
+        }
+    }
+
+    fn log_priors(&self) -> Vec<T> {
+        let n = T::from(self.counts.iter().fold(0, |a,b| a+b)).unwrap();
+        self.counts.iter().map(|&c| (T::from(c).unwrap() / n).ln()).collect()
+    }
+}
+
+// Shrinks a covariance towards a multiple of the identity with the same trace.
+fn shrink<T: Float>(covariance: Matrix<T>, shrinkage: T) -> Matrix<T> {
+    if shrinkage == T::zero() { return covariance; }
+
+    let d = covariance.n;
+    let trace = (0..d).map(|i| covariance[(i,i)]).fold(T::zero(), |a,b| a+b);
+    let target = Matrix::scalar(d, trace / T::from(d).unwrap());
+    &(&covariance * (T::one() - shrinkage)) + &(&target * shrinkage)
+}
+
+fn argmax<T: Float>(values: &[T]) -> usize {
+    let mut best = 0;
+    for (i,&v) in values.iter().enumerate() {
+        if v > values[best] { best = i; }
+    }
+    best
+}
+
+/// Linear discriminant analysis.
+///
+/// Models each class as a Gaussian with its own mean and a covariance shared by
+/// all classes. Used as a classifier, or to project samples onto the at most
+/// `K - 1` axes best separating the `K` classes.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Lda<T> {
+    shrinkage: T,
+    n_components: Option<usize>,
+
+    means: Vec<Vector<T>>,
+    log_priors: Vec<T>,
+    covariance: Matrix<T>,
+    cholesky: Matrix<T>,
+    // Cholesky-whitened class means
+    whitened_means: Vec<Vector<T>>,
+
+    overall_mean: Vector<T>,
+    // One discriminant axis per row
+    scalings: Matrix<T>,
+    explained_variance_ratio: Vector<T>,
+}
+
+impl <T: Float> Lda<T> {
+    /// Creates a new untrained LDA.
+    pub fn new() -> Self {
+        Lda {
+            shrinkage: T::zero(),
+            n_components: None,
+
+            means: Vec::new(),
+            log_priors: Vec::new(),
+            covariance: Matrix::dummy(),
+            cholesky: Matrix::dummy(),
+            whitened_means: Vec::new(),
+
+            overall_mean: Vector::dummy(),
+            scalings: Matrix::dummy(),
+            explained_variance_ratio: Vector::dummy(),
+        }
+    }
+
+    /// Shrinks the covariance towards a scaled identity, between 0 (none) and 1 (full).
+    ///
+    /// Helps when there are few samples compared to the number of features.
+    pub fn with_shrinkage(mut self, shrinkage: T) -> Self {
+        self.shrinkage = shrinkage;
+        self
+    }
+
+    /// Sets the number of discriminant axes kept by `transform`.
+    ///
+    /// Defaults to, and is capped at, `min(K - 1, dimension)`.
+    pub fn with_components(mut self, n_components: usize) -> Self {
+        self.n_components = Some(n_components);
+        self
+    }
+
+    /// Returns the mean of each class.
+    pub fn means(&self) -> &[Vector<T>] {
+        &self.means
+    }
+
+    /// Returns the shared within-class covariance.
+    pub fn covariance(&self) -> &Matrix<T> {
+        &self.covariance
+    }
+
+    /// Returns the discriminant axes, one per row, by decreasing separation.
+    pub fn scalings(&self) -> &Matrix<T> {
+        &self.scalings
+    }
+
+    /// Returns the fraction of the between-class variance along each discriminant axis.
+    pub fn explained_variance_ratio(&self) -> &Vector<T> {
+        &self.explained_variance_ratio
+    }
+
+    /// Returns the log-posterior of each class for the input, up to a constant.
+    pub fn decision_function(&self, input: &Vector<T>) -> Vec<T> {
+        let two = T::one() + T::one();
+        let z = self.cholesky.solve_lower_triangular(input);
+        self.whitened_means.iter().zip(self.log_priors.iter())
+            .map(|(m,&p)| p - (&z - m).norm_sq() / two)
+            .collect()
+    }
+
+    /// Projects a sample onto the discriminant axes.
+    pub fn transform(&self, input: &Vector<T>) -> Vector<T> {
+        &self.scalings * (input - &self.overall_mean)
+    }
+}
+
+impl <T: Float> Classifier for Lda<T> {
+    type Input = Vector<T>;
+    type Label = usize;
+
+    /// Labels must be in `0..K`.
+    fn train(&mut self, samples: &[Vector<T>], labels: &[usize]) {
+        if samples.is_empty() { return; }
+
+        let n = samples.len();
+        let d = samples[0].dim();
+        let stats = ClassStats::new(samples, labels);
+        let k = stats.means.len();
+
+        let within = stats.scatters.iter().fold(Matrix::zero(d, d), |a,s| &a + s);
+        let dof = T::from(if n > k { n - k } else { 1 }).unwrap();
+        self.covariance = shrink(&within / dof, self.shrinkage);
+        self.cholesky = self.covariance.cholesky();
+        self.whitened_means = stats.means.iter().map(|m| self.cholesky.solve_lower_triangular(m)).collect();
+        self.log_priors = stats.log_priors();
+
+        // Discriminant axes: solve `between * v = lambda * covariance * v` by
+        // whitening with the Cholesky factor, then diagonalizing.
+        let n_t = T::from(n).unwrap();
+        self.overall_mean = samples.iter().fold(Vector::zero(d), |a,x| &a + x) / n_t;
+        let whitened_overall = self.cholesky.solve_lower_triangular(&self.overall_mean);
+        let between = self.whitened_means.iter().zip(stats.counts.iter()).fold(Matrix::zero(d, d), |a,(m,&c)| {
+            let centered = m - &whitened_overall;
+            &a + &(&centered.outer_product(&centered) * (T::from(c).unwrap() / n_t))
+        });
+        let (values, vectors) = between.symmetric_eigen();
+
+        let max_components = if k > 1 { (k - 1).min(d) } else { 0 };
+        let n_components = self.n_components.map_or(max_components, |c| c.min(max_components));
+        let upper = self.cholesky.transpose();
+        let axes: Vec<Vector<T>> = (0..n_components).map(|i| upper.solve_upper_triangular(&vectors.col(i))).collect();
+
+        let total = (0..max_components).map(|i| values[i].max(T::zero())).fold(T::zero(), |a,b| a+b);
+        self.explained_variance_ratio = Vector::new(n_components, |i| {
+            if total > T::zero() { values[i].max(T::zero()) / total } else { T::zero() }
+        });
+        self.scalings = if axes.is_empty() { Matrix::dummy() } else { Matrix::from_rows(&axes) };
+        self.means = stats.means;
+    }
+
+    fn classify(&self, input: &Vector<T>) -> usize {
+        argmax(&self.decision_function(input))
+    }
+}
+
+/// Quadratic discriminant analysis.
+///
+/// Models each class as a Gaussian with its own mean and covariance.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Qda<T> {
+    shrinkage: T,
+
+    means: Vec<Vector<T>>,
+    log_priors: Vec<T>,
+    covariances: Vec<Matrix<T>>,
+    cholesky: Vec<Matrix<T>>,
+}
+
+impl <T: Float> Qda<T> {
+    /// Creates a new untrained QDA.
+    pub fn new() -> Self {
+        Qda {
+            shrinkage: T::zero(),
+
+            means: Vec::new(),
+            log_priors: Vec::new(),
+            covariances: Vec::new(),
+            cholesky: Vec::new(),
+        }
+    }
+
+    /// Shrinks each covariance towards a scaled identity, between 0 (none) and 1 (full).
+    ///
+    /// Required when a class has fewer samples than features.
+    pub fn with_shrinkage(mut self, shrinkage: T) -> Self {
+        self.shrinkage = shrinkage;
+        self
+    }
+
+    /// Returns the mean of each class.
+    pub fn means(&self) -> &[Vector<T>] {
+        &self.means
+    }
+
+    /// Returns the covariance of each class.
+    pub fn covariances(&self) -> &[Matrix<T>] {
+        &self.covariances
+    }
+
+    /// Returns the log-posterior of each class for the input, up to a constant.
+    pub fn decision_function(&self, input: &Vector<T>) -> Vec<T> {
+        let two = T::one() + T::one();
+        (0..self.means.len()).map(|i| {
+            let l = &self.cholesky[i];
+            let z = l.solve_lower_triangular(&(input - &self.means[i]));
+            let log_det = (0..l.n).map(|j| l[(j,j)].ln()).fold(T::zero(), |a,b| a+b);
+            self.log_priors[i] - log_det - z.norm_sq() / two
+        }).collect()
+    }
+}
+
+impl <T: Float> Classifier for Qda<T> {
+    type Input = Vector<T>;
+    type Label = usize;
+
+    /// Labels must be in `0..K`.
+    fn train(&mut self, samples: &[Vector<T>], labels: &[usize]) {
+        if samples.is_empty() { return; }
+
+        let stats = ClassStats::new(samples, labels);
+        self.covariances = stats.scatters.iter().zip(stats.counts.iter())
+            .map(|(s,&c)| shrink(s / T::from(if c > 1 { c - 1 } else { 1 }).unwrap(), self.shrinkage))
+            .collect();
+        self.cholesky = self.covariances.iter().map(|c| c.cholesky()).collect();
+        self.log_priors = stats.log_priors();
+        self.means = stats.means;
+    }
+
+    fn classify(&self, input: &Vector<T>) -> usize {
+        argmax(&self.decision_function(input))
+    }
+}
+
+#[cfg(test)]
+fn three_classes() -> (Vec<Vector<f64>>, Vec<usize>) {
+    // Classes differ along the first axis only; the second is shared noise.
+    let mut samples = Vec::new();
+    let mut labels = Vec::new();
+    for class in 0..3 {
+        for i in 0..8 {
+            let noise = (i % 4) as f64 - 1.5f64;
+            samples.push(Vector::from_slice(&[class as f64 * 4f64 + (i / 4) as f64 * 0.5f64, noise * 3f64]));
+            labels.push(class);
+        }
+    }
+    (samples, labels)
+}
+
+#[test]
+fn test_lda() {
+    let (samples, labels) = three_classes();
+
+    let mut lda = Lda::new();
+    lda.train(&samples, &labels);
+    for (x,&y) in samples.iter().zip(labels.iter()) {
+        assert_eq!(lda.classify(x), y);
+    }
+
+    // Only the first axis separates the classes.
+    assert_eq!(lda.scalings().m, 2);
+    assert!(lda.explained_variance_ratio()[0] > 0.999);
+    let axis = lda.scalings().row(0);
+    assert!(axis[1].abs() < 1e-9 * axis[0].abs());
+
+    // The projection keeps the classes apart.
+    let projected: Vec<f64> = samples.iter().map(|x| lda.transform(x)[0].abs()).collect();
+    assert!(projected[0] > projected[8] && projected[16] > projected[8]);
+
+    let mut lda = Lda::new().with_components(1).with_shrinkage(0.5f64);
+    lda.train(&samples, &labels);
+    assert_eq!(lda.transform(&samples[0]).dim(), 1);
+    assert_eq!(lda.classify(&samples[20]), 2);
+}
+
+#[test]
+fn test_qda() {
+    // Same mean, different spreads: only QDA can tell them apart.
+    let mut samples = Vec::new();
+    let mut labels = Vec::new();
+    for i in 0..12 {
+        let t = i as f64 * 0.5236f64;
+        samples.push(Vector::from_slice(&[t.cos() * 0.5f64, t.sin() * 0.5f64]));
+        labels.push(0);
+        samples.push(Vector::from_slice(&[t.cos() * 5f64, t.sin() * 5f64]));
+        labels.push(1);
+    }
+
+    let mut qda = Qda::new();
+    qda.train(&samples, &labels);
+    assert_eq!(qda.classify(&Vector::from_slice(&[0.1f64, 0.2f64])), 0);
+    assert_eq!(qda.classify(&Vector::from_slice(&[4f64, -3f64])), 1);
+    assert_eq!(qda.covariances().len(), 2);
+
+    let mut qda = Qda::new().with_shrinkage(0.1f64);
+    qda.train(&samples, &labels);
+    assert_eq!(qda.classify(&Vector::from_slice(&[0f64, 0f64])), 0);
+}
@@ -0,0 +1,379 @@
+use ml::Transformer;
+use ml::bayes::Value;
+use alg::Vector;
+
+/// A categorical value, as found in `Value::Integer` and `Value::Boolean` columns.
+#[derive(Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Hash,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub enum Category {
+    Boolean(bool),
+    Integer(i32),
+}
+
+impl Category {
+    /// Returns the category of a value, or `None` for a `Value::Double` or a missing value.
+    pub fn of(value: &Value) -> Option<Category> {
+        match *value {
+            Value::Boolean(b) => Some(Category::Boolean(b)),
+            Value::Integer(i) => Some(Category::Integer(i)),
+            Value::Double(_) | Value::Missing => None,
+        }
+    }
+
+    /// Returns the value of this category.
+    pub fn value(&self) -> Value {
+        match *self {
+            Category::Boolean(b) => Value::Boolean(b),
+            Category::Integer(i) => Value::Integer(i),
+        }
+    }
+}
+
+/// How encoders handle categories that were not seen during training.
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub enum Unknown {
+    /// Panics.
+    Panic,
+    /// Encodes them as no known category.
+    Ignore,
+}
+
+// Sorted categories of each column, or `None` for numeric columns.
+// Columns holding any `Value::Integer` or `Value::Boolean` are categorical.
+fn learn_categories(samples: &[Vector<Value>]) -> Vec<Option<Vec<Category>>> {
+    if samples.is_empty() { return Vec::new(); }
+
+    (0..samples[0].dim()).map(|j| {
+        let mut categories: Vec<Category> = samples.iter().filter_map(|x| Category::of(&x[j])).collect();
+        if categories.is_empty() { return None; }
+        categories.sort();
+        categories.dedup();
+        Some(categories)
+    }).collect()
+}
+
+// Index of the category of `value`, or `None` if it is unknown and ignored.
+// Missing values are never looked up.
+fn lookup(categories: &[Category], value: &Value, unknown: Unknown) -> Option<usize> {
+    let index = Category::of(value).and_then(|c| categories.binary_search(&c).ok());
+    if index.is_none() && unknown == Unknown::Panic {
+        panic!("Unknown category {:?}.", value);
+    }
+    index
+}
+
+fn numeric(value: &Value) -> f64 {
+    match *value {
+        Value::Double(x) => x as f64,
+        Value::Integer(i) => i as f64,
+        Value::Boolean(b) => if b { 1f64 } else { 0f64 },
+        Value::Missing => ::std::f64::NAN,
+    }
+}
+
+/// Encodes each categorical column as one indicator feature per category.
+///
+/// `Value::Double` columns are passed through as a single feature.
+/// Missing values are encoded as NaN, to be filled by an imputer.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct OneHotEncoder {
+    unknown: Unknown,
+
+    categories: Vec<Option<Vec<Category>>>,
+}
+
+impl OneHotEncoder {
+    /// Creates a new encoder, panicking on unknown categories.
+    pub fn new() -> Self {
+        OneHotEncoder {
+            unknown: Unknown::Panic,
+
+            categories: Vec::new(),
+        }
+    }
+
+    /// Sets how unknown categories are handled. Ignored ones are encoded as all zeros.
+    pub fn with_unknown(mut self, unknown: Unknown) -> Self {
+        self.unknown = unknown;
+        self
+    }
+
+    /// Returns the sorted categories of each column, or `None` for passed-through columns.
+    pub fn categories(&self) -> &[Option<Vec<Category>>] {
+        &self.categories
+    }
+
+    /// Returns the number of encoded features.
+    pub fn n_outputs(&self) -> usize {
+        self.categories.iter().map(|c| c.as_ref().map_or(1, |c| c.len())).fold(0, |a,b| a+b)
+    }
+}
+
+impl Transformer for OneHotEncoder {
+    type Input = Vector<Value>;
+    type Output = Vector<f64>;
+
+    fn fit(&mut self, samples: &[Vector<Value>]) {
+        self.categories = learn_categories(samples);
+    }
+
+    fn transform(&self, input: &Vector<Value>) -> Vector<f64> {
+        let mut output = Vec::with_capacity(self.n_outputs());
+        for (j,categories) in self.categories.iter().enumerate() {
+            match *categories {
+                None => output.push(numeric(&input[j])),
+                Some(ref categories) if input[j] == Value::Missing => {
+                    output.extend((0..categories.len()).map(|_| ::std::f64::NAN));
+                },
+                Some(ref categories) => {
+                    let hot = lookup(categories, &input[j], self.unknown);
+                    output.extend((0..categories.len()).map(|i| if hot == Some(i) { 1f64 } else { 0f64 }));
+                },
+            }
+        }
+        Vector::from_vec(output)
+    }
+
+    /// Decodes each categorical column to its highest indicator.
+    fn inverse_transform(&self, output: &Vector<f64>) -> Vector<Value> {
+        let mut input = Vec::with_capacity(self.categories.len());
+        let mut offset = 0;
+        for categories in self.categories.iter() {
+            match *categories {
+                None => {
+                    input.push(Value::Double(output[offset] as f32));
+                    offset += 1;
+                },
+                Some(ref categories) => {
+                    let best = (1..categories.len()).fold(0, |a,i| if output[offset + i] > output[offset + a] { i } else { a });
+                    input.push(categories[best].value());
+                    offset += categories.len();
+                },
+            }
+        }
+        Vector::from_vec(input)
+    }
+}
+
+/// Encodes each categorical column as the index of its category, in sorted order.
+///
+/// `Value::Double` columns are passed through. Missing values are encoded as NaN.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct OrdinalEncoder {
+    unknown: Unknown,
+
+    categories: Vec<Option<Vec<Category>>>,
+}
+
+impl OrdinalEncoder {
+    /// Creates a new encoder, panicking on unknown categories.
+    pub fn new() -> Self {
+        OrdinalEncoder {
+            unknown: Unknown::Panic,
+
+            categories: Vec::new(),
+        }
+    }
+
+    /// Sets how unknown categories are handled. Ignored ones are encoded as `-1`.
+    pub fn with_unknown(mut self, unknown: Unknown) -> Self {
+        self.unknown = unknown;
+        self
+    }
+
+    /// Returns the sorted categories of each column, or `None` for passed-through columns.
+    pub fn categories(&self) -> &[Option<Vec<Category>>] {
+        &self.categories
+    }
+}
+
+impl Transformer for OrdinalEncoder {
+    type Input = Vector<Value>;
+    type Output = Vector<f64>;
+
+    fn fit(&mut self, samples: &[Vector<Value>]) {
+        self.categories = learn_categories(samples);
+    }
+
+    fn transform(&self, input: &Vector<Value>) -> Vector<f64> {
+        Vector::new(self.categories.len(), |j| match self.categories[j] {
+            None => numeric(&input[j]),
+            Some(_) if input[j] == Value::Missing => ::std::f64::NAN,
+            Some(ref categories) => lookup(categories, &input[j], self.unknown).map_or(-1f64, |i| i as f64),
+        })
+    }
+
+    /// Decodes each categorical column to the nearest category index.
+    fn inverse_transform(&self, output: &Vector<f64>) -> Vector<Value> {
+        Vector::new(self.categories.len(), |j| match self.categories[j] {
+            None => Value::Double(output[j] as f32),
+            Some(ref categories) => {
+                let index = output[j].round().max(0f64).min((categories.len() - 1) as f64);
+                categories[index as usize].value()
+            },
+        })
+    }
+}
+
+/// Encodes each categorical column as the mean target of its category.
+///
+/// Means are shrunk towards the overall mean of the targets for rare categories.
+/// Since it needs the targets, this is fitted with `fit` rather than through the `Transformer` trait.
+/// `Value::Double` columns are passed through. Missing values are encoded as NaN.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct TargetEncoder {
+    smoothing: f64,
+    unknown: Unknown,
+
+    categories: Vec<Option<Vec<Category>>>,
+    encodings: Vec<Vec<f64>>,
+    prior: f64,
+}
+
+impl TargetEncoder {
+    /// Creates a new encoder, panicking on unknown categories.
+    pub fn new() -> Self {
+        TargetEncoder {
+            smoothing: 1f64,
+            unknown: Unknown::Panic,
+
+            categories: Vec::new(),
+            encodings: Vec::new(),
+            prior: 0f64,
+        }
+    }
+
+    /// Sets the weight of the overall mean, in number of samples. Defaults to 1.
+    pub fn with_smoothing(mut self, smoothing: f64) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Sets how unknown categories are handled. Ignored ones are encoded as the overall mean.
+    pub fn with_unknown(mut self, unknown: Unknown) -> Self {
+        self.unknown = unknown;
+        self
+    }
+
+    /// Returns the sorted categories of each column, or `None` for passed-through columns.
+    pub fn categories(&self) -> &[Option<Vec<Category>>] {
+        &self.categories
+    }
+
+    /// Returns the encoding of each category, empty for passed-through columns.
+    pub fn encodings(&self) -> &[Vec<f64>] {
+        &self.encodings
+    }
+
+    /// Returns the overall mean of the targets.
+    pub fn prior(&self) -> f64 {
+        self.prior
+    }
+
+    /// Learns the categories and their mean target. Use 0 and 1 as targets for binary classification.
+    pub fn fit(&mut self, samples: &[Vector<Value>], targets: &[f64]) {
+        if samples.is_empty() { return; }
+
+        self.categories = learn_categories(samples);
+        self.prior = targets.iter().fold(0f64, |a,b| a+b) / targets.len() as f64;
+        self.encodings = self.categories.iter().enumerate().map(|(j,categories)| match *categories {
+            None => Vec::new(),
+            Some(ref categories) => {
+                let mut sums = vec![0f64; categories.len()];
+                let mut counts = vec![0f64; categories.len()];
+                for (x,&y) in samples.iter().zip(targets.iter()) {
+                    if let Some(i) = lookup(categories, &x[j], Unknown::Ignore) {
+                        sums[i] += y;
+                        counts[i] += 1f64;
+                    }
+                }
+                (0..categories.len()).map(|i| (sums[i] + self.smoothing * self.prior) / (counts[i] + self.smoothing)).collect()
+            },
+        }).collect();
+    }
+
+    /// Encodes a sample.
+    pub fn transform(&self, input: &Vector<Value>) -> Vector<f64> {
+        Vector::new(self.categories.len(), |j| match self.categories[j] {
+            None => numeric(&input[j]),
+            Some(_) if input[j] == Value::Missing => ::std::f64::NAN,
+            Some(ref categories) => lookup(categories, &input[j], self.unknown).map_or(self.prior, |i| self.encodings[j][i]),
+        })
+    }
+}
+
+#[cfg(test)]
+fn records() -> Vec<Vector<Value>> {
+    [(3, true, 0.5f32), (1, false, 1.5f32), (3, false, 2.5f32), (2, true, 3.5f32)].iter().map(|&(i,b,x)| {
+        Vector::from_vec(vec![Value::Integer(i), Value::Boolean(b), Value::Double(x)])
+    }).collect()
+}
+
+#[test]
+fn test_one_hot_encoder() {
+    let samples = records();
+    let mut encoder = OneHotEncoder::new();
+    encoder.fit(&samples);
+
+    assert_eq!(encoder.categories()[0], Some(vec![Category::Integer(1), Category::Integer(2), Category::Integer(3)]));
+    assert_eq!(encoder.categories()[2], None);
+    assert_eq!(encoder.n_outputs(), 6);
+
+    let encoded = encoder.transform(&samples[0]);
+    assert_eq!(encoded, Vector::from_slice(&[0f64, 0f64, 1f64, 0f64, 1f64, 0.5f64]));
+    assert_eq!(encoder.inverse_transform(&encoded), samples[0]);
+
+    let unknown = Vector::from_vec(vec![Value::Integer(7), Value::Boolean(true), Value::Double(0f32)]);
+    let encoder = encoder.with_unknown(Unknown::Ignore);
+    assert_eq!(encoder.transform(&unknown), Vector::from_slice(&[0f64, 0f64, 0f64, 0f64, 1f64, 0f64]));
+}
+
+#[test]
+#[should_panic]
+fn test_one_hot_encoder_unknown() {
+    let mut encoder = OneHotEncoder::new();
+    encoder.fit(&records());
+    encoder.transform(&Vector::from_vec(vec![Value::Integer(7), Value::Boolean(true), Value::Double(0f32)]));
+}
+
+#[test]
+fn test_ordinal_encoder() {
+    let samples = records();
+    let mut encoder = OrdinalEncoder::new().with_unknown(Unknown::Ignore);
+    encoder.fit(&samples);
+
+    let encoded = encoder.transform(&samples[1]);
+    assert_eq!(encoded, Vector::from_slice(&[0f64, 0f64, 1.5f64]));
+    assert_eq!(encoder.inverse_transform(&encoded), samples[1]);
+
+    let unknown = Vector::from_vec(vec![Value::Integer(7), Value::Boolean(true), Value::Double(0f32)]);
+    assert_eq!(encoder.transform(&unknown), Vector::from_slice(&[-1f64, 1f64, 0f64]));
+
+    let missing = encoder.transform(&Vector::from_vec(vec![Value::Missing, Value::Boolean(true), Value::Missing]));
+    assert!(missing[0].is_nan() && missing[2].is_nan());
+}
+
+#[test]
+fn test_target_encoder() {
+    let samples = records();
+    let targets = [1f64, 0f64, 1f64, 0f64];
+
+    let mut encoder = TargetEncoder::new().with_smoothing(0f64).with_unknown(Unknown::Ignore);
+    encoder.fit(&samples, &targets);
+    assert_eq!(encoder.prior(), 0.5f64);
+    assert_eq!(encoder.encodings()[0], vec![0f64, 0f64, 1f64]);
+    assert_eq!(encoder.encodings()[1], vec![0.5f64, 0.5f64]);
+    assert!(encoder.encodings()[2].is_empty());
+
+    let unknown = Vector::from_vec(vec![Value::Integer(7), Value::Boolean(true), Value::Double(2f32)]);
+    assert_eq!(encoder.transform(&unknown), Vector::from_slice(&[0.5f64, 0.5f64, 2f64]));
+
+    // Smoothing pulls rare categories towards the prior.
+    let mut encoder = TargetEncoder::new().with_smoothing(2f64);
+    encoder.fit(&samples, &targets);
+    assert_eq!(encoder.encodings()[0], vec![1f64 / 3f64, 1f64 / 3f64, 0.75f64]);
+}
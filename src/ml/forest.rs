@@ -0,0 +1,253 @@
+use std::sync::Arc;
+use std::thread;
+
+use num::Float;
+use rand::{thread_rng,Rng,SeedableRng,StdRng};
+
+use ml::Classifier;
+use ml::tree::{DecisionTree,Target};
+use alg::Vector;
+
+/// Ensemble of decision trees, each trained on a bootstrap sample.
+///
+/// Predictions are aggregated by majority vote for `usize` labels,
+/// and by their mean for float labels.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct RandomForest<T, L> {
+    n_trees: usize,
+    max_depth: usize,
+    min_samples_split: usize,
+    max_features: Option<usize>,
+    n_threads: usize,
+    seed: Option<usize>,
+
+    trees: Vec<DecisionTree<T,L>>,
+    oob_error: Option<f64>,
+    importances: Vec<f64>,
+}
+
+impl <T, L> RandomForest<T,L>
+    where T: Float + Send + Sync + 'static,
+          L: Target + Send + Sync + 'static
+{
+    /// Creates a new untrained forest.
+    ///
+    /// - `n_trees`: number of trees in the ensemble.
+    /// - `max_depth`: maximum depth of each tree.
+    pub fn new(n_trees: usize, max_depth: usize) -> Self {
+        RandomForest {
+            n_trees: n_trees,
+            max_depth: max_depth,
+            min_samples_split: 2,
+            max_features: None,
+            n_threads: 1,
+            seed: None,
+
+            trees: Vec::new(),
+            oob_error: None,
+            importances: Vec::new(),
+        }
+    }
+
+    /// Sets the minimum number of samples required to split a node.
+    pub fn with_min_samples_split(mut self, n: usize) -> Self {
+        self.min_samples_split = n;
+        self
+    }
+
+    /// Sets the number of features considered at each split.
+    ///
+    /// Defaults to the square root of the input dimension.
+    pub fn with_max_features(mut self, n: usize) -> Self {
+        self.max_features = Some(n);
+        self
+    }
+
+    /// Sets the number of threads used to train the trees.
+    pub fn with_threads(mut self, n: usize) -> Self {
+        self.n_threads = n.max(1);
+        self
+    }
+
+    /// Seeds the random number generator, making training reproducible.
+    pub fn with_seed(mut self, seed: usize) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns the trained trees.
+    pub fn trees(&self) -> &[DecisionTree<T,L>] {
+        &self.trees
+    }
+
+    /// Returns the out-of-bag error estimate from the last training.
+    ///
+    /// Each sample is predicted by the trees that did not see it during training.
+    /// The error is the mean `Target::loss` over these predictions: the misclassification
+    /// rate for `usize` labels, the mean squared error for float labels.
+    ///
+    /// Returns `None` if no sample was left out of every bootstrap.
+    pub fn oob_error(&self) -> Option<f64> {
+        self.oob_error
+    }
+
+    /// Returns the permutation importance of each feature.
+    ///
+    /// This is the increase in out-of-bag error when the feature values are
+    /// shuffled among samples. Higher means more important.
+    pub fn feature_importances(&self) -> &[f64] {
+        &self.importances
+    }
+
+    fn predict(&self, input: &Vector<T>) -> L {
+        let predictions: Vec<L> = self.trees.iter().map(|tree| tree.classify(input)).collect();
+        L::aggregate(predictions.iter())
+    }
+
+    // Returns the mean out-of-bag loss over the samples.
+    fn compute_oob_error(&self, samples: &[Vector<T>], labels: &[L], inbag: &[Vec<bool>]) -> Option<f64> {
+        let mut total = 0f64;
+        let mut count = 0;
+
+        for (i,(sample,label)) in samples.iter().zip(labels.iter()).enumerate() {
+            let predictions: Vec<L> = self.trees.iter().zip(inbag.iter())
+                .filter(|&(_,mask)| !mask[i])
+                .map(|(tree,_)| tree.classify(sample))
+                .collect();
+            if predictions.is_empty() { continue; }
+
+            total += L::loss(&L::aggregate(predictions.iter()), label);
+            count += 1;
+        }
+
+        if count == 0 { None } else { Some(total / count as f64) }
+    }
+}
+
+// Trains one tree per seed, each on its own bootstrap sample.
+fn train_trees<T: Float, L: Target>(samples: &[Vector<T>], labels: &[L], seeds: &[usize],
+                                    max_depth: usize, min_samples_split: usize, max_features: usize)
+    -> Vec<(DecisionTree<T,L>, Vec<bool>)>
+{
+    let n = samples.len();
+    seeds.iter().map(|&seed| {
+        let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+        let indices: Vec<usize> = (0..n).map(|_| rng.gen_range(0, n)).collect();
+
+        let mut inbag = vec![false; n];
+        for &i in indices.iter() { inbag[i] = true; }
+
+        let mut tree = DecisionTree::new(max_depth, min_samples_split).with_max_features(max_features);
+        tree.train_on(samples, labels, &indices, &mut rng);
+        (tree, inbag)
+    }).collect()
+}
+
+impl <T, L> Classifier for RandomForest<T,L>
+    where T: Float + Send + Sync + 'static,
+          L: Target + Send + Sync + 'static
+{
+    type Input = Vector<T>;
+    type Label = L;
+
+    fn train(&mut self, samples: &[Vector<T>], labels: &[L]) {
+        self.trees.clear();
+        self.oob_error = None;
+        self.importances.clear();
+        if samples.is_empty() { return; }
+
+        let dim = samples[0].dim();
+        let max_features = self.max_features.unwrap_or((dim as f64).sqrt().round() as usize).max(1);
+
+        let seed = match self.seed {
+            Some(seed) => seed,
+            None => thread_rng().gen(),
+        };
+        let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+        let seeds: Vec<usize> = (0..self.n_trees).map(|_| rng.gen()).collect();
+
+        // Each thread gets a contiguous chunk of seeds, so the result does not
+        // depend on the number of threads.
+        let shared_samples = Arc::new(Vec::from(samples));
+        let shared_labels = Arc::new(Vec::from(labels));
+        let chunk_size = (self.n_trees + self.n_threads - 1) / self.n_threads;
+        let (max_depth, min_samples_split) = (self.max_depth, self.min_samples_split);
+
+        let handles: Vec<_> = seeds.chunks(chunk_size.max(1)).map(|chunk| {
+            let chunk = Vec::from(chunk);
+            let samples = shared_samples.clone();
+            let labels = shared_labels.clone();
+            thread::spawn(move || {
+                train_trees(&samples, &labels, &chunk, max_depth, min_samples_split, max_features)
+            })
+        }).collect();
+
+        let mut inbag = Vec::with_capacity(self.n_trees);
+        for handle in handles {
+            for (tree,mask) in handle.join().unwrap() {
+                self.trees.push(tree);
+                inbag.push(mask);
+            }
+        }
+
+        self.oob_error = self.compute_oob_error(samples, labels, &inbag);
+        let base = match self.oob_error {
+            None => return,
+            Some(error) => error,
+        };
+
+        // Permutation importance: shuffle one column at a time.
+        let mut permutation: Vec<usize> = (0..samples.len()).collect();
+        for feature in 0..dim {
+            rng.shuffle(&mut permutation);
+            let permuted: Vec<Vector<T>> = samples.iter().enumerate().map(|(i,x)| {
+                Vector::new(dim, |j| if j == feature { samples[permutation[i]][j] } else { x[j] })
+            }).collect();
+
+            let error = self.compute_oob_error(&permuted, labels, &inbag).unwrap_or(base);
+            self.importances.push(error - base);
+        }
+    }
+
+    /// Panics if the forest was not trained.
+    fn classify(&self, input: &Vector<T>) -> L {
+        if self.trees.is_empty() {
+            panic!("Random forest was not trained.");
+        }
+        self.predict(input)
+    }
+}
+
+#[test]
+fn test_forest_classification() {
+    // Class only depends on the first feature; the second one is noise.
+    let samples: Vec<Vector<f64>> = (0..40).map(|i| Vector::from_slice(&[(i % 10) as f64, ((i * 7) % 5) as f64])).collect();
+    let labels: Vec<usize> = (0..40).map(|i| if i % 10 >= 5 { 1 } else { 0 }).collect();
+
+    let mut forest = RandomForest::new(20, 4).with_max_features(2).with_threads(3).with_seed(42);
+    forest.train(&samples, &labels);
+
+    assert_eq!(forest.trees().len(), 20);
+    assert_eq!(forest.classify(&Vector::from_slice(&[1f64, 3f64])), 0);
+    assert_eq!(forest.classify(&Vector::from_slice(&[8f64, 3f64])), 1);
+
+    assert!(forest.oob_error().unwrap() < 0.1);
+    let importances = forest.feature_importances();
+    assert!(importances[0] > importances[1]);
+}
+
+#[test]
+fn test_forest_seed() {
+    let samples: Vec<Vector<f64>> = (0..30).map(|i| Vector::from_slice(&[i as f64, (i % 3) as f64])).collect();
+    let labels: Vec<f64> = (0..30).map(|i| (i / 10) as f64).collect();
+
+    let mut a = RandomForest::new(10, 3).with_seed(7).with_threads(1);
+    let mut b = RandomForest::new(10, 3).with_seed(7).with_threads(4);
+    a.train(&samples, &labels);
+    b.train(&samples, &labels);
+
+    assert_eq!(a.oob_error(), b.oob_error());
+    let x = Vector::from_slice(&[12.5f64, 1f64]);
+    assert_eq!(a.classify(&x), b.classify(&x));
+}
@@ -0,0 +1,560 @@
+use num::Float;
+
+use ml::{Classifier,Optimizer};
+use ml::nelder_mead::NelderMead;
+use ml::svm::{Kernel,RbfKernel};
+use alg::{Vector,Matrix};
+
+/// A kernel with hyperparameters that a `GaussianProcess` can fit.
+///
+/// Hyperparameters are exposed as logarithms, so that they stay positive while being optimized.
+pub trait GpKernel<T: Float>: Kernel<T> + Clone {
+    /// Returns the variance of a sample, between it and itself as a training sample.
+    ///
+    /// Differs from `apply(a, a)` for noise, which only applies to the diagonal.
+    fn diagonal(&self, a: &Vector<T>) -> T {
+        self.apply(a, a)
+    }
+
+    /// Returns the logarithms of the hyperparameters.
+    fn params(&self) -> Vec<T>;
+
+    /// Sets the hyperparameters from their logarithms, as many as returned by `params`.
+    fn set_params(&mut self, params: &[T]);
+
+    /// Returns the sum of both kernels.
+    fn plus<K: GpKernel<T>>(self, other: K) -> SumKernel<Self, K> where Self: Sized {
+        SumKernel(self, other)
+    }
+
+    /// Returns the product of both kernels.
+    fn times<K: GpKernel<T>>(self, other: K) -> ProductKernel<Self, K> where Self: Sized {
+        ProductKernel(self, other)
+    }
+}
+
+fn distance<T: Float>(a: &Vector<T>, b: &Vector<T>) -> T {
+    (a - b).norm_sq().sqrt()
+}
+
+/// The parameter is `ln(gamma)`, with `gamma = 1 / (2 * length_scale²)`.
+impl <T: Float> GpKernel<T> for RbfKernel<T> {
+    fn params(&self) -> Vec<T> {
+        vec![self.gamma.ln()]
+    }
+
+    fn set_params(&mut self, params: &[T]) {
+        self.gamma = params[0].exp();
+    }
+}
+
+/// Matérn kernel with `nu = 3/2`: `K(a,b) = (1 + √3 r / length_scale) * exp(-√3 r / length_scale)`, with `r = |a-b|`
+///
+/// Functions drawn from it are once differentiable.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Matern32Kernel<T> {
+    pub length_scale: T,
+}
+
+impl <T: Float> Matern32Kernel<T> {
+    pub fn new(length_scale: T) -> Self {
+        Matern32Kernel {
+            length_scale: length_scale,
+        }
+    }
+}
+
+impl <T: Float> Kernel<T> for Matern32Kernel<T> {
+    fn apply(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        let d = T::from(3).unwrap().sqrt() * distance(a, b) / self.length_scale;
+        (T::one() + d) * (-d).exp()
+    }
+}
+
+impl <T: Float> GpKernel<T> for Matern32Kernel<T> {
+    fn params(&self) -> Vec<T> {
+        vec![self.length_scale.ln()]
+    }
+
+    fn set_params(&mut self, params: &[T]) {
+        self.length_scale = params[0].exp();
+    }
+}
+
+/// Matérn kernel with `nu = 5/2`: `K(a,b) = (1 + d + d²/3) * exp(-d)`, with `d = √5 |a-b| / length_scale`
+///
+/// Functions drawn from it are twice differentiable.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Matern52Kernel<T> {
+    pub length_scale: T,
+}
+
+impl <T: Float> Matern52Kernel<T> {
+    pub fn new(length_scale: T) -> Self {
+        Matern52Kernel {
+            length_scale: length_scale,
+        }
+    }
+}
+
+impl <T: Float> Kernel<T> for Matern52Kernel<T> {
+    fn apply(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        let d = T::from(5).unwrap().sqrt() * distance(a, b) / self.length_scale;
+        (T::one() + d + d * d / T::from(3).unwrap()) * (-d).exp()
+    }
+}
+
+impl <T: Float> GpKernel<T> for Matern52Kernel<T> {
+    fn params(&self) -> Vec<T> {
+        vec![self.length_scale.ln()]
+    }
+
+    fn set_params(&mut self, params: &[T]) {
+        self.length_scale = params[0].exp();
+    }
+}
+
+/// Mixture of RBF kernels of different scales: `K(a,b) = (1 + |a-b|² / (2 * alpha * length_scale²)) ^ -alpha`
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct RationalQuadraticKernel<T> {
+    pub length_scale: T,
+    pub alpha: T,
+}
+
+impl <T: Float> RationalQuadraticKernel<T> {
+    pub fn new(length_scale: T, alpha: T) -> Self {
+        RationalQuadraticKernel {
+            length_scale: length_scale,
+            alpha: alpha,
+        }
+    }
+}
+
+impl <T: Float> Kernel<T> for RationalQuadraticKernel<T> {
+    fn apply(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        let two = T::from(2).unwrap();
+        let d = (a - b).norm_sq() / (two * self.alpha * self.length_scale * self.length_scale);
+        (T::one() + d).powf(-self.alpha)
+    }
+}
+
+impl <T: Float> GpKernel<T> for RationalQuadraticKernel<T> {
+    fn params(&self) -> Vec<T> {
+        vec![self.length_scale.ln(), self.alpha.ln()]
+    }
+
+    fn set_params(&mut self, params: &[T]) {
+        self.length_scale = params[0].exp();
+        self.alpha = params[1].exp();
+    }
+}
+
+/// `K(a,b) = exp(-2 * sin²(π |a-b| / period) / length_scale²)`
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct PeriodicKernel<T> {
+    pub length_scale: T,
+    pub period: T,
+}
+
+impl <T: Float> PeriodicKernel<T> {
+    pub fn new(length_scale: T, period: T) -> Self {
+        PeriodicKernel {
+            length_scale: length_scale,
+            period: period,
+        }
+    }
+}
+
+impl <T: Float> Kernel<T> for PeriodicKernel<T> {
+    fn apply(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        let pi = T::from(::std::f64::consts::PI).unwrap();
+        let s = (pi * distance(a, b) / self.period).sin() / self.length_scale;
+        (-T::from(2).unwrap() * s * s).exp()
+    }
+}
+
+impl <T: Float> GpKernel<T> for PeriodicKernel<T> {
+    fn params(&self) -> Vec<T> {
+        vec![self.length_scale.ln(), self.period.ln()]
+    }
+
+    fn set_params(&mut self, params: &[T]) {
+        self.length_scale = params[0].exp();
+        self.period = params[1].exp();
+    }
+}
+
+/// Independent noise of the given variance on each training sample: `K(a,b) = 0`, but `noise` on the diagonal.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct WhiteNoiseKernel<T> {
+    pub noise: T,
+}
+
+impl <T: Float> WhiteNoiseKernel<T> {
+    pub fn new(noise: T) -> Self {
+        WhiteNoiseKernel {
+            noise: noise,
+        }
+    }
+}
+
+impl <T: Float> Kernel<T> for WhiteNoiseKernel<T> {
+    fn apply(&self, _: &Vector<T>, _: &Vector<T>) -> T {
+        T::zero()
+    }
+}
+
+impl <T: Float> GpKernel<T> for WhiteNoiseKernel<T> {
+    fn diagonal(&self, _: &Vector<T>) -> T {
+        self.noise
+    }
+
+    fn params(&self) -> Vec<T> {
+        vec![self.noise.ln()]
+    }
+
+    fn set_params(&mut self, params: &[T]) {
+        self.noise = params[0].exp();
+    }
+}
+
+/// `K(a,b) = value`. Multiplied with another kernel, scales its variance.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct ConstantKernel<T> {
+    pub value: T,
+}
+
+impl <T: Float> ConstantKernel<T> {
+    pub fn new(value: T) -> Self {
+        ConstantKernel {
+            value: value,
+        }
+    }
+}
+
+impl <T: Float> Kernel<T> for ConstantKernel<T> {
+    fn apply(&self, _: &Vector<T>, _: &Vector<T>) -> T {
+        self.value
+    }
+}
+
+impl <T: Float> GpKernel<T> for ConstantKernel<T> {
+    fn params(&self) -> Vec<T> {
+        vec![self.value.ln()]
+    }
+
+    fn set_params(&mut self, params: &[T]) {
+        self.value = params[0].exp();
+    }
+}
+
+/// `K(a,b) = K1(a,b) + K2(a,b)`, with the parameters of `K1` first.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct SumKernel<A, B>(pub A, pub B);
+
+impl <T: Float, A: Kernel<T>, B: Kernel<T>> Kernel<T> for SumKernel<A,B> {
+    fn apply(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        self.0.apply(a, b) + self.1.apply(a, b)
+    }
+}
+
+impl <T: Float, A: GpKernel<T>, B: GpKernel<T>> GpKernel<T> for SumKernel<A,B> {
+    fn diagonal(&self, a: &Vector<T>) -> T {
+        self.0.diagonal(a) + self.1.diagonal(a)
+    }
+
+    fn params(&self) -> Vec<T> {
+        let mut params = self.0.params();
+        params.extend(self.1.params());
+        params
+    }
+
+    fn set_params(&mut self, params: &[T]) {
+        let n = self.0.params().len();
+        self.0.set_params(&params[..n]);
+        self.1.set_params(&params[n..]);
+    }
+}
+
+/// `K(a,b) = K1(a,b) * K2(a,b)`, with the parameters of `K1` first.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct ProductKernel<A, B>(pub A, pub B);
+
+impl <T: Float, A: Kernel<T>, B: Kernel<T>> Kernel<T> for ProductKernel<A,B> {
+    fn apply(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        self.0.apply(a, b) * self.1.apply(a, b)
+    }
+}
+
+impl <T: Float, A: GpKernel<T>, B: GpKernel<T>> GpKernel<T> for ProductKernel<A,B> {
+    fn diagonal(&self, a: &Vector<T>) -> T {
+        self.0.diagonal(a) * self.1.diagonal(a)
+    }
+
+    fn params(&self) -> Vec<T> {
+        let mut params = self.0.params();
+        params.extend(self.1.params());
+        params
+    }
+
+    fn set_params(&mut self, params: &[T]) {
+        let n = self.0.params().len();
+        self.0.set_params(&params[..n]);
+        self.1.set_params(&params[n..]);
+    }
+}
+
+/// Gaussian process regression, predicting a mean and a variance for each input.
+///
+/// The prior mean is the mean of the training labels. Unless disabled, the kernel hyperparameters
+/// are fitted by maximizing the log marginal likelihood of the training data, with `NelderMead`
+/// starting from the given kernel.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct GaussianProcess<T, K> {
+    kernel: K,
+    alpha: T,
+    optimize: bool,
+    max_iter: usize,
+
+    samples: Vec<Vector<T>>,
+    label_mean: T,
+    // Cholesky factor of the training covariance
+    cholesky: Matrix<T>,
+    // Covariance inverse times the centered labels
+    weights: Vector<T>,
+    log_marginal_likelihood: T,
+}
+
+// Result of conditioning on the training data.
+struct Posterior<T> {
+    cholesky: Matrix<T>,
+    weights: Vector<T>,
+    log_marginal_likelihood: T,
+}
+
+// Bound of the hyperparameter logarithms while optimizing.
+const MAX_LOG_PARAM: f64 = 11.5;
+
+impl <T: Float, K: GpKernel<T>> GaussianProcess<T,K> {
+    /// Creates a new Gaussian process with the given kernel, as the starting point of the optimization.
+    pub fn new(kernel: K) -> Self {
+        GaussianProcess {
+            kernel: kernel,
+            alpha: T::from(1e-10).unwrap(),
+            optimize: true,
+            max_iter: 200,
+
+            samples: Vec::new(),
+            label_mean: T::zero(),
+            cholesky: Matrix::dummy(),
+            weights: Vector::dummy(),
+            log_marginal_likelihood: T::nan(),
+        }
+    }
+
+    /// Sets the value added to the diagonal of the training covariance, for numerical stability.
+    pub fn with_alpha(mut self, alpha: T) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Keeps the kernel hyperparameters as given.
+    pub fn without_optimizer(mut self) -> Self {
+        self.optimize = false;
+        self
+    }
+
+    /// Sets the maximum number of iterations of the hyperparameter optimization.
+    pub fn with_max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    /// Returns the kernel, with its fitted hyperparameters once trained.
+    pub fn kernel(&self) -> &K {
+        &self.kernel
+    }
+
+    /// Returns the log marginal likelihood of the training data.
+    pub fn log_marginal_likelihood(&self) -> T {
+        self.log_marginal_likelihood
+    }
+
+    /// Returns the predictive mean and variance for the input.
+    pub fn predict(&self, input: &Vector<T>) -> (T, T) {
+        if self.samples.is_empty() {
+            panic!("GaussianProcess was not trained.");
+        }
+
+        let k = Vector::new(self.samples.len(), |i| self.kernel.apply(input, &self.samples[i]));
+        let mean = self.label_mean + k.dot(&self.weights);
+        let v = self.cholesky.solve_lower_triangular(&k);
+        let variance = (self.kernel.diagonal(input) - v.norm_sq()).max(T::zero());
+        (mean, variance)
+    }
+
+    // Conditions the kernel on the samples, or returns `None` if their covariance isn't positive definite.
+    fn posterior(&self, kernel: &K, samples: &[Vector<T>], labels: &Vector<T>) -> Option<Posterior<T>> {
+        let n = samples.len();
+        let covariance = Matrix::new(n, n, |x,y| {
+            if x == y { kernel.diagonal(&samples[x]) + self.alpha } else { kernel.apply(&samples[x], &samples[y]) }
+        });
+        let cholesky = covariance.cholesky();
+        if (0..n).any(|i| !(cholesky[(i,i)] > T::zero())) {
+            return None;
+        }
+
+        let z = cholesky.solve_lower_triangular(labels);
+        let weights = cholesky.transpose().solve_upper_triangular(&z);
+
+        let two = T::from(2).unwrap();
+        let log_det = (0..n).fold(T::zero(), |a,i| a + cholesky[(i,i)].ln());
+        let log_2pi = T::from(2f64 * ::std::f64::consts::PI).unwrap().ln();
+        let log_marginal_likelihood = -labels.dot(&weights) / two - log_det - T::from(n).unwrap() * log_2pi / two;
+        if !log_marginal_likelihood.is_finite() {
+            return None;
+        }
+
+        Some(Posterior {
+            cholesky: cholesky,
+            weights: weights,
+            log_marginal_likelihood: log_marginal_likelihood,
+        })
+    }
+}
+
+impl <T: Float, K: GpKernel<T>> Classifier for GaussianProcess<T,K> {
+    type Input = Vector<T>;
+    type Label = T;
+
+    fn train(&mut self, samples: &[Vector<T>], labels: &[T]) {
+        if samples.is_empty() {
+            panic!("GaussianProcess needs at least one sample.");
+        }
+        if samples.len() != labels.len() {
+            panic!("GaussianProcess needs one label per sample.");
+        }
+
+        let label_mean = labels.iter().fold(T::zero(), |a,&b| a+b) / T::from(labels.len()).unwrap();
+        let centered = Vector::new(labels.len(), |i| labels[i] - label_mean);
+
+        let start = self.kernel.params();
+        if self.optimize && !start.is_empty() {
+            let bound = T::from(MAX_LOG_PARAM).unwrap();
+            let with_params = |params: &[T]| {
+                let clamped: Vec<T> = params.iter().map(|&p| p.max(-bound).min(bound)).collect();
+                let mut kernel = self.kernel.clone();
+                kernel.set_params(&clamped);
+                kernel
+            };
+
+            let optimizer = NelderMead::new(Vector::from_vec(start)).with_max_iter(self.max_iter);
+            let best = optimizer.optimize(|params| {
+                match self.posterior(&with_params(params.data()), samples, &centered) {
+                    Some(posterior) => -posterior.log_marginal_likelihood,
+                    None => T::infinity(),
+                }
+            });
+            let kernel = with_params(best.data());
+            // The best kernel tried is only invalid if they all were, including the given one.
+            if self.posterior(&kernel, samples, &centered).is_some() {
+                self.kernel = kernel;
+            }
+        }
+
+        let posterior = match self.posterior(&self.kernel, samples, &centered) {
+            Some(posterior) => posterior,
+            None => panic!("GaussianProcess covariance is not positive definite."),
+        };
+        self.samples = samples.to_vec();
+        self.label_mean = label_mean;
+        self.cholesky = posterior.cholesky;
+        self.weights = posterior.weights;
+        self.log_marginal_likelihood = posterior.log_marginal_likelihood;
+    }
+
+    fn classify(&self, input: &Vector<T>) -> T {
+        self.predict(input).0
+    }
+}
+
+#[test]
+fn test_gp_kernels() {
+    let a = Vector::from_slice(&[0f64, 0f64]);
+    let b = Vector::from_slice(&[3f64, 4f64]);
+
+    let d = 3f64.sqrt();
+    assert!((Matern32Kernel::new(5f64).apply(&a, &b) - (1f64 + d) * (-d).exp()).abs() < 1e-12);
+    assert!((RationalQuadraticKernel::new(5f64, 0.5f64).apply(&a, &b) - 0.5f64.sqrt()).abs() < 1e-12);
+    assert!((PeriodicKernel::new(1f64, 5f64).apply(&a, &b) - 1f64).abs() < 1e-12);
+
+    // Noise only appears on the diagonal.
+    let kernel = ConstantKernel::new(2f64).times(Matern52Kernel::new(1f64)).plus(WhiteNoiseKernel::new(0.1f64));
+    assert_eq!(kernel.apply(&a, &a), 2f64);
+    assert!((kernel.diagonal(&a) - 2.1f64).abs() < 1e-12);
+
+    let mut params = kernel.params();
+    assert_eq!(params.len(), 3);
+    params[1] = 3f64.ln();
+    let mut kernel = kernel;
+    kernel.set_params(&params);
+    assert!(((kernel.0).1.length_scale - 3f64).abs() < 1e-12);
+}
+
+#[cfg(test)]
+fn sine_data() -> (Vec<Vector<f64>>, Vec<f64>) {
+    let samples: Vec<Vector<f64>> = (0..12).map(|i| Vector::from_slice(&[i as f64 * 0.5f64])).collect();
+    let labels = samples.iter().map(|x| x[0].sin()).collect();
+    (samples, labels)
+}
+
+#[test]
+fn test_gaussian_process() {
+    let (samples, labels) = sine_data();
+
+    let mut gp = GaussianProcess::new(RbfKernel::new(0.5f64)).without_optimizer();
+    gp.train(&samples, &labels);
+
+    // Training points are interpolated, with no uncertainty.
+    let (mean, variance) = gp.predict(&samples[3]);
+    assert!((mean - labels[3]).abs() < 1e-4);
+    assert!(variance < 1e-4);
+
+    // Between them, predictions are close; far away, the prior is recovered.
+    let (mean, variance) = gp.predict(&Vector::from_slice(&[1.25f64]));
+    assert!((mean - 1.25f64.sin()).abs() < 1e-2);
+    assert!(variance > 0f64 && variance < 1e-2);
+    let (_, variance) = gp.predict(&Vector::from_slice(&[50f64]));
+    assert!((variance - 1f64).abs() < 1e-6);
+}
+
+#[test]
+fn test_gaussian_process_fit() {
+    let (samples, labels) = sine_data();
+    let kernel = ConstantKernel::new(1f64).times(Matern52Kernel::new(10f64)).plus(WhiteNoiseKernel::new(1e-3));
+
+    let mut fixed = GaussianProcess::new(kernel.clone()).without_optimizer();
+    fixed.train(&samples, &labels);
+    let mut fitted = GaussianProcess::new(kernel);
+    fitted.train(&samples, &labels);
+
+    assert!(fitted.log_marginal_likelihood() > fixed.log_marginal_likelihood());
+    assert!(fitted.kernel().params() != fixed.kernel().params());
+    assert!((fitted.classify(&Vector::from_slice(&[2.25f64])) - 2.25f64.sin()).abs() < 0.05f64);
+}
+
+#[test]
+#[should_panic]
+fn test_gaussian_process_labels() {
+    let mut gp = GaussianProcess::new(RbfKernel::new(1f64));
+    gp.train(&[Vector::from_slice(&[0f64]), Vector::from_slice(&[1f64])], &[1f64]);
+}
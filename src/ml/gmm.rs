@@ -0,0 +1,377 @@
+use num::Float;
+use rand::Rng;
+use rand::distributions::{IndependentSample,Normal};
+
+use ml::Clusterer;
+use ml::kmeans::KMeans;
+use alg::{Vector,Matrix};
+
+/// Shape of the covariance matrices of a `GaussianMixture`.
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub enum CovarianceType {
+    /// Each component has its own general covariance matrix.
+    Full,
+    /// Each component has its own diagonal covariance matrix.
+    Diagonal,
+    /// Each component has its own single variance.
+    Spherical,
+    /// All components share the same general covariance matrix.
+    Tied,
+}
+
+/// Gaussian mixture model, fitted by expectation-maximization.
+///
+/// Provides soft clustering and density estimation.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct GaussianMixture<T> {
+    k: usize,
+    covariance_type: CovarianceType,
+    max_iter: usize,
+    tol: T,
+    reg_covar: T,
+    seed: Option<usize>,
+
+    weights: Vec<T>,
+    means: Vec<Vector<T>>,
+    covariances: Vec<Matrix<T>>,
+    // Cholesky factor of each covariance
+    cholesky: Vec<Matrix<T>>,
+    log_likelihood: T,
+    converged: bool,
+}
+
+impl <T: Float> GaussianMixture<T> {
+    /// Creates a new mixture with `k` components.
+    pub fn new(k: usize, covariance_type: CovarianceType) -> Self {
+        GaussianMixture {
+            k: k,
+            covariance_type: covariance_type,
+            max_iter: 100,
+            tol: T::from(1e-3).unwrap(),
+            reg_covar: T::from(1e-6).unwrap(),
+            seed: None,
+
+            weights: Vec::new(),
+            means: Vec::new(),
+            covariances: Vec::new(),
+            cholesky: Vec::new(),
+            log_likelihood: T::neg_infinity(),
+            converged: false,
+        }
+    }
+
+    /// Sets the maximum number of EM iterations. Defaults to 100.
+    pub fn with_max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    /// Stops when the mean log-likelihood per sample improves by less than `tol`.
+    pub fn with_tolerance(mut self, tol: T) -> Self {
+        self.tol = tol;
+        self
+    }
+
+    /// Sets the value added to the covariance diagonals, keeping them positive-definite.
+    ///
+    /// Defaults to `1e-6`.
+    pub fn with_reg_covar(mut self, reg_covar: T) -> Self {
+        self.reg_covar = reg_covar;
+        self
+    }
+
+    /// Seeds the k-means initialization, making fitting reproducible.
+    pub fn with_seed(mut self, seed: usize) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns the mixing weight of each component.
+    pub fn weights(&self) -> &[T] {
+        &self.weights
+    }
+
+    /// Returns the mean of each component.
+    pub fn means(&self) -> &[Vector<T>] {
+        &self.means
+    }
+
+    /// Returns the covariance matrix of each component.
+    ///
+    /// Diagonal and spherical covariances are returned as full matrices.
+    /// With tied covariances, every component has the same matrix.
+    pub fn covariances(&self) -> &[Matrix<T>] {
+        &self.covariances
+    }
+
+    /// Returns the total log-likelihood of the training samples.
+    pub fn log_likelihood(&self) -> T {
+        self.log_likelihood
+    }
+
+    /// Returns `true` if the last fit converged before `max_iter` iterations.
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+
+    /// Returns the log of the probability density at the given point.
+    pub fn score_sample(&self, input: &Vector<T>) -> T {
+        log_sum_exp(&self.weighted_log_prob(input))
+    }
+
+    /// Returns the posterior probability of each component for the given point.
+    pub fn predict_proba(&self, input: &Vector<T>) -> Vector<T> {
+        let log_prob = self.weighted_log_prob(input);
+        let total = log_sum_exp(&log_prob);
+        Vector::new(self.k, |i| (log_prob[i] - total).exp())
+    }
+
+    /// Returns the number of free parameters of the model.
+    pub fn n_parameters(&self) -> usize {
+        let d = self.means.first().map_or(0, |m| m.dim());
+        let cov = match self.covariance_type {
+            CovarianceType::Full => self.k * d * (d + 1) / 2,
+            CovarianceType::Diagonal => self.k * d,
+            CovarianceType::Spherical => self.k,
+            CovarianceType::Tied => d * (d + 1) / 2,
+        };
+        cov + self.k * d + self.k - 1
+    }
+
+    /// Bayesian information criterion on the given samples. Lower is better.
+    pub fn bic(&self, samples: &[Vector<T>]) -> T {
+        let n = T::from(samples.len()).unwrap();
+        let two = T::one() + T::one();
+        -two * self.total_score(samples) + T::from(self.n_parameters()).unwrap() * n.ln()
+    }
+
+    /// Akaike information criterion on the given samples. Lower is better.
+    pub fn aic(&self, samples: &[Vector<T>]) -> T {
+        let two = T::one() + T::one();
+        -two * self.total_score(samples) + two * T::from(self.n_parameters()).unwrap()
+    }
+
+    /// Draws `n` samples from the fitted model.
+    pub fn sample<R: Rng>(&self, n: usize, rng: &mut R) -> Vec<Vector<T>> {
+        let normal = Normal::new(0f64, 1f64);
+        (0..n).map(|_| {
+            let mut target = T::from(rng.gen::<f64>()).unwrap();
+            let mut component = self.k - 1;
+            for (i,&w) in self.weights.iter().enumerate() {
+                if target < w {
+                    component = i;
+                    break;
+                }
+                target = target - w;
+            }
+
+            let mean = &self.means[component];
+            let z = Vector::new(mean.dim(), |_| T::from(normal.ind_sample(rng)).unwrap());
+            mean + &self.cholesky[component] * z
+        }).collect()
+    }
+
+    fn total_score(&self, samples: &[Vector<T>]) -> T {
+        samples.iter().map(|x| self.score_sample(x)).fold(T::zero(), |a,b| a+b)
+    }
+
+    // Log of `weight * density` for each component.
+    fn weighted_log_prob(&self, input: &Vector<T>) -> Vec<T> {
+        let two = T::one() + T::one();
+        let log_2pi = T::from(2f64 * ::std::f64::consts::PI).unwrap().ln();
+        let d = T::from(input.dim()).unwrap();
+
+        (0..self.k).map(|i| {
+            let l = &self.cholesky[i];
+            let z = l.solve_lower_triangular(&(input - &self.means[i]));
+            let log_det = (0..l.n).map(|j| l[(j,j)].ln()).fold(T::zero(), |a,b| a+b) * two;
+            self.weights[i].ln() - (d * log_2pi + log_det + z.norm_sq()) / two
+        }).collect()
+    }
+
+    // Returns the log-likelihood of the samples, and fills the responsibilities.
+    fn e_step(&self, samples: &[Vector<T>], resp: &mut Vec<Vec<T>>) -> T {
+        let mut total = T::zero();
+        for (x,r) in samples.iter().zip(resp.iter_mut()) {
+            let log_prob = self.weighted_log_prob(x);
+            let norm = log_sum_exp(&log_prob);
+            total = total + norm;
+            for (r,lp) in r.iter_mut().zip(log_prob.into_iter()) {
+                *r = (lp - norm).exp();
+            }
+        }
+        total
+    }
+
+    fn m_step(&mut self, samples: &[Vector<T>], resp: &[Vec<T>]) {
+        let n = samples.len();
+        let d = samples[0].dim();
+        let eps = T::from(10f64).unwrap() * T::epsilon();
+
+        let counts: Vec<T> = (0..self.k).map(|i| resp.iter().map(|r| r[i]).fold(eps, |a,b| a+b)).collect();
+        self.weights = counts.iter().map(|&c| c / T::from(n).unwrap()).collect();
+        self.means = (0..self.k).map(|i| {
+            samples.iter().zip(resp.iter()).fold(Vector::zero(d), |a,(x,r)| a + x * r[i]) / counts[i]
+        }).collect();
+
+        // Weighted scatter matrix of each component
+        let scatter: Vec<Matrix<T>> = (0..self.k).map(|i| {
+            samples.iter().zip(resp.iter()).fold(Matrix::zero(d, d), |a,(x,r)| {
+                let centered = x - &self.means[i];
+                &a + &(&centered.outer_product(&centered) * r[i])
+            })
+        }).collect();
+
+        let reg = Matrix::scalar(d, self.reg_covar);
+        self.covariances = match self.covariance_type {
+            CovarianceType::Full => {
+                scatter.iter().zip(counts.iter()).map(|(s,&c)| &(s / c) + &reg).collect()
+            },
+            CovarianceType::Diagonal => {
+                scatter.iter().zip(counts.iter()).map(|(s,&c)| {
+                    Matrix::diagonal(Vector::new(d, |j| s[(j,j)] / c + self.reg_covar))
+                }).collect()
+            },
+            CovarianceType::Spherical => {
+                scatter.iter().zip(counts.iter()).map(|(s,&c)| {
+                    let trace = (0..d).map(|j| s[(j,j)]).fold(T::zero(), |a,b| a+b);
+                    Matrix::scalar(d, trace / (c * T::from(d).unwrap()) + self.reg_covar)
+                }).collect()
+            },
+            CovarianceType::Tied => {
+                let total = scatter.iter().fold(Matrix::zero(d, d), |a,s| &a + s);
+                let shared = &(&total / T::from(n).unwrap()) + &reg;
+                vec![shared; self.k]
+            },
+        };
+
+        self.cholesky = self.covariances.iter().map(|c| c.cholesky()).collect();
+    }
+}
+
+fn log_sum_exp<T: Float>(values: &[T]) -> T {
+    let max = values.iter().fold(T::neg_infinity(), |a,&b| a.max(b));
+    if max == T::neg_infinity() { return max; }
+    max + values.iter().map(|&v| (v - max).exp()).fold(T::zero(), |a,b| a+b).ln()
+}
+
+impl <T: Float> Clusterer for GaussianMixture<T> {
+    type Input = Vector<T>;
+
+    fn fit(&mut self, samples: &[Vector<T>]) {
+        self.converged = false;
+        if samples.is_empty() || self.k == 0 { return; }
+
+        // Initialize with hard assignments from k-means
+        let mut kmeans = KMeans::new(self.k);
+        if let Some(seed) = self.seed {
+            kmeans = kmeans.with_seed(seed);
+        }
+        kmeans.fit(samples);
+
+        let mut resp: Vec<Vec<T>> = samples.iter().map(|x| {
+            let label = kmeans.predict(x);
+            (0..self.k).map(|i| if i == label { T::one() } else { T::zero() }).collect()
+        }).collect();
+        self.m_step(samples, &resp);
+
+        let n = T::from(samples.len()).unwrap();
+        let mut previous = T::neg_infinity();
+        for _ in 0..self.max_iter {
+            let log_likelihood = self.e_step(samples, &mut resp);
+            self.m_step(samples, &resp);
+
+            if (log_likelihood - previous).abs() / n < self.tol {
+                self.converged = true;
+                break;
+            }
+            previous = log_likelihood;
+        }
+
+        self.log_likelihood = self.total_score(samples);
+    }
+
+    /// Returns the most likely component.
+    ///
+    /// Panics if the model was not fitted.
+    fn predict(&self, input: &Vector<T>) -> usize {
+        if self.means.is_empty() {
+            panic!("Gaussian mixture was not fitted.");
+        }
+        let log_prob = self.weighted_log_prob(input);
+        let mut best = 0;
+        for (i,&lp) in log_prob.iter().enumerate() {
+            if lp > log_prob[best] { best = i; }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+fn two_blobs() -> Vec<Vector<f64>> {
+    // Two groups with different spreads
+    let mut samples = Vec::new();
+    for i in 0..20 {
+        let t = i as f64 * 0.7;
+        samples.push(Vector::from_slice(&[t.cos() * 0.5, t.sin() * 0.5]));
+        samples.push(Vector::from_slice(&[10f64 + t.cos() * 2f64, 10f64 + t.sin()]));
+    }
+    samples
+}
+
+#[test]
+fn test_gmm_types() {
+    let samples = two_blobs();
+
+    for &cov in [CovarianceType::Full, CovarianceType::Diagonal, CovarianceType::Spherical, CovarianceType::Tied].iter() {
+        let mut gmm = GaussianMixture::new(2, cov).with_seed(1);
+        gmm.fit(&samples);
+
+        assert!(gmm.converged());
+        let a = gmm.predict(&samples[0]);
+        let b = gmm.predict(&samples[1]);
+        assert!(a != b);
+        for pair in samples.chunks(2) {
+            assert_eq!(gmm.predict(&pair[0]), a);
+            assert_eq!(gmm.predict(&pair[1]), b);
+        }
+
+        let proba = gmm.predict_proba(&samples[0]);
+        assert!((proba[0] + proba[1] - 1f64).abs() < 1e-9);
+        assert!((gmm.weights()[0] - 0.5f64).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn test_gmm_criteria() {
+    let samples = two_blobs();
+
+    let mut one = GaussianMixture::new(1, CovarianceType::Full).with_seed(1);
+    let mut two = GaussianMixture::new(2, CovarianceType::Full).with_seed(1);
+    one.fit(&samples);
+    two.fit(&samples);
+
+    assert_eq!(two.n_parameters(), 2 * 3 + 2 * 2 + 1);
+    assert!(two.bic(&samples) < one.bic(&samples));
+    assert!(two.aic(&samples) < one.aic(&samples));
+    assert!((two.log_likelihood() - samples.iter().map(|x| two.score_sample(x)).fold(0f64, |a,b| a+b)).abs() < 1e-9);
+}
+
+#[test]
+fn test_gmm_sample() {
+    use rand::{SeedableRng,StdRng};
+
+    let samples = two_blobs();
+    let mut gmm = GaussianMixture::new(2, CovarianceType::Diagonal).with_seed(1);
+    gmm.fit(&samples);
+
+    let mut rng: StdRng = SeedableRng::from_seed(&[3usize][..]);
+    let drawn = gmm.sample(2000, &mut rng);
+    let mean = drawn.iter().fold(Vector::zero(2), |a,x| a + x.clone()) / 2000f64;
+
+    // The overall mean is halfway between the two groups.
+    assert!((mean[0] - 5f64).abs() < 0.3);
+    assert!((mean[1] - 5f64).abs() < 0.3);
+}
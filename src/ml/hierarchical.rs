@@ -0,0 +1,264 @@
+use num::Float;
+
+use ml::neighbors::Distance;
+use alg::{Vector,Matrix};
+
+/// How the distance between two clusters is computed from their samples.
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub enum Linkage {
+    /// Distance between the closest samples.
+    Single,
+    /// Distance between the farthest samples.
+    Complete,
+    /// Mean distance between samples of each cluster.
+    Average,
+    /// Increase in within-cluster variance. Only meaningful with the `Euclidean` distance.
+    Ward,
+}
+
+/// A merge of two clusters in a `Dendrogram`.
+#[derive(Clone,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Merge<T> {
+    /// First merged cluster: `i < n` is the sample `i`, `n + j` is the cluster from the `j`-th merge.
+    pub a: usize,
+    /// Second merged cluster, with `a < b`.
+    pub b: usize,
+    /// Linkage distance between the two clusters.
+    pub distance: T,
+    /// Number of samples in the merged cluster.
+    pub size: usize,
+}
+
+/// Full merge history of a hierarchical clustering, in order of increasing distance.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Dendrogram<T> {
+    n_samples: usize,
+    merges: Vec<Merge<T>>,
+}
+
+impl <T: Float> Dendrogram<T> {
+    /// Returns the number of clustered samples.
+    pub fn n_samples(&self) -> usize {
+        self.n_samples
+    }
+
+    /// Returns the `n - 1` merges.
+    pub fn merges(&self) -> &[Merge<T>] {
+        &self.merges
+    }
+
+    /// Returns the linkage matrix: one row per merge, with columns `a`, `b`, `distance` and `size`.
+    pub fn linkage_matrix(&self) -> Matrix<T> {
+        let mut matrix = Matrix::zero(4, self.merges.len());
+        for (y,merge) in self.merges.iter().enumerate() {
+            matrix[(0,y)] = T::from(merge.a).unwrap();
+            matrix[(1,y)] = T::from(merge.b).unwrap();
+            matrix[(2,y)] = merge.distance;
+            matrix[(3,y)] = T::from(merge.size).unwrap();
+        }
+        matrix
+    }
+
+    /// Returns the cluster of each sample when stopping at `n_clusters` clusters.
+    ///
+    /// Clusters are numbered in order of their first sample.
+    pub fn cut(&self, n_clusters: usize) -> Vec<usize> {
+        let n_merges = self.n_samples - n_clusters.max(1).min(self.n_samples);
+        self.labels(n_merges)
+    }
+
+    /// Returns the cluster of each sample when only merging clusters closer than `threshold`.
+    ///
+    /// Clusters are numbered in order of their first sample.
+    pub fn cut_at_distance(&self, threshold: T) -> Vec<usize> {
+        let n_merges = self.merges.iter().take_while(|m| m.distance <= threshold).count();
+        self.labels(n_merges)
+    }
+
+    // Labels after applying the first `n_merges` merges.
+    fn labels(&self, n_merges: usize) -> Vec<usize> {
+        let n = self.n_samples;
+        let mut parent: Vec<usize> = (0..n + n_merges).collect();
+        for (j,merge) in self.merges[..n_merges].iter().enumerate() {
+            parent[merge.a] = n + j;
+            parent[merge.b] = n + j;
+        }
+
+        let mut ids = vec![None; n + n_merges];
+        let mut next = 0;
+        (0..n).map(|i| {
+            let mut root = i;
+            while parent[root] != root { root = parent[root]; }
+            match ids[root] {
+                Some(id) => id,
+                None => {
+                    ids[root] = Some(next);
+                    next += 1;
+                    next - 1
+                }
+            }
+        }).collect()
+    }
+}
+
+/// Bottom-up hierarchical clustering: repeatedly merges the two closest clusters.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Agglomerative<D> {
+    linkage: Linkage,
+    distance: D,
+}
+
+impl <D> Agglomerative<D> {
+    /// Creates a new hierarchical clusterer.
+    pub fn new(linkage: Linkage, distance: D) -> Self {
+        Agglomerative {
+            linkage: linkage,
+            distance: distance,
+        }
+    }
+
+    /// Builds the dendrogram of the given samples.
+    pub fn fit<T: Float>(&self, samples: &[Vector<T>]) -> Dendrogram<T>
+        where D: Distance<T>
+    {
+        let n = samples.len();
+        let mut dist = vec![vec![T::zero(); n]; n];
+        for i in 0..n {
+            for j in 0..i {
+                let d = self.distance.distance(&samples[i], &samples[j]);
+                dist[i][j] = d;
+                dist[j][i] = d;
+            }
+        }
+
+        // Nearest-neighbor chain: valid since every linkage here is reducible.
+        // A merged cluster takes the slot of one of its halves.
+        let mut active = vec![true; n];
+        let mut sizes = vec![1usize; n];
+        let mut raw: Vec<(usize, usize, T)> = Vec::with_capacity(n.saturating_sub(1));
+        let mut chain: Vec<usize> = Vec::new();
+
+        while raw.len() + 1 < n {
+            if chain.is_empty() {
+                chain.push((0..n).find(|&i| active[i]).unwrap());
+            }
+
+            let a = chain[chain.len() - 1];
+            let previous = if chain.len() >= 2 { Some(chain[chain.len() - 2]) } else { None };
+
+            // Closest active cluster, preferring the previous one in the chain on ties.
+            let mut b = previous;
+            for k in 0..n {
+                if k == a || !active[k] { continue; }
+                if b.map_or(true, |b| dist[a][k] < dist[a][b]) { b = Some(k); }
+            }
+            let b = b.unwrap();
+
+            if Some(b) != previous {
+                chain.push(b);
+                continue;
+            }
+
+            chain.pop();
+            chain.pop();
+            raw.push((a, b, dist[a][b]));
+
+            for k in 0..n {
+                if !active[k] || k == a || k == b { continue; }
+                let d = self.update(dist[a][k], dist[b][k], dist[a][b], sizes[a], sizes[b], sizes[k]);
+                dist[b][k] = d;
+                dist[k][b] = d;
+            }
+            active[a] = false;
+            sizes[b] += sizes[a];
+        }
+
+        // Order the merges by distance, and renumber clusters after their merge.
+        raw.sort_by(|x,y| x.2.partial_cmp(&y.2).unwrap());
+        let mut parent: Vec<usize> = (0..n).collect();
+        // Current dendrogram id of each set root
+        let mut ids: Vec<usize> = (0..n).collect();
+        let mut merges: Vec<Merge<T>> = Vec::with_capacity(raw.len());
+        for (j,&(a,b,d)) in raw.iter().enumerate() {
+            let ra = find(&mut parent, a);
+            let rb = find(&mut parent, b);
+            let (ia, ib) = (ids[ra].min(ids[rb]), ids[ra].max(ids[rb]));
+            let size_a = if ia < n { 1 } else { merges[ia - n].size };
+            let size_b = if ib < n { 1 } else { merges[ib - n].size };
+
+            parent[ra] = rb;
+            ids[rb] = n + j;
+            merges.push(Merge { a: ia, b: ib, distance: d, size: size_a + size_b });
+        }
+
+        Dendrogram {
+            n_samples: n,
+            merges: merges,
+        }
+    }
+
+    // Lance-Williams update: distance from `k` to the union of `i` and `j`.
+    fn update<T: Float>(&self, d_ik: T, d_jk: T, d_ij: T, n_i: usize, n_j: usize, n_k: usize) -> T {
+        let (n_i, n_j, n_k) = (T::from(n_i).unwrap(), T::from(n_j).unwrap(), T::from(n_k).unwrap());
+        match self.linkage {
+            Linkage::Single => d_ik.min(d_jk),
+            Linkage::Complete => d_ik.max(d_jk),
+            Linkage::Average => (n_i * d_ik + n_j * d_jk) / (n_i + n_j),
+            Linkage::Ward => {
+                let sq = (n_i + n_k) * d_ik * d_ik + (n_j + n_k) * d_jk * d_jk - n_k * d_ij * d_ij;
+                (sq / (n_i + n_j + n_k)).max(T::zero()).sqrt()
+            },
+        }
+    }
+}
+
+fn find(parent: &mut Vec<usize>, mut i: usize) -> usize {
+    while parent[i] != i {
+        let grand = parent[parent[i]];
+        parent[i] = grand;
+        i = grand;
+    }
+    i
+}
+
+#[test]
+fn test_hierarchical() {
+    use ml::neighbors::Euclidean;
+
+    let samples: Vec<Vector<f64>> = [0f64, 1f64, 5f64, 6f64, 20f64].iter().map(|&x| Vector::from_slice(&[x])).collect();
+
+    let dendrogram = Agglomerative::new(Linkage::Single, Euclidean).fit(&samples);
+    let merges = dendrogram.merges();
+    assert_eq!(merges.len(), 4);
+    assert_eq!(merges[0], Merge { a: 0, b: 1, distance: 1f64, size: 2 });
+    assert_eq!(merges[1], Merge { a: 2, b: 3, distance: 1f64, size: 2 });
+    assert_eq!(merges[2], Merge { a: 5, b: 6, distance: 4f64, size: 4 });
+    assert_eq!(merges[3], Merge { a: 4, b: 7, distance: 14f64, size: 5 });
+
+    assert_eq!(dendrogram.cut(3), vec![0, 0, 1, 1, 2]);
+    assert_eq!(dendrogram.cut(1), vec![0; 5]);
+    assert_eq!(dendrogram.cut_at_distance(5f64), vec![0, 0, 0, 0, 1]);
+    assert_eq!(dendrogram.cut_at_distance(0.5f64), vec![0, 1, 2, 3, 4]);
+
+    let linkage = dendrogram.linkage_matrix();
+    assert_eq!((linkage.n, linkage.m), (4, 4));
+    assert_eq!(linkage.row(2), Vector::from_slice(&[5f64, 6f64, 4f64, 4f64]));
+}
+
+#[test]
+fn test_linkages() {
+    use ml::neighbors::Euclidean;
+
+    let samples: Vec<Vector<f64>> = [0f64, 1f64, 5f64, 6f64].iter().map(|&x| Vector::from_slice(&[x])).collect();
+
+    let last = |linkage| Agglomerative::new(linkage, Euclidean).fit(&samples).merges()[2].distance;
+    assert_eq!(last(Linkage::Single), 4f64);
+    assert_eq!(last(Linkage::Complete), 6f64);
+    assert_eq!(last(Linkage::Average), 5f64);
+    // sqrt(2 * n_a * n_b / (n_a + n_b)) * |c_a - c_b|, with centroids at 0.5 and 5.5
+    assert!((last(Linkage::Ward) - (2f64 * 25f64).sqrt()).abs() < 1e-12);
+}
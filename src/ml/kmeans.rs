@@ -0,0 +1,337 @@
+use num::Float;
+use rand::{thread_rng,Rng,SeedableRng,StdRng};
+
+use ml::Clusterer;
+use alg::{Vector,Matrix};
+use data::Error;
+use data::model::{Model,Scalar,PayloadWriter,PayloadReader};
+
+/// k-means clustering, minimizing the sum of squared distances to the closest centroid.
+///
+/// Centroids are seeded with k-means++, and the best of several restarts is kept.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct KMeans<T> {
+    k: usize,
+    n_init: usize,
+    max_iter: usize,
+    tol: T,
+    batch_size: Option<usize>,
+    seed: Option<usize>,
+
+    centroids: Vec<Vector<T>>,
+    inertia: T,
+}
+
+/// Returns the index of the closest centroid, and the squared distance to it.
+fn closest<T: Float>(centroids: &[Vector<T>], input: &Vector<T>) -> (usize, T) {
+    let mut best = (0, T::infinity());
+    for (i,c) in centroids.iter().enumerate() {
+        let d = (c - input).norm_sq();
+        if d < best.1 { best = (i, d); }
+    }
+    best
+}
+
+impl <T: Float> KMeans<T> {
+    /// Creates a new k-means clusterer looking for `k` clusters.
+    pub fn new(k: usize) -> Self {
+        KMeans {
+            k: k,
+            n_init: 10,
+            max_iter: 300,
+            tol: T::from(1e-4).unwrap(),
+            batch_size: None,
+            seed: None,
+
+            centroids: Vec::new(),
+            inertia: T::infinity(),
+        }
+    }
+
+    /// Sets the number of restarts. The run with the lowest inertia is kept. Defaults to 10.
+    pub fn with_restarts(mut self, n_init: usize) -> Self {
+        self.n_init = n_init.max(1);
+        self
+    }
+
+    /// Sets the maximum number of iterations of each run. Defaults to 300.
+    pub fn with_max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    /// Stops a run when the centroids moved by less than `tol` (sum of squared shifts).
+    pub fn with_tolerance(mut self, tol: T) -> Self {
+        self.tol = tol;
+        self
+    }
+
+    /// Updates the centroids from random mini-batches of `batch_size` samples
+    /// instead of the full dataset, trading accuracy for speed on large datasets.
+    pub fn with_mini_batch(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size.max(1));
+        self
+    }
+
+    /// Seeds the random number generator, making fitting reproducible.
+    pub fn with_seed(mut self, seed: usize) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns the centroids, one per row.
+    pub fn centroids(&self) -> Matrix<T> {
+        Matrix::from_rows(&self.centroids)
+    }
+
+    /// Returns the sum of squared distances of the training samples to their closest centroid.
+    pub fn inertia(&self) -> T {
+        self.inertia
+    }
+
+    /// Returns the squared distance from the input to each centroid.
+    pub fn transform(&self, input: &Vector<T>) -> Vector<T> {
+        Vector::new(self.centroids.len(), |i| (&self.centroids[i] - input).norm_sq())
+    }
+
+    // k-means++: each new centroid is drawn with a probability proportional
+    // to its squared distance to the closest existing centroid.
+    fn init_centroids<R: Rng>(&self, samples: &[Vector<T>], rng: &mut R) -> Vec<Vector<T>> {
+        let mut centroids = vec![samples[rng.gen_range(0, samples.len())].clone()];
+        let mut distances: Vec<T> = samples.iter().map(|x| (x - &centroids[0]).norm_sq()).collect();
+
+        while centroids.len() < self.k {
+            let total = distances.iter().fold(T::zero(), |a,&b| a+b);
+            let next = if total <= T::zero() {
+                // Every sample is already a centroid
+                rng.gen_range(0, samples.len())
+            } else {
+                let mut target = total * T::from(rng.gen::<f64>()).unwrap();
+                let mut chosen = samples.len() - 1;
+                for (i,&d) in distances.iter().enumerate() {
+                    if target < d {
+                        chosen = i;
+                        break;
+                    }
+                    target = target - d;
+                }
+                chosen
+            };
+
+            let centroid = samples[next].clone();
+            for (x,d) in samples.iter().zip(distances.iter_mut()) {
+                *d = d.min((x - &centroid).norm_sq());
+            }
+            centroids.push(centroid);
+        }
+
+        centroids
+    }
+
+    fn inertia_of(centroids: &[Vector<T>], samples: &[Vector<T>]) -> T {
+        samples.iter().map(|x| closest(centroids, x).1).fold(T::zero(), |a,b| a+b)
+    }
+
+    // Lloyd's algorithm: alternates assignment and mean update.
+    fn run_full(&self, mut centroids: Vec<Vector<T>>, samples: &[Vector<T>]) -> Vec<Vector<T>> {
+        let dim = samples[0].dim();
+        for _ in 0..self.max_iter {
+            let mut sums = vec![Vector::zero(dim); centroids.len()];
+            let mut counts = vec![0usize; centroids.len()];
+            for x in samples {
+                let (i,_) = closest(&centroids, x);
+                sums[i].add_in_place(x);
+                counts[i] += 1;
+            }
+
+            let mut shift = T::zero();
+            for (i,(sum,count)) in sums.into_iter().zip(counts.into_iter()).enumerate() {
+                // Empty clusters keep their previous centroid.
+                if count == 0 { continue; }
+                let mean = sum / T::from(count).unwrap();
+                shift = shift + (&mean - &centroids[i]).norm_sq();
+                centroids[i] = mean;
+            }
+
+            if shift <= self.tol { break; }
+        }
+        centroids
+    }
+
+    // Mini-batch k-means (Sculley, 2010): per-centroid learning rate decreasing with its count.
+    fn run_mini_batch<R: Rng>(&self, mut centroids: Vec<Vector<T>>, samples: &[Vector<T>], batch_size: usize, rng: &mut R) -> Vec<Vector<T>> {
+        let mut counts = vec![0usize; centroids.len()];
+        for _ in 0..self.max_iter {
+            let batch: Vec<usize> = (0..batch_size).map(|_| rng.gen_range(0, samples.len())).collect();
+            let assignments: Vec<usize> = batch.iter().map(|&b| closest(&centroids, &samples[b]).0).collect();
+
+            let mut shift = T::zero();
+            for (&b,&i) in batch.iter().zip(assignments.iter()) {
+                counts[i] += 1;
+                let rate = T::one() / T::from(counts[i]).unwrap();
+                let step = &(&samples[b] - &centroids[i]) * rate;
+                shift = shift + step.norm_sq();
+                centroids[i].add_in_place(&step);
+            }
+
+            if shift <= self.tol { break; }
+        }
+        centroids
+    }
+}
+
+impl <T: Float> Clusterer for KMeans<T> {
+    type Input = Vector<T>;
+
+    fn fit(&mut self, samples: &[Vector<T>]) {
+        self.centroids.clear();
+        self.inertia = T::infinity();
+        if samples.is_empty() || self.k == 0 { return; }
+
+        let seed = match self.seed {
+            Some(seed) => seed,
+            None => thread_rng().gen(),
+        };
+        let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+
+        for _ in 0..self.n_init {
+            let centroids = self.init_centroids(samples, &mut rng);
+            let centroids = match self.batch_size {
+                None => self.run_full(centroids, samples),
+                Some(batch_size) => self.run_mini_batch(centroids, samples, batch_size, &mut rng),
+            };
+
+            let inertia = KMeans::inertia_of(&centroids, samples);
+            if inertia < self.inertia {
+                self.inertia = inertia;
+                self.centroids = centroids;
+            }
+        }
+    }
+
+    /// Returns the index of the closest centroid.
+    ///
+    /// Panics if the model was not fitted.
+    fn predict(&self, input: &Vector<T>) -> usize {
+        if self.centroids.is_empty() {
+            panic!("KMeans was not fitted.");
+        }
+        closest(&self.centroids, input).0
+    }
+}
+
+impl <T: Scalar> Model for KMeans<T> {
+    type Scalar = T;
+
+    fn model_type() -> &'static str { "KMeans" }
+
+    fn write_payload(&self, payload: &mut PayloadWriter) {
+        payload.write_usize(self.k);
+        payload.write_usize(self.n_init);
+        payload.write_usize(self.max_iter);
+        payload.write_scalar(self.tol);
+        payload.write_option(self.batch_size);
+        payload.write_option(self.seed);
+
+        payload.write_usize(self.centroids.len());
+        for c in self.centroids.iter() {
+            payload.write_vector(c);
+        }
+        payload.write_scalar(self.inertia);
+    }
+
+    fn read_payload(payload: &mut PayloadReader) -> Result<Self, Error> {
+        let k = try!(payload.read_usize());
+        let n_init = try!(payload.read_usize());
+        let max_iter = try!(payload.read_usize());
+        let tol = try!(payload.read_scalar());
+        let batch_size = try!(payload.read_option());
+        let seed = try!(payload.read_option());
+
+        let n_centroids = try!(payload.read_usize());
+        let mut centroids: Vec<Vector<T>> = Vec::new();
+        for _ in 0..n_centroids {
+            centroids.push(try!(payload.read_vector()));
+        }
+        // Untrained models have no centroid.
+        if n_centroids != 0 && n_centroids != k {
+            return Err(Error::Format(format!("KMeans has {} centroids, expected {}", n_centroids, k)));
+        }
+        if centroids.iter().any(|c| c.dim() != centroids[0].dim()) {
+            return Err(Error::Format("KMeans centroids have different dimensions".to_string()));
+        }
+
+        Ok(KMeans {
+            k: k,
+            n_init: n_init,
+            max_iter: max_iter,
+            tol: tol,
+            batch_size: batch_size,
+            seed: seed,
+
+            centroids: centroids,
+            inertia: try!(payload.read_scalar()),
+        })
+    }
+}
+
+#[cfg(test)]
+fn blobs() -> Vec<Vector<f64>> {
+    // Three well-separated groups of 5 points
+    let centers = [(0f64, 0f64), (10f64, 10f64), (0f64, 10f64)];
+    let offsets = [(0f64, 0f64), (0.5f64, 0f64), (0f64, 0.5f64), (-0.5f64, 0f64), (0f64, -0.5f64)];
+    centers.iter().flat_map(|&(cx,cy)| offsets.iter().map(move |&(dx,dy)| Vector::from_slice(&[cx+dx, cy+dy]))).collect()
+}
+
+#[test]
+fn test_kmeans() {
+    let samples = blobs();
+    let mut kmeans = KMeans::new(3).with_seed(1);
+    kmeans.fit(&samples);
+
+    // Every group ends in its own cluster, centered on the group.
+    for group in samples.chunks(5) {
+        let label = kmeans.predict(&group[0]);
+        assert!(group.iter().all(|x| kmeans.predict(x) == label));
+        assert!((&kmeans.centroids().row(label) - &group[0]).norm_sq() < 1e-12);
+    }
+    assert!((kmeans.inertia() - 3f64).abs() < 1e-9);
+
+    let centroids = kmeans.centroids();
+    assert_eq!((centroids.n, centroids.m), (2, 3));
+}
+
+#[test]
+fn test_kmeans_mini_batch() {
+    let samples = blobs();
+    let mut kmeans = KMeans::new(3).with_mini_batch(4).with_max_iter(100).with_seed(2);
+    kmeans.fit(&samples);
+
+    for group in samples.chunks(5) {
+        let label = kmeans.predict(&group[0]);
+        assert!(group.iter().all(|x| kmeans.predict(x) == label));
+    }
+}
+
+#[test]
+fn test_kmeans_model_file() {
+    use data::model::{save_model,load_model};
+
+    let mut broken = KMeans::<f64>::new(3);
+    broken.centroids = vec![Vector::zero(2), Vector::zero(2)];
+    let mut bytes = Vec::new();
+    save_model(&mut bytes, &broken, &[]).unwrap();
+    match load_model::<KMeans<f64>, _>(&bytes[..]) {
+        Err(Error::Format(_)) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+
+    broken.centroids.push(Vector::zero(3));
+    let mut bytes = Vec::new();
+    save_model(&mut bytes, &broken, &[]).unwrap();
+    match load_model::<KMeans<f64>, _>(&bytes[..]) {
+        Err(Error::Format(_)) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+}
@@ -4,7 +4,11 @@ use num::Num;
 
 use ml::Classifier;
 use alg::{Vector,Matrix};
+use data::Error;
+use data::model::{Model,Scalar,PayloadWriter,PayloadReader};
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
 pub struct LinearRegression<T> {
     model: Vector<T>,
 }
@@ -39,3 +43,19 @@ impl <T: Clone + Num + Debug> Classifier for LinearRegression<T> {
         self.model.dot(input)
     }
 }
+
+impl <T: Scalar> Model for LinearRegression<T> {
+    type Scalar = T;
+
+    fn model_type() -> &'static str { "LinearRegression" }
+
+    fn write_payload(&self, payload: &mut PayloadWriter) {
+        payload.write_vector(&self.model);
+    }
+
+    fn read_payload(payload: &mut PayloadReader) -> Result<Self, Error> {
+        Ok(LinearRegression {
+            model: try!(payload.read_vector()),
+        })
+    }
+}
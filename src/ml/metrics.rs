@@ -0,0 +1,311 @@
+use std::cmp::Ordering;
+
+use num::Float;
+
+use alg::{Vector,Matrix};
+
+fn check_lengths(truth: usize, predicted: usize) {
+    if truth != predicted {
+        panic!("Metrics need as many predictions as labels.");
+    }
+}
+
+fn ratio(num: f64, den: f64) -> f64 {
+    if den == 0f64 { 0f64 } else { num / den }
+}
+
+/// Returns the fraction of correct predictions.
+pub fn accuracy<Y: PartialEq>(truth: &[Y], predicted: &[Y]) -> f64 {
+    check_lengths(truth.len(), predicted.len());
+    let correct = truth.iter().zip(predicted.iter()).filter(|&(t,p)| t == p).count();
+    ratio(correct as f64, truth.len() as f64)
+}
+
+/// Returns the confusion matrix of class indices below `n_classes`.
+///
+/// The element at `(p, t)`, in row `t` and column `p`, counts the samples of class `t` predicted as `p`.
+pub fn confusion_matrix(truth: &[usize], predicted: &[usize], n_classes: usize) -> Matrix<usize> {
+    check_lengths(truth.len(), predicted.len());
+    let mut matrix = Matrix::zero(n_classes, n_classes);
+    for (&t,&p) in truth.iter().zip(predicted.iter()) {
+        matrix[(p, t)] += 1;
+    }
+    matrix
+}
+
+/// How per-class scores are combined into one.
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum Average {
+    /// Mean of the scores of each class.
+    Macro,
+    /// Score of the counts summed over every class.
+    Micro,
+    /// Mean of the scores of each class, weighted by its number of samples.
+    Weighted,
+}
+
+// True positives, predicted positives and actual positives of each class.
+fn class_counts(truth: &[usize], predicted: &[usize], n_classes: usize) -> Vec<(f64, f64, f64)> {
+    let matrix = confusion_matrix(truth, predicted, n_classes);
+    (0..n_classes).map(|k| {
+        let predicted = (0..n_classes).fold(0, |a,t| a + matrix[(k, t)]);
+        let actual = (0..n_classes).fold(0, |a,p| a + matrix[(p, k)]);
+        (matrix[(k, k)] as f64, predicted as f64, actual as f64)
+    }).collect()
+}
+
+// Combines a score computed from `(true positives, predicted positives, actual positives)`.
+fn averaged<F>(truth: &[usize], predicted: &[usize], n_classes: usize, average: Average, score: F) -> f64
+    where F: Fn(f64, f64, f64) -> f64
+{
+    let counts = class_counts(truth, predicted, n_classes);
+    match average {
+        Average::Macro => ratio(counts.iter().fold(0f64, |a,&(tp,p,t)| a + score(tp, p, t)), n_classes as f64),
+        Average::Micro => {
+            let (tp, p, t) = counts.iter().fold((0f64, 0f64, 0f64), |a,&c| (a.0 + c.0, a.1 + c.1, a.2 + c.2));
+            score(tp, p, t)
+        },
+        Average::Weighted => ratio(counts.iter().fold(0f64, |a,&(tp,p,t)| a + t * score(tp, p, t)), truth.len() as f64),
+    }
+}
+
+/// Returns the precision: the fraction of the predictions of a class that are correct.
+///
+/// Classes without predictions score `0`.
+pub fn precision(truth: &[usize], predicted: &[usize], n_classes: usize, average: Average) -> f64 {
+    averaged(truth, predicted, n_classes, average, |tp,p,_| ratio(tp, p))
+}
+
+/// Returns the recall: the fraction of the samples of a class that are predicted as such.
+///
+/// Classes without samples score `0`.
+pub fn recall(truth: &[usize], predicted: &[usize], n_classes: usize, average: Average) -> f64 {
+    averaged(truth, predicted, n_classes, average, |tp,_,t| ratio(tp, t))
+}
+
+/// Returns the F1 score: the harmonic mean of the precision and the recall.
+pub fn f1_score(truth: &[usize], predicted: &[usize], n_classes: usize, average: Average) -> f64 {
+    averaged(truth, predicted, n_classes, average, |tp,p,t| ratio(2f64 * tp, p + t))
+}
+
+/// Returns the Matthews correlation coefficient, between `-1` and `1`, for any number of classes.
+///
+/// Returns `0` when all the labels or all the predictions are the same.
+pub fn matthews_corrcoef(truth: &[usize], predicted: &[usize], n_classes: usize) -> f64 {
+    let counts = class_counts(truth, predicted, n_classes);
+    let n = truth.len() as f64;
+    let correct = counts.iter().fold(0f64, |a,c| a + c.0);
+    let pt = counts.iter().fold(0f64, |a,c| a + c.1 * c.2);
+    let pp = counts.iter().fold(0f64, |a,c| a + c.1 * c.1);
+    let tt = counts.iter().fold(0f64, |a,c| a + c.2 * c.2);
+    ratio(correct * n - pt, ((n * n - pp) * (n * n - tt)).sqrt())
+}
+
+/// Returns the mean negative log-likelihood of the labels, given the predicted probability of each class.
+///
+/// Probabilities are clipped to `[1e-15, 1 - 1e-15]`.
+pub fn log_loss<T: Float>(truth: &[usize], probabilities: &[Vector<T>]) -> T {
+    check_lengths(truth.len(), probabilities.len());
+    let eps = T::from(1e-15).unwrap();
+    let total = truth.iter().zip(probabilities.iter())
+        .fold(T::zero(), |a,(&t,p)| a - p[t].max(eps).min(T::one() - eps).ln());
+    total / T::from(truth.len()).unwrap()
+}
+
+// Counts of true and false positives at each distinct score, from the highest.
+fn positives_by_threshold<T: Float>(truth: &[bool], scores: &[T]) -> Vec<(f64, f64, T)> {
+    check_lengths(truth.len(), scores.len());
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    // NaN scores rank last, keeping the order total
+    order.sort_by(|&a,&b| match (scores[a].is_nan(), scores[b].is_nan()) {
+        (false, false) => scores[b].partial_cmp(&scores[a]).unwrap_or(Ordering::Equal),
+        (x, y) => x.cmp(&y),
+    });
+
+    let mut points = Vec::new();
+    let (mut tp, mut fp) = (0f64, 0f64);
+    for (k,&i) in order.iter().enumerate() {
+        if truth[i] { tp += 1f64; } else { fp += 1f64; }
+        // Samples with the same score share a threshold.
+        if k + 1 == order.len() || scores[order[k + 1]] != scores[i] {
+            points.push((tp, fp, scores[i]));
+        }
+    }
+    points
+}
+
+/// Returns the ROC curve of binary labels given scores, higher for positives: the false positive rates,
+/// the true positive rates, and the score thresholds they are reached at, decreasing.
+///
+/// The curve starts at `(0, 0)` with an infinite threshold. NaN scores are ranked lowest.
+pub fn roc_curve<T: Float>(truth: &[bool], scores: &[T]) -> (Vec<f64>, Vec<f64>, Vec<T>) {
+    let points = positives_by_threshold(truth, scores);
+    let positives = truth.iter().filter(|&&t| t).count() as f64;
+    let negatives = truth.len() as f64 - positives;
+
+    let mut fpr = vec![0f64];
+    let mut tpr = vec![0f64];
+    let mut thresholds = vec![T::infinity()];
+    for (tp, fp, threshold) in points {
+        fpr.push(ratio(fp, negatives));
+        tpr.push(ratio(tp, positives));
+        thresholds.push(threshold);
+    }
+    (fpr, tpr, thresholds)
+}
+
+/// Returns the area under the ROC curve.
+pub fn roc_auc<T: Float>(truth: &[bool], scores: &[T]) -> f64 {
+    let (fpr, tpr, _) = roc_curve(truth, scores);
+    (1..fpr.len()).fold(0f64, |a,i| a + (fpr[i] - fpr[i-1]) * (tpr[i] + tpr[i-1]) / 2f64)
+}
+
+/// Returns the precision-recall curve of binary labels given scores, higher for positives: the precisions,
+/// the recalls, and the score thresholds they are reached at, decreasing.
+pub fn precision_recall_curve<T: Float>(truth: &[bool], scores: &[T]) -> (Vec<f64>, Vec<f64>, Vec<T>) {
+    let points = positives_by_threshold(truth, scores);
+    let positives = truth.iter().filter(|&&t| t).count() as f64;
+
+    let precisions = points.iter().map(|&(tp,fp,_)| ratio(tp, tp + fp)).collect();
+    let recalls = points.iter().map(|&(tp,_,_)| ratio(tp, positives)).collect();
+    let thresholds = points.iter().map(|&(_,_,t)| t).collect();
+    (precisions, recalls, thresholds)
+}
+
+/// Returns the average precision: the mean of the precisions at each threshold, weighted by the increase in recall.
+pub fn average_precision<T: Float>(truth: &[bool], scores: &[T]) -> f64 {
+    let (precisions, recalls, _) = precision_recall_curve(truth, scores);
+    (0..recalls.len()).fold(0f64, |a,i| {
+        let previous = if i == 0 { 0f64 } else { recalls[i-1] };
+        a + (recalls[i] - previous) * precisions[i]
+    })
+}
+
+fn mean<T: Float, F: Fn(T, T) -> T>(truth: &[T], predicted: &[T], f: F) -> T {
+    check_lengths(truth.len(), predicted.len());
+    let total = truth.iter().zip(predicted.iter()).fold(T::zero(), |a,(&t,&p)| a + f(t, p));
+    total / T::from(truth.len()).unwrap()
+}
+
+fn variance<T: Float>(values: &[T]) -> T {
+    let n = T::from(values.len()).unwrap();
+    let m = values.iter().fold(T::zero(), |a,&b| a+b) / n;
+    values.iter().fold(T::zero(), |a,&v| a + (v - m) * (v - m)) / n
+}
+
+// `1 - unexplained / total`. Constant true values give `1` when nothing is unexplained, and `0` otherwise.
+fn explained_fraction<T: Float>(unexplained: T, total: T) -> T {
+    if total == T::zero() {
+        if unexplained == T::zero() { T::one() } else { T::zero() }
+    } else {
+        T::one() - unexplained / total
+    }
+}
+
+/// Returns the mean squared error.
+pub fn mean_squared_error<T: Float>(truth: &[T], predicted: &[T]) -> T {
+    mean(truth, predicted, |t,p| (t - p) * (t - p))
+}
+
+/// Returns the root of the mean squared error.
+pub fn root_mean_squared_error<T: Float>(truth: &[T], predicted: &[T]) -> T {
+    mean_squared_error(truth, predicted).sqrt()
+}
+
+/// Returns the mean absolute error.
+pub fn mean_absolute_error<T: Float>(truth: &[T], predicted: &[T]) -> T {
+    mean(truth, predicted, |t,p| (t - p).abs())
+}
+
+/// Returns the mean absolute error relative to the true values, as a fraction.
+///
+/// Zero true values give an infinite error.
+pub fn mean_absolute_percentage_error<T: Float>(truth: &[T], predicted: &[T]) -> T {
+    mean(truth, predicted, |t,p| ((t - p) / t).abs())
+}
+
+/// Returns the coefficient of determination: `1` for perfect predictions, `0` for predicting the mean.
+///
+/// Constant true values give `1` for perfect predictions, and `0` otherwise.
+pub fn r2_score<T: Float>(truth: &[T], predicted: &[T]) -> T {
+    explained_fraction(mean_squared_error(truth, predicted), variance(truth))
+}
+
+/// Returns the fraction of the variance of the true values explained by the predictions.
+///
+/// Unlike `r2_score`, a constant bias of the predictions isn't penalized.
+/// Constant true values give `1` for predictions off by a constant, and `0` otherwise.
+pub fn explained_variance<T: Float>(truth: &[T], predicted: &[T]) -> T {
+    check_lengths(truth.len(), predicted.len());
+    let residuals: Vec<T> = truth.iter().zip(predicted.iter()).map(|(&t,&p)| t - p).collect();
+    explained_fraction(variance(&residuals), variance(truth))
+}
+
+#[test]
+fn test_classification_metrics() {
+    let truth = [0, 0, 1, 1, 2, 2];
+    let predicted = [0, 1, 1, 1, 2, 0];
+
+    assert_eq!(accuracy(&truth, &predicted), 4f64 / 6f64);
+    let matrix = confusion_matrix(&truth, &predicted, 3);
+    assert_eq!(matrix.row(0).data(), &[1, 1, 0]);
+    assert_eq!(matrix.row(2).data(), &[1, 0, 1]);
+
+    // Per class precisions are 1/2, 2/3 and 1, recalls 1/2, 1 and 1/2.
+    assert!((precision(&truth, &predicted, 3, Average::Macro) - 13f64 / 18f64).abs() < 1e-12);
+    assert!((recall(&truth, &predicted, 3, Average::Macro) - 2f64 / 3f64).abs() < 1e-12);
+    assert!((f1_score(&truth, &predicted, 3, Average::Micro) - 2f64 / 3f64).abs() < 1e-12);
+    assert!((f1_score(&truth, &predicted, 3, Average::Weighted) - (0.5f64 + 0.8f64 + 2f64 / 3f64) / 3f64).abs() < 1e-12);
+
+    assert_eq!(matthews_corrcoef(&truth, &truth, 3), 1f64);
+    assert_eq!(matthews_corrcoef(&[0, 1, 0, 1], &[1, 0, 1, 0], 2), -1f64);
+    assert_eq!(matthews_corrcoef(&[0, 1], &[0, 0], 2), 0f64);
+
+    let probabilities = vec![Vector::from_slice(&[0.5f64, 0.5f64]), Vector::from_slice(&[0f64, 1f64])];
+    assert!((log_loss(&[0, 1], &probabilities) - 2f64.ln() / 2f64).abs() < 1e-12);
+}
+
+#[test]
+fn test_ranking_metrics() {
+    let truth = [false, false, true, true];
+    let scores = [0.1f64, 0.4f64, 0.35f64, 0.8f64];
+
+    let (fpr, tpr, thresholds) = roc_curve(&truth, &scores);
+    assert_eq!(fpr, vec![0f64, 0f64, 0.5f64, 0.5f64, 1f64]);
+    assert_eq!(tpr, vec![0f64, 0.5f64, 0.5f64, 1f64, 1f64]);
+    assert_eq!(thresholds[1..].to_vec(), vec![0.8f64, 0.4f64, 0.35f64, 0.1f64]);
+    assert_eq!(roc_auc(&truth, &scores), 0.75f64);
+
+    let (precisions, recalls, _) = precision_recall_curve(&truth, &scores);
+    assert_eq!(precisions, vec![1f64, 0.5f64, 2f64 / 3f64, 0.5f64]);
+    assert_eq!(recalls, vec![0.5f64, 0.5f64, 1f64, 1f64]);
+    assert!((average_precision(&truth, &scores) - (0.5f64 + 1f64 / 3f64)).abs() < 1e-12);
+
+    // Tied scores make a single point.
+    assert_eq!(roc_curve(&truth, &[0.5f64; 4]).0, vec![0f64, 1f64]);
+    // NaN scores are ranked lowest.
+    assert_eq!(roc_auc(&truth, &[0.1f64, ::std::f64::NAN, 0.35f64, 0.8f64]), 1f64);
+}
+
+#[test]
+fn test_regression_metrics() {
+    let truth = [1f64, 2f64, 3f64, 4f64];
+    let predicted = [2f64, 2f64, 3f64, 2f64];
+
+    assert_eq!(mean_squared_error(&truth, &predicted), 1.25f64);
+    assert_eq!(root_mean_squared_error(&truth, &predicted), 1.25f64.sqrt());
+    assert_eq!(mean_absolute_error(&truth, &predicted), 0.75f64);
+    assert_eq!(mean_absolute_percentage_error(&truth, &predicted), 0.375f64);
+    assert_eq!(r2_score(&truth, &predicted), 0f64);
+    assert_eq!(r2_score(&truth, &truth), 1f64);
+
+    // A constant bias is fully explained.
+    let shifted: Vec<f64> = truth.iter().map(|t| t + 1f64).collect();
+    assert_eq!(explained_variance(&truth, &shifted), 1f64);
+    assert!(r2_score(&truth, &shifted) < 1f64);
+
+    let constant = [2f64, 2f64, 2f64];
+    assert_eq!(r2_score(&constant, &constant), 1f64);
+    assert_eq!(r2_score(&constant, &[2f64, 3f64, 2f64]), 0f64);
+    assert_eq!(explained_variance(&constant, &[3f64, 3f64, 3f64]), 1f64);
+}
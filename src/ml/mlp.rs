@@ -2,7 +2,11 @@ use num::Float;
 
 use ml::Classifier;
 use alg::{Vector,Matrix};
+use data::Error;
+use data::model::{Model,Scalar,PayloadWriter,PayloadReader};
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
 pub struct MultiLayerPerceptron<T> {
     layers: Vec<Matrix<T>>,
 }
@@ -38,7 +42,58 @@ impl <T: Clone + Float> Classifier for MultiLayerPerceptron<T> {
     }
 }
 
+impl <T: Scalar> Model for MultiLayerPerceptron<T> {
+    type Scalar = T;
+
+    fn model_type() -> &'static str { "MultiLayerPerceptron" }
+
+    fn write_payload(&self, payload: &mut PayloadWriter) {
+        payload.write_usize(self.layers.len());
+        for layer in self.layers.iter() {
+            payload.write_matrix(layer);
+        }
+    }
+
+    fn read_payload(payload: &mut PayloadReader) -> Result<Self, Error> {
+        let n_layers = try!(payload.read_usize());
+        let mut layers: Vec<Matrix<T>> = Vec::new();
+        for _ in 0..n_layers {
+            let layer: Matrix<T> = try!(payload.read_matrix());
+            // Each layer takes the outputs of the previous one.
+            if let Some(previous) = layers.last() {
+                if layer.n != previous.m {
+                    return Err(Error::Format(format!("MultiLayerPerceptron layer takes {} inputs, but the previous one has {} outputs", layer.n, previous.m)));
+                }
+            }
+            layers.push(layer);
+        }
+        Ok(MultiLayerPerceptron {
+            layers: layers,
+        })
+    }
+}
+
 #[test]
 fn test_mlp() {
     let mlp = MultiLayerPerceptron::<f64>::new(&[5,1]);
 }
+
+#[test]
+fn test_mlp_model_file() {
+    use data::model::{save_model,load_model};
+
+    let mlp = MultiLayerPerceptron::<f64>::new(&[3,2,1]);
+    let mut bytes = Vec::new();
+    save_model(&mut bytes, &mlp, &[]).unwrap();
+    let (loaded, _) = load_model::<MultiLayerPerceptron<f64>, _>(&bytes[..]).unwrap();
+    assert_eq!(loaded.classify(&Vector::zero(3)), mlp.classify(&Vector::zero(3)));
+
+    // The second layer doesn't take the outputs of the first.
+    let broken = MultiLayerPerceptron::<f64> { layers: vec![Matrix::zero(3, 2), Matrix::zero(4, 1)] };
+    let mut bytes = Vec::new();
+    save_model(&mut bytes, &broken, &[]).unwrap();
+    match load_model::<MultiLayerPerceptron<f64>, _>(&bytes[..]) {
+        Err(Error::Format(_)) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+}
@@ -1,14 +1,39 @@
 //! Machine-learning module
 //!
-//! The main traits are the `Classifier` and the `Optimizer`. Various implementations are provided.
+//! The main traits are the `Classifier`, the `Clusterer`, the `Transformer` and the `Optimizer`. Various implementations are provided.
 mod classifier;
+mod clusterer;
 mod optimizer;
+mod transformer;
 
 pub mod binary;
 pub mod linear;
 pub mod bayes;
 pub mod cmaes;
+pub mod nelder_mead;
 pub mod mlp;
+pub mod tree;
+pub mod forest;
+pub mod boosting;
+pub mod svm;
+pub mod kmeans;
+pub mod gmm;
+pub mod neighbors;
+pub mod dbscan;
+pub mod hierarchical;
+pub mod pca;
+pub mod discriminant;
+pub mod preprocessing;
+pub mod encoding;
+pub mod pipeline;
+pub mod cross_validation;
+pub mod metrics;
+pub mod search;
+pub mod gaussian_process;
+#[cfg(feature = "serde")]
+pub mod saved;
 
 pub use self::classifier::Classifier;
+pub use self::clusterer::Clusterer;
 pub use self::optimizer::Optimizer;
+pub use self::transformer::Transformer;
@@ -0,0 +1,279 @@
+use std::cmp::Ordering;
+
+use num::Float;
+
+use alg::Vector;
+
+/// A distance between two vectors.
+pub trait Distance<T> {
+    fn distance(&self, a: &Vector<T>, b: &Vector<T>) -> T;
+
+    /// Returns `true` if `|a[i] - b[i]| <= distance(a,b)` on every axis.
+    ///
+    /// This lets `NeighborSearch` prune its search with a kd-tree.
+    fn bounded_by_axes(&self) -> bool {
+        false
+    }
+}
+
+/// Straight-line distance: square root of the sum of squared differences.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Euclidean;
+
+impl <T: Float> Distance<T> for Euclidean {
+    fn distance(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        (a - b).norm_sq().sqrt()
+    }
+
+    fn bounded_by_axes(&self) -> bool { true }
+}
+
+/// Sum of absolute differences.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Manhattan;
+
+impl <T: Float> Distance<T> for Manhattan {
+    fn distance(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        a.data().iter().zip(b.data().iter()).map(|(&x,&y)| (x - y).abs()).fold(T::zero(), |a,b| a+b)
+    }
+
+    fn bounded_by_axes(&self) -> bool { true }
+}
+
+/// Largest absolute difference on any axis.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Chebyshev;
+
+impl <T: Float> Distance<T> for Chebyshev {
+    fn distance(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        a.data().iter().zip(b.data().iter()).map(|(&x,&y)| (x - y).abs()).fold(T::zero(), |a,b| a.max(b))
+    }
+
+    fn bounded_by_axes(&self) -> bool { true }
+}
+
+/// Euclidean distance ignoring missing (NaN) coordinates.
+///
+/// The sum of squares over coordinates present in both vectors is scaled up by the
+/// fraction of such coordinates. Vectors sharing no coordinate are infinitely far apart.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct NanEuclidean;
+
+impl <T: Float> Distance<T> for NanEuclidean {
+    fn distance(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        let mut sum = T::zero();
+        let mut present = 0;
+        for (&x,&y) in a.data().iter().zip(b.data().iter()) {
+            if x.is_nan() || y.is_nan() { continue; }
+            sum = sum + (x - y) * (x - y);
+            present += 1;
+        }
+
+        if present == 0 { return T::infinity(); }
+        (sum * T::from(a.dim()).unwrap() / T::from(present).unwrap()).sqrt()
+    }
+}
+
+/// Great-circle distance between `[latitude, longitude]` points given in degrees.
+///
+/// The distance is in the unit of the sphere radius.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Haversine<T> {
+    radius: T,
+}
+
+impl <T: Float> Haversine<T> {
+    /// Creates a new haversine distance on a sphere of the given radius.
+    pub fn new(radius: T) -> Self {
+        Haversine {
+            radius: radius,
+        }
+    }
+
+    /// Haversine distance on the Earth, in kilometers.
+    pub fn earth_km() -> Self {
+        Haversine::new(T::from(6371.0088f64).unwrap())
+    }
+}
+
+impl <T: Float> Distance<T> for Haversine<T> {
+    fn distance(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        let two = T::one() + T::one();
+        let (lat_a, lat_b) = (a[0].to_radians(), b[0].to_radians());
+        let d_lat = (b[0] - a[0]).to_radians();
+        let d_lon = (b[1] - a[1]).to_radians();
+
+        let h = (d_lat / two).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / two).sin().powi(2);
+        two * self.radius * h.sqrt().min(T::one()).asin()
+    }
+}
+
+/// Neighborhood queries over a fixed set of samples.
+///
+/// Uses a kd-tree when the distance allows it, and a linear scan otherwise.
+pub struct NeighborSearch<'a, T: 'a, D: 'a> {
+    samples: &'a [Vector<T>],
+    distance: &'a D,
+    // Sample indices in kd-tree order: each range has its median in the middle,
+    // smaller values on the left and larger on the right.
+    tree: Option<Vec<usize>>,
+}
+
+impl <'a, T: Float, D: Distance<T>> NeighborSearch<'a,T,D> {
+    /// Indexes the given samples.
+    ///
+    /// 0-dimensional samples, all at the same distance, are not indexed.
+    pub fn new(samples: &'a [Vector<T>], distance: &'a D) -> Self {
+        let tree = if distance.bounded_by_axes() && !samples.is_empty() && samples[0].dim() > 0 {
+            let mut indices: Vec<usize> = (0..samples.len()).collect();
+            build(samples, &mut indices, 0);
+            Some(indices)
+        } else {
+            None
+        };
+
+        NeighborSearch {
+            samples: samples,
+            distance: distance,
+            tree: tree,
+        }
+    }
+
+    /// Returns `true` if queries use a kd-tree.
+    pub fn is_indexed(&self) -> bool {
+        self.tree.is_some()
+    }
+
+    /// Returns the indices of the samples within `radius` of the point, itself included.
+    pub fn within(&self, point: &Vector<T>, radius: T) -> Vec<usize> {
+        match self.tree {
+            None => (0..self.samples.len()).filter(|&i| self.distance.distance(&self.samples[i], point) <= radius).collect(),
+            Some(ref tree) => {
+                let mut result = Vec::new();
+                self.search_within(tree, 0, point, radius, &mut result);
+                result
+            }
+        }
+    }
+
+    /// Returns the `k` samples closest to the point, with their distance, closest first.
+    pub fn nearest(&self, point: &Vector<T>, k: usize) -> Vec<(usize, T)> {
+        let mut best = Vec::with_capacity(k + 1);
+        if k == 0 { return best; }
+
+        match self.tree {
+            None => for i in 0..self.samples.len() {
+                insert(&mut best, k, i, self.distance.distance(&self.samples[i], point));
+            },
+            Some(ref tree) => self.search_nearest(tree, 0, point, k, &mut best),
+        }
+        best
+    }
+
+    fn search_within(&self, range: &[usize], depth: usize, point: &Vector<T>, radius: T, result: &mut Vec<usize>) {
+        if range.is_empty() { return; }
+
+        let mid = range.len() / 2;
+        let i = range[mid];
+        let sample = &self.samples[i];
+        if self.distance.distance(sample, point) <= radius {
+            result.push(i);
+        }
+
+        let axis = depth % sample.dim();
+        if point[axis] - radius <= sample[axis] {
+            self.search_within(&range[..mid], depth + 1, point, radius, result);
+        }
+        if point[axis] + radius >= sample[axis] {
+            self.search_within(&range[mid+1..], depth + 1, point, radius, result);
+        }
+    }
+
+    fn search_nearest(&self, range: &[usize], depth: usize, point: &Vector<T>, k: usize, best: &mut Vec<(usize, T)>) {
+        if range.is_empty() { return; }
+
+        let mid = range.len() / 2;
+        let i = range[mid];
+        let sample = &self.samples[i];
+        insert(best, k, i, self.distance.distance(sample, point));
+
+        // Search the side containing the point first, then the other one if it may be closer.
+        let axis = depth % sample.dim();
+        let diff = point[axis] - sample[axis];
+        let (near, far) = if diff <= T::zero() {
+            (&range[..mid], &range[mid+1..])
+        } else {
+            (&range[mid+1..], &range[..mid])
+        };
+
+        self.search_nearest(near, depth + 1, point, k, best);
+        if best.len() < k || diff.abs() <= best[best.len() - 1].1 {
+            self.search_nearest(far, depth + 1, point, k, best);
+        }
+    }
+}
+
+// Inserts a candidate in the sorted list of the `k` best neighbors.
+fn insert<T: Float>(best: &mut Vec<(usize, T)>, k: usize, i: usize, d: T) {
+    if best.len() == k && d >= best[k - 1].1 { return; }
+
+    let pos = best.iter().position(|&(_,b)| d < b).unwrap_or(best.len());
+    best.insert(pos, (i, d));
+    best.truncate(k);
+}
+
+fn build<T: Float>(samples: &[Vector<T>], range: &mut [usize], depth: usize) {
+    if range.len() <= 1 { return; }
+
+    let axis = depth % samples[range[0]].dim();
+    range.sort_by(|&a, &b| samples[a][axis].partial_cmp(&samples[b][axis]).unwrap_or(Ordering::Equal));
+
+    let mid = range.len() / 2;
+    let (left, right) = range.split_at_mut(mid);
+    build(samples, left, depth + 1);
+    build(samples, &mut right[1..], depth + 1);
+}
+
+#[test]
+fn test_neighbors() {
+    let samples: Vec<Vector<f64>> = (0..50).map(|i| Vector::from_slice(&[(i % 7) as f64, (i / 7) as f64])).collect();
+    let point = Vector::from_slice(&[3.2f64, 2.9f64]);
+
+    let indexed = NeighborSearch::new(&samples, &Euclidean);
+    assert!(indexed.is_indexed());
+
+    // Compare with a linear scan
+    let mut expected: Vec<usize> = (0..samples.len()).filter(|&i| Euclidean.distance(&samples[i], &point) <= 1.5f64).collect();
+    let mut found = indexed.within(&point, 1.5f64);
+    expected.sort();
+    found.sort();
+    assert_eq!(found, expected);
+
+    let nearest = indexed.nearest(&point, 3);
+    assert_eq!(nearest.len(), 3);
+    assert_eq!(nearest[0].0, 3 + 3 * 7);
+    assert!(nearest[0].1 <= nearest[1].1 && nearest[1].1 <= nearest[2].1);
+
+    let manhattan = NeighborSearch::new(&samples, &Manhattan);
+    assert_eq!(manhattan.nearest(&point, 1)[0].0, 3 + 3 * 7);
+
+    // Without features, every sample is at distance 0.
+    let empty = vec![Vector::<f64>::dummy(); 3];
+    let search = NeighborSearch::new(&empty, &Euclidean);
+    assert_eq!(search.nearest(&Vector::dummy(), 2), vec![(0, 0f64), (1, 0f64)]);
+    assert_eq!(search.within(&Vector::dummy(), 0f64), vec![0, 1, 2]);
+}
+
+#[test]
+fn test_haversine() {
+    // Paris to London is about 344 km.
+    let paris = Vector::from_slice(&[48.8566f64, 2.3522f64]);
+    let london = Vector::from_slice(&[51.5074f64, -0.1278f64]);
+    let d = Haversine::earth_km().distance(&paris, &london);
+    assert!((d - 344f64).abs() < 2f64);
+}
@@ -0,0 +1,126 @@
+use std::cmp::Ordering;
+
+use num::Float;
+
+use alg::Vector;
+use ml::Optimizer;
+
+/// Nelder-Mead simplex method: a derivative-free local optimizer, for smooth functions of a few variables.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct NelderMead<T> {
+    start: Vector<T>,
+    step: T,
+    max_iter: usize,
+    tol: T,
+}
+
+impl <T: Float> NelderMead<T> {
+    /// Creates a new optimizer starting from the given point.
+    ///
+    /// Its dimension is the dimension of the function input vector.
+    pub fn new(start: Vector<T>) -> Self {
+        NelderMead {
+            start: start,
+            step: T::one(),
+            max_iter: 200,
+            tol: T::from(1e-8).unwrap(),
+        }
+    }
+
+    /// Sets the size of the initial simplex along each axis.
+    pub fn with_step(mut self, step: T) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Sets the maximum number of iterations.
+    pub fn with_max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    /// Stops when the values on the simplex differ by at most `tol`.
+    pub fn with_tol(mut self, tol: T) -> Self {
+        self.tol = tol;
+        self
+    }
+}
+
+// Sorts the simplex from the best point, NaN values last.
+fn sort<T: Float>(simplex: &mut Vec<(Vector<T>, T)>) {
+    simplex.sort_by(|a,b| match (a.1.is_nan(), b.1.is_nan()) {
+        (false, false) => a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal),
+        (x, y) => x.cmp(&y),
+    });
+}
+
+// Moves from `from` towards `to`, by `t` times their difference.
+fn towards<T: Float>(from: &Vector<T>, to: &Vector<T>, t: T) -> Vector<T> {
+    from + &(&(to - from) * t)
+}
+
+impl <T: Float> Optimizer for NelderMead<T> {
+    type Input = Vector<T>;
+    type Score = T;
+
+    fn optimize<F>(&self, f: F) -> Vector<T>
+        where F: Fn(Vector<T>) -> T
+    {
+        let n = self.start.dim();
+        let half = T::from(0.5).unwrap();
+        if n == 0 {
+            return self.start.clone();
+        }
+
+        let mut simplex: Vec<(Vector<T>, T)> = (0..n+1).map(|i| {
+            let point = Vector::new(n, |d| if d + 1 == i { self.start[d] + self.step } else { self.start[d] });
+            let value = f(point.clone());
+            (point, value)
+        }).collect();
+
+        for _ in 0..self.max_iter {
+            sort(&mut simplex);
+            if (simplex[n].1 - simplex[0].1).abs() <= self.tol {
+                break;
+            }
+
+            let centroid = simplex[..n].iter().fold(Vector::zero(n), |a,p| &a + &p.0) / T::from(n).unwrap();
+
+            let reflected = towards(&simplex[n].0, &centroid, T::from(2).unwrap());
+            let fr = f(reflected.clone());
+            if fr < simplex[0].1 {
+                let expanded = towards(&simplex[n].0, &centroid, T::from(3).unwrap());
+                let fe = f(expanded.clone());
+                simplex[n] = if fe < fr { (expanded, fe) } else { (reflected, fr) };
+            } else if fr < simplex[n-1].1 {
+                simplex[n] = (reflected, fr);
+            } else {
+                let contracted = towards(&centroid, &simplex[n].0, half);
+                let fc = f(contracted.clone());
+                if fc < simplex[n].1 {
+                    simplex[n] = (contracted, fc);
+                } else {
+                    // Shrink towards the best point
+                    for i in 1..n+1 {
+                        let point = towards(&simplex[0].0, &simplex[i].0, half);
+                        let value = f(point.clone());
+                        simplex[i] = (point, value);
+                    }
+                }
+            }
+        }
+
+        sort(&mut simplex);
+        simplex.swap_remove(0).0
+    }
+}
+
+#[test]
+fn test_nelder_mead() {
+    // Rosenbrock function, minimal at (1, 1)
+    let optimizer = NelderMead::new(Vector::from_slice(&[-1f64, 2f64])).with_max_iter(1000).with_tol(1e-14);
+    let best = optimizer.optimize(|x| (1f64 - x[0]).powi(2) + 100f64 * (x[1] - x[0] * x[0]).powi(2));
+    assert!((best[0] - 1f64).abs() < 1e-3);
+    assert!((best[1] - 1f64).abs() < 1e-3);
+}
@@ -0,0 +1,237 @@
+use num::Float;
+
+use ml::Transformer;
+use alg::{Vector,Matrix};
+
+/// Number of components kept by a `Pca`.
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub enum Components<T> {
+    /// Keeps this many components.
+    Count(usize),
+    /// Keeps the fewest components explaining at least this fraction of the variance.
+    Variance(T),
+}
+
+/// Method used to find the principal components.
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub enum Solver {
+    /// Eigen-decomposition of the covariance matrix. Fast with few features.
+    Covariance,
+    /// Singular value decomposition of the centered data. More accurate.
+    Svd,
+}
+
+/// Principal component analysis: projects samples onto the directions of highest variance.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Pca<T> {
+    n_components: Components<T>,
+    whiten: bool,
+    solver: Solver,
+
+    mean: Vector<T>,
+    // One component per row
+    components: Matrix<T>,
+    explained_variance: Vector<T>,
+    explained_variance_ratio: Vector<T>,
+}
+
+impl <T: Float> Pca<T> {
+    /// Creates a new PCA keeping the given number of components.
+    pub fn new(n_components: Components<T>) -> Self {
+        Pca {
+            n_components: n_components,
+            whiten: false,
+            solver: Solver::Svd,
+
+            mean: Vector::dummy(),
+            components: Matrix::dummy(),
+            explained_variance: Vector::dummy(),
+            explained_variance_ratio: Vector::dummy(),
+        }
+    }
+
+    /// Scales the projected values to unit variance.
+    ///
+    /// Components with no variance, up to rounding, are left unscaled.
+    pub fn with_whitening(mut self) -> Self {
+        self.whiten = true;
+        self
+    }
+
+    // Standard deviation of the `i`-th component, or 1 if it has no variance.
+    fn whitening_scale(&self, i: usize) -> T {
+        let variance = self.explained_variance[i];
+        if variance > T::epsilon() * self.explained_variance[0] {
+            variance.sqrt()
+        } else {
+            T::one()
+        }
+    }
+
+    /// Sets the decomposition method. Defaults to `Solver::Svd`.
+    pub fn with_solver(mut self, solver: Solver) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    /// Returns the mean of the training samples.
+    pub fn mean(&self) -> &Vector<T> {
+        &self.mean
+    }
+
+    /// Returns the principal axes, one per row, by decreasing variance.
+    pub fn components(&self) -> &Matrix<T> {
+        &self.components
+    }
+
+    /// Returns the variance along each component.
+    pub fn explained_variance(&self) -> &Vector<T> {
+        &self.explained_variance
+    }
+
+    /// Returns the fraction of the total variance along each component.
+    pub fn explained_variance_ratio(&self) -> &Vector<T> {
+        &self.explained_variance_ratio
+    }
+}
+
+impl <T: Float> Transformer for Pca<T> {
+    type Input = Vector<T>;
+    type Output = Vector<T>;
+
+    fn fit(&mut self, samples: &[Vector<T>]) {
+        if samples.is_empty() { return; }
+
+        let n = samples.len();
+        let d = samples[0].dim();
+        let mean = samples.iter().fold(Vector::zero(d), |a,x| &a + x) / T::from(n).unwrap();
+        let centered: Vec<Vector<T>> = samples.iter().map(|x| x - &mean).collect();
+        let dof = T::from(n.max(2) - 1).unwrap();
+
+        // Each column of `axes` is a component.
+        let (variances, axes) = match self.solver {
+            Solver::Covariance => {
+                let x = Matrix::from_rows(&centered);
+                let covariance = &(&x.transpose() * &x) / dof;
+                covariance.symmetric_eigen()
+            },
+            Solver::Svd => {
+                let (_, s, v) = Matrix::from_rows(&centered).svd();
+                (s.chain_apply(|s| s * s / dof), v)
+            },
+        };
+
+        let total = variances.data().iter().fold(T::zero(), |a,&b| a+b);
+        let ratios = if total > T::zero() { &variances / total } else { Vector::zero(variances.dim()) };
+
+        let k = match self.n_components {
+            Components::Count(k) => k.min(variances.dim()),
+            Components::Variance(fraction) => {
+                let mut sum = T::zero();
+                let mut k = 0;
+                while k < ratios.dim() && sum < fraction {
+                    sum = sum + ratios[k];
+                    k += 1;
+                }
+                k.max(1)
+            },
+        };
+
+        // Make signs deterministic: the largest coordinate of each component is positive.
+        let mut components = Matrix::new(d, k, |x,y| axes[(y,x)]);
+        for y in 0..k {
+            let row = components.row(y);
+            let largest = row.data().iter().fold(T::zero(), |a,&b| if b.abs() > a.abs() { b } else { a });
+            if largest < T::zero() {
+                components.set_row(y, row * -T::one());
+            }
+        }
+
+        self.mean = mean;
+        self.components = components;
+        self.explained_variance = Vector::new(k, |i| variances[i]);
+        self.explained_variance_ratio = Vector::new(k, |i| ratios[i]);
+    }
+
+    /// Projects a sample onto the principal components.
+    fn transform(&self, input: &Vector<T>) -> Vector<T> {
+        let projected = &self.components * (input - &self.mean);
+        if self.whiten {
+            Vector::new(projected.dim(), |i| projected[i] / self.whitening_scale(i))
+        } else {
+            projected
+        }
+    }
+
+    /// Maps projected values back to the original space.
+    ///
+    /// This is exact only if no component was dropped.
+    fn inverse_transform(&self, input: &Vector<T>) -> Vector<T> {
+        let input = if self.whiten {
+            Vector::new(input.dim(), |i| input[i] * self.whitening_scale(i))
+        } else {
+            input.clone()
+        };
+        &self.components.transpose() * input + self.mean.clone()
+    }
+}
+
+#[cfg(test)]
+fn line_samples() -> Vec<Vector<f64>> {
+    // Points along (1, 2, 0) with a little noise on the third axis
+    (0..10).map(|i| {
+        let t = i as f64 - 4.5f64;
+        Vector::from_slice(&[1f64 + t, 2f64 * t, if i % 2 == 0 { 0.1f64 } else { -0.1f64 }])
+    }).collect()
+}
+
+#[test]
+fn test_pca() {
+    let samples = line_samples();
+
+    for &solver in [Solver::Covariance, Solver::Svd].iter() {
+        let mut pca = Pca::new(Components::Count(3)).with_solver(solver);
+        pca.fit(&samples);
+
+        let ratio = pca.explained_variance_ratio();
+        assert!(ratio[0] > 0.99);
+        assert!((ratio.data().iter().fold(0f64, |a,b| a+b) - 1f64).abs() < 1e-9);
+
+        let axis = pca.components().row(0);
+        let expected = Vector::from_slice(&[1f64, 2f64, 0f64]) / 5f64.sqrt();
+        assert!((&axis - &expected).norm_sq() < 1e-4);
+
+        // Nothing is lost when keeping every component.
+        for x in samples.iter() {
+            assert!((&pca.inverse_transform(&pca.transform(x)) - x).norm_sq() < 1e-18);
+        }
+    }
+}
+
+#[test]
+fn test_pca_variance_fraction() {
+    let samples = line_samples();
+
+    let mut pca = Pca::new(Components::Variance(0.99f64)).with_whitening();
+    pca.fit(&samples);
+    assert_eq!(pca.components().m, 1);
+
+    // Whitened projections have unit variance.
+    let projected: Vec<f64> = samples.iter().map(|x| pca.transform(x)[0]).collect();
+    let variance = projected.iter().map(|p| p * p).fold(0f64, |a,b| a+b) / 9f64;
+    assert!((variance - 1f64).abs() < 1e-9);
+
+    let back = pca.inverse_transform(&pca.transform(&samples[0]));
+    assert!((&back - &samples[0]).norm_sq() < 0.02);
+
+    // The third feature is constant, so the last component has no variance.
+    let flat: Vec<Vector<f64>> = samples.iter().map(|x| Vector::from_slice(&[x[0], x[1], 1f64])).collect();
+    let mut pca = Pca::new(Components::Count(3)).with_whitening();
+    pca.fit(&flat);
+    let projected = pca.transform(&flat[0]);
+    assert!(projected.data().iter().all(|v| v.is_finite()));
+    assert!((&pca.inverse_transform(&projected) - &flat[0]).norm_sq() < 1e-12);
+}
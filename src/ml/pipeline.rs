@@ -0,0 +1,129 @@
+use ml::{Classifier,Transformer};
+
+/// Two transformers applied one after the other.
+///
+/// Chains can be nested to compose any number of transformers.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+}
+
+impl <A, B> Chain<A,B> {
+    /// Creates a chain applying `first`, then `second` on its output.
+    pub fn new(first: A, second: B) -> Self {
+        Chain {
+            first: first,
+            second: second,
+        }
+    }
+
+    /// Returns the first transformer.
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// Returns the second transformer.
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+}
+
+impl <A: Transformer, B: Transformer<Input=A::Output>> Transformer for Chain<A,B> {
+    type Input = A::Input;
+    type Output = B::Output;
+
+    /// Fits the first transformer, then the second one on the transformed samples.
+    fn fit(&mut self, samples: &[A::Input]) {
+        self.first.fit(samples);
+        let transformed: Vec<A::Output> = samples.iter().map(|x| self.first.transform(x)).collect();
+        self.second.fit(&transformed);
+    }
+
+    fn transform(&self, input: &A::Input) -> B::Output {
+        self.second.transform(&self.first.transform(input))
+    }
+
+    fn inverse_transform(&self, output: &B::Output) -> A::Input {
+        self.first.inverse_transform(&self.second.inverse_transform(output))
+    }
+}
+
+/// A transformer followed by a classifier, trained and applied together.
+///
+/// Training fits the transformer on the training samples only, so the same
+/// preprocessing is applied to any later input. Use a `Chain` to apply several
+/// transformers, e.g. `Pipeline::new(Chain::new(StandardScaler::new(), pca), lda)`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Pipeline<T, C> {
+    transformer: T,
+    classifier: C,
+}
+
+impl <T, C> Pipeline<T,C> {
+    /// Creates a new pipeline.
+    pub fn new(transformer: T, classifier: C) -> Self {
+        Pipeline {
+            transformer: transformer,
+            classifier: classifier,
+        }
+    }
+
+    /// Returns the transformer.
+    pub fn transformer(&self) -> &T {
+        &self.transformer
+    }
+
+    /// Returns the final classifier.
+    pub fn classifier(&self) -> &C {
+        &self.classifier
+    }
+}
+
+impl <T: Transformer, C: Classifier<Input=T::Output>> Classifier for Pipeline<T,C> {
+    type Input = T::Input;
+    type Label = C::Label;
+
+    fn train(&mut self, samples: &[T::Input], labels: &[C::Label]) {
+        self.transformer.fit(samples);
+        let transformed: Vec<T::Output> = samples.iter().map(|x| self.transformer.transform(x)).collect();
+        self.classifier.train(&transformed, labels);
+    }
+
+    fn classify(&self, input: &T::Input) -> C::Label {
+        self.classifier.classify(&self.transformer.transform(input))
+    }
+}
+
+#[test]
+fn test_pipeline() {
+    use alg::Vector;
+    use ml::preprocessing::{StandardScaler,MinMaxScaler};
+    use ml::pca::{Pca,Components};
+    use ml::tree::DecisionTree;
+
+    // Class is 1 iff the sum of the features is positive: a single split on the first component.
+    let samples: Vec<Vector<f64>> = (0..20).map(|i| {
+        let t = i as f64 - 9.5f64;
+        Vector::from_slice(&[100f64 * t, t + if i % 2 == 0 { 0.1f64 } else { -0.1f64 }])
+    }).collect();
+    let labels: Vec<usize> = (0..20).map(|i| if i >= 10 { 1 } else { 0 }).collect();
+
+    let steps = Chain::new(StandardScaler::new(), Pca::new(Components::Count(1)));
+    let mut pipeline = Pipeline::new(steps, DecisionTree::new(1, 2));
+    pipeline.train(&samples, &labels);
+
+    for (x,&y) in samples.iter().zip(labels.iter()) {
+        assert_eq!(pipeline.classify(x), y);
+    }
+    assert_eq!(pipeline.transformer().first().mean()[0], 0f64);
+    assert_eq!(pipeline.transformer().transform(&samples[0]).dim(), 1);
+
+    // Chained transformers invert in reverse order.
+    let mut chain = Chain::new(StandardScaler::new(), MinMaxScaler::new(0f64, 1f64));
+    chain.fit(&samples);
+    let back = chain.inverse_transform(&chain.transform(&samples[3]));
+    assert!((&back - &samples[3]).norm_sq() < 1e-18);
+}
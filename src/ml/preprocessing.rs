@@ -0,0 +1,609 @@
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+use num::Float;
+
+use ml::Transformer;
+use ml::neighbors::{Distance,NanEuclidean};
+use alg::Vector;
+use data::Error;
+use data::model::{Model,Scalar,PayloadWriter,PayloadReader};
+
+// Replaces zero scales, from constant features, with one.
+fn safe_scale<T: Float>(scale: T) -> T {
+    if scale == T::zero() { T::one() } else { scale }
+}
+
+// Value at the given quantile of the sorted values, interpolating linearly.
+fn quantile<T: Float>(sorted: &[T], q: T) -> T {
+    if sorted.is_empty() { return T::nan(); }
+    let position = q * T::from(sorted.len() - 1).unwrap();
+    let low = position.floor().to_usize().unwrap();
+    let high = (low + 1).min(sorted.len() - 1);
+    let frac = position - T::from(low).unwrap();
+    sorted[low] + (sorted[high] - sorted[low]) * frac
+}
+
+// Sorted values of each feature, without the missing ones.
+fn sorted_columns<T: Float>(samples: &[Vector<T>]) -> Vec<Vec<T>> {
+    let d = samples[0].dim();
+    (0..d).map(|j| {
+        let mut column: Vec<T> = samples.iter().map(|x| x[j]).filter(|v| !v.is_nan()).collect();
+        column.sort_by(|a,b| a.partial_cmp(b).unwrap());
+        column
+    }).collect()
+}
+
+/// Scales each feature to zero mean and unit variance.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct StandardScaler<T> {
+    with_mean: bool,
+    with_std: bool,
+
+    mean: Vector<T>,
+    scale: Vector<T>,
+}
+
+impl <T: Float> StandardScaler<T> {
+    /// Creates a new scaler, both centering and scaling.
+    pub fn new() -> Self {
+        StandardScaler {
+            with_mean: true,
+            with_std: true,
+
+            mean: Vector::dummy(),
+            scale: Vector::dummy(),
+        }
+    }
+
+    /// Disables centering: only divides by the standard deviation.
+    pub fn without_mean(mut self) -> Self {
+        self.with_mean = false;
+        self
+    }
+
+    /// Disables scaling: only subtracts the mean.
+    pub fn without_std(mut self) -> Self {
+        self.with_std = false;
+        self
+    }
+
+    /// Returns the value subtracted from each feature.
+    pub fn mean(&self) -> &Vector<T> {
+        &self.mean
+    }
+
+    /// Returns the value each feature is divided by.
+    pub fn scale(&self) -> &Vector<T> {
+        &self.scale
+    }
+}
+
+impl <T: Float> Transformer for StandardScaler<T> {
+    type Input = Vector<T>;
+    type Output = Vector<T>;
+
+    fn fit(&mut self, samples: &[Vector<T>]) {
+        if samples.is_empty() { return; }
+
+        let d = samples[0].dim();
+        let n = T::from(samples.len()).unwrap();
+        let mean = samples.iter().fold(Vector::zero(d), |a,x| &a + x) / n;
+        let variance = samples.iter()
+            .map(|x| (x - &mean).chain_apply(|v| v * v))
+            .fold(Vector::zero(d), |a,x| a + x) / n;
+
+        self.scale = if self.with_std {
+            variance.chain_apply(|v| safe_scale(v.sqrt()))
+        } else {
+            Vector::from_copies(d, T::one())
+        };
+        self.mean = if self.with_mean { mean } else { Vector::zero(d) };
+    }
+
+    fn transform(&self, input: &Vector<T>) -> Vector<T> {
+        Vector::new(input.dim(), |i| (input[i] - self.mean[i]) / self.scale[i])
+    }
+
+    fn inverse_transform(&self, output: &Vector<T>) -> Vector<T> {
+        Vector::new(output.dim(), |i| output[i] * self.scale[i] + self.mean[i])
+    }
+}
+
+impl <T: Scalar> Model for StandardScaler<T> {
+    type Scalar = T;
+
+    fn model_type() -> &'static str { "StandardScaler" }
+
+    fn write_payload(&self, payload: &mut PayloadWriter) {
+        payload.write_bool(self.with_mean);
+        payload.write_bool(self.with_std);
+        payload.write_vector(&self.mean);
+        payload.write_vector(&self.scale);
+    }
+
+    fn read_payload(payload: &mut PayloadReader) -> Result<Self, Error> {
+        let with_mean = try!(payload.read_bool());
+        let with_std = try!(payload.read_bool());
+        let mean: Vector<T> = try!(payload.read_vector());
+        let scale: Vector<T> = try!(payload.read_vector());
+        if mean.dim() != scale.dim() {
+            return Err(Error::Format(format!("StandardScaler has {} means, but {} scales", mean.dim(), scale.dim())));
+        }
+
+        Ok(StandardScaler {
+            with_mean: with_mean,
+            with_std: with_std,
+
+            mean: mean,
+            scale: scale,
+        })
+    }
+}
+
+/// Scales each feature linearly to the given range.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct MinMaxScaler<T> {
+    min: T,
+    max: T,
+
+    data_min: Vector<T>,
+    data_max: Vector<T>,
+}
+
+impl <T: Float> MinMaxScaler<T> {
+    /// Creates a new scaler mapping the training range of each feature to `[min, max]`.
+    pub fn new(min: T, max: T) -> Self {
+        MinMaxScaler {
+            min: min,
+            max: max,
+
+            data_min: Vector::dummy(),
+            data_max: Vector::dummy(),
+        }
+    }
+
+    /// Returns the smallest training value of each feature.
+    pub fn data_min(&self) -> &Vector<T> {
+        &self.data_min
+    }
+
+    /// Returns the largest training value of each feature.
+    pub fn data_max(&self) -> &Vector<T> {
+        &self.data_max
+    }
+
+    fn scale(&self, i: usize) -> T {
+        safe_scale(self.data_max[i] - self.data_min[i]) / (self.max - self.min)
+    }
+}
+
+impl <T: Float> Transformer for MinMaxScaler<T> {
+    type Input = Vector<T>;
+    type Output = Vector<T>;
+
+    fn fit(&mut self, samples: &[Vector<T>]) {
+        if samples.is_empty() { return; }
+
+        let d = samples[0].dim();
+        self.data_min = Vector::new(d, |j| samples.iter().fold(T::infinity(), |a,x| a.min(x[j])));
+        self.data_max = Vector::new(d, |j| samples.iter().fold(T::neg_infinity(), |a,x| a.max(x[j])));
+    }
+
+    fn transform(&self, input: &Vector<T>) -> Vector<T> {
+        Vector::new(input.dim(), |i| (input[i] - self.data_min[i]) / self.scale(i) + self.min)
+    }
+
+    fn inverse_transform(&self, output: &Vector<T>) -> Vector<T> {
+        Vector::new(output.dim(), |i| (output[i] - self.min) * self.scale(i) + self.data_min[i])
+    }
+}
+
+/// Centers each feature on its median and scales it by its interquartile range.
+///
+/// Less sensitive to outliers than the `StandardScaler`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct RobustScaler<T> {
+    q_low: T,
+    q_high: T,
+
+    center: Vector<T>,
+    scale: Vector<T>,
+}
+
+impl <T: Float> RobustScaler<T> {
+    /// Creates a new scaler using the 25th and 75th percentiles.
+    pub fn new() -> Self {
+        let q_low = T::from(0.25f64).unwrap();
+        let q_high = T::from(0.75f64).unwrap();
+        RobustScaler::with_quantiles(q_low, q_high)
+    }
+
+    /// Creates a new scaler dividing by the range between the given quantiles, in `[0, 1]`.
+    pub fn with_quantiles(q_low: T, q_high: T) -> Self {
+        RobustScaler {
+            q_low: q_low,
+            q_high: q_high,
+
+            center: Vector::dummy(),
+            scale: Vector::dummy(),
+        }
+    }
+
+    /// Returns the median of each feature.
+    pub fn center(&self) -> &Vector<T> {
+        &self.center
+    }
+
+    /// Returns the quantile range of each feature.
+    pub fn scale(&self) -> &Vector<T> {
+        &self.scale
+    }
+}
+
+impl <T: Float> Transformer for RobustScaler<T> {
+    type Input = Vector<T>;
+    type Output = Vector<T>;
+
+    fn fit(&mut self, samples: &[Vector<T>]) {
+        if samples.is_empty() { return; }
+
+        let columns = sorted_columns(samples);
+        let half = T::from(0.5f64).unwrap();
+        self.center = Vector::new(columns.len(), |j| quantile(&columns[j], half));
+        self.scale = Vector::new(columns.len(), |j| {
+            safe_scale(quantile(&columns[j], self.q_high) - quantile(&columns[j], self.q_low))
+        });
+    }
+
+    fn transform(&self, input: &Vector<T>) -> Vector<T> {
+        Vector::new(input.dim(), |i| (input[i] - self.center[i]) / self.scale[i])
+    }
+
+    fn inverse_transform(&self, output: &Vector<T>) -> Vector<T> {
+        Vector::new(output.dim(), |i| output[i] * self.scale[i] + self.center[i])
+    }
+}
+
+/// Norm used by a `Normalizer`.
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub enum Norm {
+    /// Sum of absolute values.
+    L1,
+    /// Euclidean norm.
+    L2,
+    /// Largest absolute value.
+    Max,
+}
+
+/// Scales each sample individually to unit norm.
+///
+/// This is stateless: `fit` does nothing. Since the original norm is lost,
+/// `inverse_transform` returns its input unchanged.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Normalizer<T> {
+    norm: Norm,
+
+    phantom: PhantomData<T>,
+}
+
+impl <T: Float> Normalizer<T> {
+    /// Creates a new normalizer for the given norm.
+    pub fn new(norm: Norm) -> Self {
+        Normalizer {
+            norm: norm,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the norm of the given vector.
+    pub fn norm_of(&self, input: &Vector<T>) -> T {
+        let values = input.data().iter();
+        match self.norm {
+            Norm::L1 => values.fold(T::zero(), |a,&b| a + b.abs()),
+            Norm::L2 => input.norm_sq().sqrt(),
+            Norm::Max => values.fold(T::zero(), |a,&b| a.max(b.abs())),
+        }
+    }
+}
+
+impl <T: Float> Transformer for Normalizer<T> {
+    type Input = Vector<T>;
+    type Output = Vector<T>;
+
+    fn fit(&mut self, _: &[Vector<T>]) {
+    }
+
+    /// Zero vectors are left unchanged.
+    fn transform(&self, input: &Vector<T>) -> Vector<T> {
+        input / safe_scale(self.norm_of(input))
+    }
+
+    fn inverse_transform(&self, output: &Vector<T>) -> Vector<T> {
+        output.clone()
+    }
+}
+
+/// Statistic used by a `SimpleImputer` to replace missing values.
+#[derive(Clone,Copy,PartialEq,Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub enum Strategy<T> {
+    /// Mean of the feature.
+    Mean,
+    /// Median of the feature.
+    Median,
+    /// Most frequent value of the feature, the smallest on ties.
+    MostFrequent,
+    /// The given value.
+    Constant(T),
+}
+
+/// Replaces missing values, represented as NaN, by a statistic of their feature.
+///
+/// Features with no value at all during training are filled with zero,
+/// unless a constant is given.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct SimpleImputer<T> {
+    strategy: Strategy<T>,
+
+    statistics: Vector<T>,
+}
+
+impl <T: Float> SimpleImputer<T> {
+    /// Creates a new imputer using the given strategy.
+    pub fn new(strategy: Strategy<T>) -> Self {
+        SimpleImputer {
+            strategy: strategy,
+
+            statistics: Vector::dummy(),
+        }
+    }
+
+    /// Returns the value replacing missing values of each feature.
+    pub fn statistics(&self) -> &Vector<T> {
+        &self.statistics
+    }
+}
+
+// Most frequent of the sorted values, the smallest on ties.
+fn mode<T: Float>(sorted: &[T]) -> T {
+    let mut best = (T::nan(), 0);
+    let mut start = 0;
+    for i in 1..sorted.len() + 1 {
+        if i == sorted.len() || sorted[i] != sorted[start] {
+            if i - start > best.1 { best = (sorted[start], i - start); }
+            start = i;
+        }
+    }
+    best.0
+}
+
+impl <T: Float> Transformer for SimpleImputer<T> {
+    type Input = Vector<T>;
+    type Output = Vector<T>;
+
+    fn fit(&mut self, samples: &[Vector<T>]) {
+        if samples.is_empty() { return; }
+
+        let columns = sorted_columns(samples);
+        let half = T::from(0.5f64).unwrap();
+        self.statistics = Vector::new(columns.len(), |j| {
+            let column = &columns[j];
+            let statistic = match self.strategy {
+                Strategy::Constant(value) => return value,
+                Strategy::Mean => column.iter().fold(T::zero(), |a,&b| a+b) / T::from(column.len()).unwrap(),
+                Strategy::Median => quantile(column, half),
+                Strategy::MostFrequent => mode(column),
+            };
+            if column.is_empty() { T::zero() } else { statistic }
+        });
+    }
+
+    fn transform(&self, input: &Vector<T>) -> Vector<T> {
+        Vector::new(input.dim(), |i| if input[i].is_nan() { self.statistics[i] } else { input[i] })
+    }
+
+    /// Returns the input unchanged: which values were missing is not known.
+    fn inverse_transform(&self, output: &Vector<T>) -> Vector<T> {
+        output.clone()
+    }
+}
+
+/// Replaces missing values, represented as NaN, by their mean over the nearest training samples.
+///
+/// Distances only use the features present in both samples, as in `NanEuclidean`.
+/// For each missing feature, the `k` nearest samples having it are averaged.
+/// Falls back to the mean of the feature when no training sample has it.
+/// Fitted on no samples, inputs are returned unchanged.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct KnnImputer<T> {
+    k: usize,
+
+    samples: Vec<Vector<T>>,
+    means: SimpleImputer<T>,
+}
+
+impl <T: Float> KnnImputer<T> {
+    /// Creates a new imputer averaging `k` neighbors.
+    pub fn new(k: usize) -> Self {
+        KnnImputer {
+            k: k,
+
+            samples: Vec::new(),
+            means: SimpleImputer::new(Strategy::Mean),
+        }
+    }
+}
+
+impl <T: Float> Transformer for KnnImputer<T> {
+    type Input = Vector<T>;
+    type Output = Vector<T>;
+
+    fn fit(&mut self, samples: &[Vector<T>]) {
+        self.samples = samples.to_vec();
+        self.means.fit(samples);
+    }
+
+    fn transform(&self, input: &Vector<T>) -> Vector<T> {
+        if !input.data().iter().any(|v| v.is_nan()) {
+            return input.clone();
+        }
+
+        if self.samples.is_empty() {
+            return input.clone();
+        }
+
+        // Samples sharing at least one feature, nearest first
+        let mut neighbors: Vec<(usize, T)> = self.samples.iter()
+            .map(|s| NanEuclidean.distance(input, s))
+            .enumerate()
+            .filter(|&(_,d)| d.is_finite())
+            .collect();
+        neighbors.sort_by(|a,b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+        Vector::new(input.dim(), |j| {
+            if !input[j].is_nan() { return input[j]; }
+
+            let donors: Vec<T> = neighbors.iter()
+                .map(|&(i,_)| self.samples[i][j])
+                .filter(|v| !v.is_nan())
+                .take(self.k)
+                .collect();
+            if donors.is_empty() {
+                self.means.statistics()[j]
+            } else {
+                donors.iter().fold(T::zero(), |a,&b| a+b) / T::from(donors.len()).unwrap()
+            }
+        })
+    }
+
+    /// Returns the input unchanged: which values were missing is not known.
+    fn inverse_transform(&self, output: &Vector<T>) -> Vector<T> {
+        output.clone()
+    }
+}
+
+#[cfg(test)]
+fn columns() -> Vec<Vector<f64>> {
+    vec![
+        Vector::from_slice(&[1f64, 10f64, 5f64]),
+        Vector::from_slice(&[2f64, 20f64, 5f64]),
+        Vector::from_slice(&[3f64, 30f64, 5f64]),
+        Vector::from_slice(&[4f64, 1000f64, 5f64]),
+    ]
+}
+
+#[test]
+fn test_standard_scaler() {
+    let samples = columns();
+    let mut scaler = StandardScaler::new();
+    scaler.fit(&samples);
+
+    assert_eq!(scaler.mean()[0], 2.5f64);
+    // Constant features are only centered.
+    assert_eq!(scaler.scale()[2], 1f64);
+
+    let scaled: Vec<Vector<f64>> = samples.iter().map(|x| scaler.transform(x)).collect();
+    for j in 0..2 {
+        let mean = scaled.iter().map(|x| x[j]).fold(0f64, |a,b| a+b) / 4f64;
+        let variance = scaled.iter().map(|x| x[j] * x[j]).fold(0f64, |a,b| a+b) / 4f64;
+        assert!(mean.abs() < 1e-12);
+        assert!((variance - 1f64).abs() < 1e-12);
+    }
+    for (x,s) in samples.iter().zip(scaled.iter()) {
+        assert!((&scaler.inverse_transform(s) - x).norm_sq() < 1e-18);
+    }
+}
+
+#[test]
+fn test_min_max_scaler() {
+    let samples = columns();
+    let mut scaler = MinMaxScaler::new(-1f64, 1f64);
+    scaler.fit(&samples);
+
+    assert_eq!(scaler.data_max()[1], 1000f64);
+    assert_eq!(scaler.transform(&samples[0]), Vector::from_slice(&[-1f64, -1f64, -1f64]));
+    assert_eq!(scaler.transform(&samples[3])[0], 1f64);
+    assert!((&scaler.inverse_transform(&scaler.transform(&samples[2])) - &samples[2]).norm_sq() < 1e-18);
+}
+
+#[test]
+fn test_robust_scaler() {
+    let samples = columns();
+    let mut scaler = RobustScaler::new();
+    scaler.fit(&samples);
+
+    // The outlier barely moves the median and quartiles.
+    assert_eq!(scaler.center()[1], 25f64);
+    assert_eq!(scaler.scale()[1], 272.5f64 - 17.5f64);
+    assert_eq!(scaler.transform(&samples[1])[0], -0.5f64 / 1.5f64);
+    assert!((&scaler.inverse_transform(&scaler.transform(&samples[3])) - &samples[3]).norm_sq() < 1e-18);
+}
+
+#[test]
+fn test_normalizer() {
+    let x = Vector::from_slice(&[3f64, -4f64]);
+
+    assert_eq!(Normalizer::new(Norm::L1).transform(&x), Vector::from_slice(&[3f64 / 7f64, -4f64 / 7f64]));
+    assert_eq!(Normalizer::new(Norm::L2).transform(&x), Vector::from_slice(&[0.6f64, -0.8f64]));
+    assert_eq!(Normalizer::new(Norm::Max).transform(&x), Vector::from_slice(&[0.75f64, -1f64]));
+    let zero: Vector<f64> = Vector::zero(2);
+    assert_eq!(Normalizer::new(Norm::L2).transform(&zero), zero);
+}
+
+#[test]
+fn test_simple_imputer() {
+    let nan = ::std::f64::NAN;
+    let samples = vec![
+        Vector::from_slice(&[1f64, 2f64, nan]),
+        Vector::from_slice(&[2f64, nan, nan]),
+        Vector::from_slice(&[nan, 2f64, nan]),
+        Vector::from_slice(&[6f64, 5f64, nan]),
+    ];
+    let missing = Vector::from_slice(&[nan, nan, nan]);
+
+    let expected = [
+        (Strategy::Mean, [3f64, 3f64, 0f64]),
+        (Strategy::Median, [2f64, 2f64, 0f64]),
+        (Strategy::MostFrequent, [1f64, 2f64, 0f64]),
+        (Strategy::Constant(-1f64), [-1f64, -1f64, -1f64]),
+    ];
+    for &(strategy, ref values) in expected.iter() {
+        let mut imputer = SimpleImputer::new(strategy);
+        imputer.fit(&samples);
+        assert_eq!(imputer.transform(&missing), Vector::from_slice(values));
+        assert_eq!(imputer.transform(&samples[0])[0], 1f64);
+    }
+}
+
+#[test]
+fn test_knn_imputer() {
+    let nan = ::std::f64::NAN;
+    let samples = vec![
+        Vector::from_slice(&[0f64, 0f64, nan]),
+        Vector::from_slice(&[1f64, 1f64, 10f64]),
+        Vector::from_slice(&[2f64, 2f64, 20f64]),
+        Vector::from_slice(&[10f64, 10f64, 100f64]),
+    ];
+
+    let mut imputer = KnnImputer::new(2);
+    imputer.fit(&samples);
+
+    // The nearest sample lacks the feature, so the next two are used.
+    assert_eq!(imputer.transform(&Vector::from_slice(&[0f64, 0f64, nan])), Vector::from_slice(&[0f64, 0f64, 15f64]));
+    assert_eq!(imputer.transform(&Vector::from_slice(&[nan, 9f64, nan])), Vector::from_slice(&[6f64, 9f64, 60f64]));
+    // No shared feature: falls back to the mean.
+    let mean = imputer.transform(&Vector::from_slice(&[nan, nan, nan]));
+    assert_eq!(mean, Vector::from_slice(&[13f64 / 4f64, 13f64 / 4f64, 130f64 / 3f64]));
+
+    let mut empty = KnnImputer::new(2);
+    empty.fit(&[]);
+    assert!(empty.transform(&Vector::from_slice(&[nan, 1f64]))[0].is_nan());
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize,Deserializer};
+use serde::de::Error;
+
+/// Version of the serialized model layout, increased whenever the fields of a model change.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// A model serialized along with the format version, to check compatibility on load.
+///
+/// Deserializing fails if the saved version is not `FORMAT_VERSION`.
+#[derive(Serialize,Deserialize)]
+pub struct Saved<M> {
+    #[serde(deserialize_with = "check_version")]
+    version: u32,
+    model: M,
+}
+
+impl <M> Saved<M> {
+    /// Wraps a model with the current format version.
+    pub fn new(model: M) -> Self {
+        Saved {
+            version: FORMAT_VERSION,
+            model: model,
+        }
+    }
+
+    /// Returns the format version the model was saved with.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the model.
+    pub fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// Unwraps the model.
+    pub fn into_model(self) -> M {
+        self.model
+    }
+}
+
+fn check_version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let version = try!(u32::deserialize(deserializer));
+    if version != FORMAT_VERSION {
+        return Err(D::Error::custom(format!("model format version {} is not supported, expected {}", version, FORMAT_VERSION)));
+    }
+    Ok(version)
+}
+
+#[test]
+fn test_saved_model() {
+    use serde_json;
+    use alg::Vector;
+    use ml::Classifier;
+    use ml::linear::LinearRegression;
+
+    let samples = vec![Vector::from_slice(&[1f64, 0f64]), Vector::from_slice(&[1f64, 1f64]), Vector::from_slice(&[1f64, 2f64])];
+    let mut model = LinearRegression::new();
+    model.train(&samples, &[1f64, 3f64, 5f64]);
+
+    let json = serde_json::to_string(&Saved::new(model)).unwrap();
+    let loaded: Saved<LinearRegression<f64>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.version(), FORMAT_VERSION);
+    assert!((loaded.model().classify(&Vector::from_slice(&[1f64, 3f64])) - 7f64).abs() < 1e-9);
+
+    // Models saved with another version are refused.
+    let json = json.replacen(&format!("\"version\":{}", FORMAT_VERSION), "\"version\":0", 1);
+    assert!(serde_json::from_str::<Saved<LinearRegression<f64>>>(&json).is_err());
+}
@@ -0,0 +1,384 @@
+use std::marker::PhantomData;
+
+use num::Float;
+use rand::Rng;
+
+use alg::Vector;
+use ml::{Classifier,Optimizer};
+use ml::cross_validation::{Fold,cross_validate};
+
+/// Range of values a hyperparameter can take.
+#[derive(Clone,PartialEq,Debug)]
+pub enum Parameter {
+    /// A real value, uniformly in `[low, high]`.
+    Continuous(f64, f64),
+    /// A real value, uniformly in `[low, high]` on a logarithmic scale. Both bounds must be positive.
+    LogScaled(f64, f64),
+    /// An integer in `[low, high]`.
+    Integer(i64, i64),
+    /// One of the given choices.
+    Categorical(Vec<String>),
+}
+
+/// Value of a hyperparameter.
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub enum ParameterValue {
+    /// Value of a `Continuous` or `LogScaled` parameter.
+    Real(f64),
+    /// Value of an `Integer` parameter.
+    Integer(i64),
+    /// Index of the choice of a `Categorical` parameter.
+    Category(usize),
+}
+
+impl ParameterValue {
+    /// Returns the real value. Panics for other kinds of values.
+    pub fn real(&self) -> f64 {
+        match *self {
+            ParameterValue::Real(x) => x,
+            _ => panic!("Parameter value is not real."),
+        }
+    }
+
+    /// Returns the integer value. Panics for other kinds of values.
+    pub fn integer(&self) -> i64 {
+        match *self {
+            ParameterValue::Integer(i) => i,
+            _ => panic!("Parameter value is not an integer."),
+        }
+    }
+
+    /// Returns the index of the choice. Panics for other kinds of values.
+    pub fn category(&self) -> usize {
+        match *self {
+            ParameterValue::Category(c) => c,
+            _ => panic!("Parameter value is not a category."),
+        }
+    }
+}
+
+/// Named hyperparameters, and their mapping to the vectors explored by an `Optimizer`.
+///
+/// Each parameter maps to one coordinate in `[-1, 1]`, centered on `0` like the initial samples of `CmaEs`;
+/// coordinates outside are clamped.
+/// Integer and categorical parameters split `[-1, 1]` into one equal interval per value.
+#[derive(Clone,PartialEq,Debug)]
+pub struct ParameterSpace {
+    names: Vec<String>,
+    parameters: Vec<Parameter>,
+}
+
+// Index of the interval of `u` among `count` equal ones.
+fn bucket(u: f64, count: usize) -> usize {
+    ((u * count as f64).floor() as usize).min(count - 1)
+}
+
+impl ParameterSpace {
+    /// Creates a new space, without parameters.
+    pub fn new() -> Self {
+        ParameterSpace {
+            names: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Adds a parameter.
+    ///
+    /// Panics if its range is empty, or if a `LogScaled` bound isn't positive.
+    pub fn with(mut self, name: &str, parameter: Parameter) -> Self {
+        let valid = match parameter {
+            Parameter::Continuous(low, high) => low <= high,
+            Parameter::LogScaled(low, high) => 0f64 < low && low <= high,
+            Parameter::Integer(low, high) => low <= high,
+            Parameter::Categorical(ref choices) => !choices.is_empty(),
+        };
+        if !valid {
+            panic!("Invalid range for parameter `{}`.", name);
+        }
+        self.names.push(name.to_string());
+        self.parameters.push(parameter);
+        self
+    }
+
+    /// Returns the number of parameters, which is the dimension of the vectors.
+    pub fn dim(&self) -> usize {
+        self.parameters.len()
+    }
+
+    /// Returns the name of each parameter.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns the range of each parameter.
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    /// Returns the index of the parameter with the given name.
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    // Maps a fraction `u` of the range, in `[0, 1]`, to a value of the `i`-th parameter.
+    fn value(&self, i: usize, u: f64) -> ParameterValue {
+        let u = if u.is_nan() { 0f64 } else { u.max(0f64).min(1f64) };
+        match self.parameters[i] {
+            Parameter::Continuous(low, high) => ParameterValue::Real(low + u * (high - low)),
+            Parameter::LogScaled(low, high) => ParameterValue::Real((low.ln() + u * (high.ln() - low.ln())).exp()),
+            Parameter::Integer(low, high) => ParameterValue::Integer(low + bucket(u, (high - low + 1) as usize) as i64),
+            Parameter::Categorical(ref choices) => ParameterValue::Category(bucket(u, choices.len())),
+        }
+    }
+
+    // Maps a value of the `i`-th parameter to a fraction of its range, in the middle of its interval if discrete.
+    fn coordinate(&self, i: usize, value: ParameterValue) -> f64 {
+        let ratio = |x: f64, low: f64, high: f64| if high > low { (x - low) / (high - low) } else { 0.5f64 };
+        match (&self.parameters[i], value) {
+            (&Parameter::Continuous(low, high), ParameterValue::Real(x)) => ratio(x, low, high),
+            (&Parameter::LogScaled(low, high), ParameterValue::Real(x)) => ratio(x.ln(), low.ln(), high.ln()),
+            (&Parameter::Integer(low, high), ParameterValue::Integer(x)) => ((x - low) as f64 + 0.5f64) / (high - low + 1) as f64,
+            (&Parameter::Categorical(ref choices), ParameterValue::Category(c)) => (c as f64 + 0.5f64) / choices.len() as f64,
+            _ => panic!("Parameter value doesn't match parameter `{}`.", self.names[i]),
+        }
+    }
+
+    /// Returns the parameter values of an optimizer vector.
+    ///
+    /// Panics if the vector doesn't have one coordinate per parameter.
+    pub fn from_vector<T: Float>(&self, v: &Vector<T>) -> Vec<ParameterValue> {
+        if v.dim() != self.dim() {
+            panic!("Vector doesn't match the parameter space.");
+        }
+        (0..self.dim()).map(|i| self.value(i, (v[i].to_f64().unwrap() + 1f64) / 2f64)).collect()
+    }
+
+    /// Returns the optimizer vector of parameter values.
+    ///
+    /// Panics if the values don't match the parameters.
+    pub fn to_vector<T: Float>(&self, values: &[ParameterValue]) -> Vector<T> {
+        if values.len() != self.dim() {
+            panic!("Values don't match the parameter space.");
+        }
+        Vector::new(self.dim(), |i| T::from(2f64 * self.coordinate(i, values[i]) - 1f64).unwrap())
+    }
+
+    /// Returns every combination of values, with `steps` values for each real parameter, from low to high.
+    ///
+    /// Integer parameters take at most `steps` values too, and categorical parameters every choice.
+    pub fn grid(&self, steps: usize) -> Vec<Vec<ParameterValue>> {
+        let steps = steps.max(1);
+        let mut grid = vec![Vec::new()];
+        for (i,parameter) in self.parameters.iter().enumerate() {
+            let values: Vec<ParameterValue> = match *parameter {
+                Parameter::Integer(low, high) if ((high - low + 1) as usize) <= steps => {
+                    (low..high + 1).map(ParameterValue::Integer).collect()
+                },
+                Parameter::Categorical(ref choices) => (0..choices.len()).map(ParameterValue::Category).collect(),
+                _ => {
+                    let mut values: Vec<ParameterValue> = (0..steps)
+                        .map(|k| if steps == 1 { 0.5f64 } else { k as f64 / (steps - 1) as f64 })
+                        .map(|u| self.value(i, u))
+                        .collect();
+                    values.dedup();
+                    values
+                },
+            };
+
+            grid = grid.into_iter().flat_map(|point: Vec<ParameterValue>| {
+                values.iter().map(move |&v| {
+                    let mut point = point.clone();
+                    point.push(v);
+                    point
+                }).collect::<Vec<_>>()
+            }).collect();
+        }
+        grid
+    }
+
+    /// Returns random parameter values, uniform in the space.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Vec<ParameterValue> {
+        (0..self.dim()).map(|i| self.value(i, rng.gen::<f64>())).collect()
+    }
+}
+
+/// Scores of the parameter values tried by a search.
+#[derive(Clone,PartialEq,Debug)]
+pub struct SearchResult {
+    /// Values with the highest score, the first ones on ties.
+    pub best: Vec<ParameterValue>,
+    /// Highest score.
+    pub best_score: f64,
+    /// Every value tried, with its score, in order.
+    pub results: Vec<(Vec<ParameterValue>, f64)>,
+}
+
+fn search<I, F>(candidates: I, objective: F) -> SearchResult
+    where I: Iterator<Item=Vec<ParameterValue>>, F: Fn(&[ParameterValue]) -> f64
+{
+    let results: Vec<(Vec<ParameterValue>, f64)> = candidates.map(|values| {
+        let score = objective(&values);
+        (values, score)
+    }).collect();
+
+    let mut best = 0;
+    for i in 1..results.len() {
+        if results[i].1 > results[best].1 {
+            best = i;
+        }
+    }
+    SearchResult {
+        best: results[best].0.clone(),
+        best_score: results[best].1,
+        results: results,
+    }
+}
+
+/// Tries every point of the grid of the space, keeping the one with the highest score.
+pub fn grid_search<F>(space: &ParameterSpace, steps: usize, objective: F) -> SearchResult
+    where F: Fn(&[ParameterValue]) -> f64
+{
+    search(space.grid(steps).into_iter(), objective)
+}
+
+/// Tries `n_iter` random points of the space, keeping the one with the highest score.
+///
+/// Panics if `n_iter` is `0`.
+pub fn random_search<F, R>(space: &ParameterSpace, n_iter: usize, rng: &mut R, objective: F) -> SearchResult
+    where F: Fn(&[ParameterValue]) -> f64, R: Rng
+{
+    if n_iter == 0 {
+        panic!("Random search needs at least one iteration.");
+    }
+    let candidates: Vec<Vec<ParameterValue>> = (0..n_iter).map(|_| space.sample(rng)).collect();
+    search(candidates.into_iter(), objective)
+}
+
+/// Maximizes the score with an optimizer exploring the vectors of the space, such as `CmaEs`.
+///
+/// Optimizers minimize, so they are given the negated score.
+/// The optimizer must explore vectors of dimension `space.dim()`, as `CmaEs::new(space.dim(), pop, t)` does.
+pub fn optimizer_search<O, T, F>(optimizer: &O, space: &ParameterSpace, objective: F) -> Vec<ParameterValue>
+    where O: Optimizer<Input=Vector<T>, Score=T>, T: Float, F: Fn(&[ParameterValue]) -> f64
+{
+    let best = optimizer.optimize(|v| T::from(-objective(&space.from_vector(&v))).unwrap());
+    space.from_vector(&best)
+}
+
+/// Scores parameter values by cross-validating the classifier they build.
+pub struct CrossValidated<'a, C, B, S>
+    where C: Classifier, C::Input: 'a, C::Label: 'a
+{
+    build: B,
+    score: S,
+    samples: &'a [C::Input],
+    labels: &'a [C::Label],
+    folds: Vec<Fold>,
+
+    phantom: PhantomData<C>,
+}
+
+impl <'a, C, B, S> CrossValidated<'a, C, B, S>
+    where C: Classifier + Clone, C::Input: Clone, C::Label: Clone,
+          B: Fn(&[ParameterValue]) -> C, S: Fn(&[C::Label], &[C::Label]) -> f64
+{
+    /// Creates a new objective.
+    ///
+    /// - `build`: builds an untrained classifier from parameter values.
+    /// - `score`: scores predictions given the true labels, as for `cross_validate`.
+    pub fn new(build: B, score: S, samples: &'a [C::Input], labels: &'a [C::Label], folds: Vec<Fold>) -> Self {
+        CrossValidated {
+            build: build,
+            score: score,
+            samples: samples,
+            labels: labels,
+            folds: folds,
+
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the mean score over the folds of the classifier built from the values.
+    pub fn score(&self, values: &[ParameterValue]) -> f64 {
+        let classifier = (self.build)(values);
+        let scores = cross_validate(&classifier, self.samples, self.labels, &self.folds, &self.score);
+        scores.iter().fold(0f64, |a,b| a+b) / scores.len() as f64
+    }
+}
+
+#[cfg(test)]
+fn space() -> ParameterSpace {
+    ParameterSpace::new()
+        .with("alpha", Parameter::LogScaled(1e-3, 1e1))
+        .with("depth", Parameter::Integer(1, 4))
+        .with("kernel", Parameter::Categorical(vec!["linear".to_string(), "rbf".to_string()]))
+}
+
+#[test]
+fn test_parameter_space() {
+    let space = space();
+    let values = vec![ParameterValue::Real(0.1), ParameterValue::Integer(3), ParameterValue::Category(1)];
+    let v: Vector<f64> = space.to_vector(&values);
+    assert!(v[0].abs() < 1e-12);
+    assert_eq!(v[1], 0.25f64);
+    let back = space.from_vector(&v);
+    assert!((back[0].real() - 0.1f64).abs() < 1e-12);
+    assert_eq!(&back[1..], &values[1..]);
+
+    // Coordinates are clamped.
+    let values = space.from_vector(&Vector::from_slice(&[-2f64, 2f64, -0.2f64]));
+    assert!((values[0].real() - 1e-3f64).abs() < 1e-15);
+    assert_eq!(&values[1..], &[ParameterValue::Integer(4), ParameterValue::Category(0)]);
+
+    let grid = space.grid(3);
+    assert_eq!(grid.len(), 3 * 3 * 2);
+    assert_eq!(grid[0][1], ParameterValue::Integer(1));
+    assert_eq!(grid[2][1], ParameterValue::Integer(3));
+    assert_eq!(space.grid(4).len(), 4 * 4 * 2);
+}
+
+#[test]
+fn test_search() {
+    use rand::{SeedableRng,StdRng};
+
+    let space = space();
+    let objective = |values: &[ParameterValue]| {
+        let depth = values[1].integer() as f64;
+        -(values[0].real().log10() + 1f64).powi(2) - (depth - 3f64).powi(2) + values[2].category() as f64
+    };
+
+    let result = grid_search(&space, 5, &objective);
+    assert_eq!(result.results.len(), 5 * 4 * 2);
+    assert!((result.best[0].real() - 0.1f64).abs() < 1e-12);
+    assert_eq!(&result.best[1..], &[ParameterValue::Integer(3), ParameterValue::Category(1)]);
+
+    let mut rng: StdRng = SeedableRng::from_seed(&[3][..]);
+    let result = random_search(&space, 50, &mut rng, &objective);
+    assert_eq!(result.results.len(), 50);
+    assert!(result.results.iter().all(|&(_,s)| s <= result.best_score));
+}
+
+#[test]
+fn test_cross_validated_search() {
+    use ml::tree::DecisionTree;
+    use ml::cmaes::CmaEs;
+    use ml::metrics::accuracy;
+    use ml::cross_validation::StratifiedKFold;
+
+    // The middle block can only be isolated with two splits.
+    let xs = [0, 1, 2, 3, 10, 11, 12, 13, 20, 21, 22, 23];
+    let samples: Vec<Vector<f64>> = xs.iter().map(|&x| Vector::from_slice(&[x as f64])).collect();
+    let labels: Vec<usize> = xs.iter().map(|&x| if x >= 10 && x < 20 { 1 } else { 0 }).collect();
+
+    let space = ParameterSpace::new().with("max_depth", Parameter::Integer(1, 3));
+    let objective = CrossValidated::new(
+        |values: &[ParameterValue]| DecisionTree::<f64,usize>::new(values[0].integer() as usize, 2),
+        accuracy, &samples, &labels, StratifiedKFold::new(3).split(&labels));
+
+    let result = grid_search(&space, 3, |values| objective.score(values));
+    assert_eq!(result.best, vec![ParameterValue::Integer(2)]);
+    assert_eq!(result.best_score, 1f64);
+
+    let best = optimizer_search(&CmaEs::<f64>::new(1, 6, 10), &space, |values| objective.score(values));
+    assert_eq!(objective.score(&best), 1f64);
+}
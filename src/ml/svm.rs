@@ -0,0 +1,520 @@
+use num::Float;
+
+use ml::Classifier;
+use alg::{Vector,Matrix};
+
+/// A positive semi-definite similarity measure between two vectors.
+pub trait Kernel<T> {
+    fn apply(&self, a: &Vector<T>, b: &Vector<T>) -> T;
+}
+
+/// `K(a,b) = a.b`
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct LinearKernel;
+
+impl <T: Float> Kernel<T> for LinearKernel {
+    fn apply(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        a.dot(b)
+    }
+}
+
+/// `K(a,b) = (gamma * a.b + coef0) ^ degree`
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct PolynomialKernel<T> {
+    pub degree: i32,
+    pub gamma: T,
+    pub coef0: T,
+}
+
+impl <T: Float> PolynomialKernel<T> {
+    pub fn new(degree: i32, gamma: T, coef0: T) -> Self {
+        PolynomialKernel {
+            degree: degree,
+            gamma: gamma,
+            coef0: coef0,
+        }
+    }
+}
+
+impl <T: Float> Kernel<T> for PolynomialKernel<T> {
+    fn apply(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        (self.gamma * a.dot(b) + self.coef0).powi(self.degree)
+    }
+}
+
+/// Gaussian radial basis function: `K(a,b) = exp(-gamma * |a-b|²)`
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct RbfKernel<T> {
+    pub gamma: T,
+}
+
+impl <T: Float> RbfKernel<T> {
+    pub fn new(gamma: T) -> Self {
+        RbfKernel {
+            gamma: gamma,
+        }
+    }
+}
+
+impl <T: Float> Kernel<T> for RbfKernel<T> {
+    fn apply(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        (-self.gamma * (a - b).norm_sq()).exp()
+    }
+}
+
+/// `K(a,b) = tanh(gamma * a.b + coef0)`
+///
+/// Not positive semi-definite for every parameter choice.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct SigmoidKernel<T> {
+    pub gamma: T,
+    pub coef0: T,
+}
+
+impl <T: Float> SigmoidKernel<T> {
+    pub fn new(gamma: T, coef0: T) -> Self {
+        SigmoidKernel {
+            gamma: gamma,
+            coef0: coef0,
+        }
+    }
+}
+
+impl <T: Float> Kernel<T> for SigmoidKernel<T> {
+    fn apply(&self, a: &Vector<T>, b: &Vector<T>) -> T {
+        (self.gamma * a.dot(b) + self.coef0).tanh()
+    }
+}
+
+/// Marker for models trained directly from a Gram matrix.
+///
+/// See `Svc::train_gram` and `Svr::train_gram`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Precomputed;
+
+/// Returns the Gram matrix of the samples: `G[(i,j)] = K(samples[i], samples[j])`.
+pub fn gram_matrix<T: Float, K: Kernel<T>>(kernel: &K, samples: &[Vector<T>]) -> Matrix<T> {
+    let n = samples.len();
+    let mut gram = Matrix::zero(n, n);
+    for i in 0..n {
+        for j in 0..i+1 {
+            let k = kernel.apply(&samples[i], &samples[j]);
+            gram[(i,j)] = k;
+            gram[(j,i)] = k;
+        }
+    }
+    gram
+}
+
+// Sequential Minimal Optimization, with the maximal violating pair working set.
+//
+// Solves `min 1/2 a'Qa + p'a` subject to `y'a = 0` and `0 <= a <= C`,
+// where `Q[s][t] = y[s] * y[t] * K[index[s]][index[t]]`.
+// Returns the optimal `a` and the intercept.
+fn smo<T: Float>(gram: &Matrix<T>, index: &[usize], y: &[T], p: &[T], c: T, tol: T, max_iter: usize) -> (Vec<T>, T) {
+    let n = index.len();
+    let mut alpha = vec![T::zero(); n];
+    // Gradient of the objective: Qa + p
+    let mut grad = Vec::from(p);
+
+    let is_up = |a: T, y: T| (y > T::zero() && a < c) || (y < T::zero() && a > T::zero());
+    let is_low = |a: T, y: T| (y > T::zero() && a > T::zero()) || (y < T::zero() && a < c);
+
+    for _ in 0..max_iter {
+        // i maximizes -y*G over the variables that can move up, j minimizes it over those that can move down.
+        let mut i = None;
+        let mut j = None;
+        for t in 0..n {
+            let v = -y[t] * grad[t];
+            if is_up(alpha[t], y[t]) && i.map_or(true, |i: usize| v > -y[i] * grad[i]) { i = Some(t); }
+            if is_low(alpha[t], y[t]) && j.map_or(true, |j: usize| v < -y[j] * grad[j]) { j = Some(t); }
+        }
+        let (i, j) = match (i, j) {
+            (Some(i), Some(j)) => (i, j),
+            _ => break,
+        };
+
+        let gap = -y[i] * grad[i] + y[j] * grad[j];
+        if gap < tol { break; }
+
+        // Move alpha[i] by y[i]*t and alpha[j] by -y[j]*t, keeping y'a constant.
+        let (ki, kj) = (index[i], index[j]);
+        let mut eta = gram[(ki,ki)] + gram[(kj,kj)] - (gram[(ki,kj)] + gram[(ki,kj)]);
+        if eta <= T::zero() { eta = T::from(1e-12).unwrap(); }
+
+        let mut t = gap / eta;
+        t = t.min(if y[i] > T::zero() { c - alpha[i] } else { alpha[i] });
+        t = t.min(if y[j] > T::zero() { alpha[j] } else { c - alpha[j] });
+
+        alpha[i] = (alpha[i] + y[i] * t).max(T::zero()).min(c);
+        alpha[j] = (alpha[j] - y[j] * t).max(T::zero()).min(c);
+
+        for s in 0..n {
+            let ks = index[s];
+            grad[s] = grad[s] + y[s] * t * (gram[(ks,ki)] - gram[(ks,kj)]);
+        }
+    }
+
+    // Intercept: averaged over free variables if any, else the middle of the feasible range.
+    let mut sum = T::zero();
+    let mut count = 0;
+    let mut upper = T::infinity();
+    let mut lower = T::neg_infinity();
+    for t in 0..n {
+        let v = -y[t] * grad[t];
+        if alpha[t] > T::zero() && alpha[t] < c {
+            sum = sum + v;
+            count += 1;
+        }
+        if is_up(alpha[t], y[t]) { lower = lower.max(v); }
+        if is_low(alpha[t], y[t]) { upper = upper.min(v); }
+    }
+
+    let b = if count > 0 {
+        sum / T::from(count).unwrap()
+    } else if lower.is_finite() && upper.is_finite() {
+        (lower + upper) / (T::one() + T::one())
+    } else if lower.is_finite() {
+        lower
+    } else if upper.is_finite() {
+        upper
+    } else {
+        T::zero()
+    };
+
+    (alpha, b)
+}
+
+// Fitted parameters shared by both SVM types.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+struct Support<T> {
+    // Index of each support vector in the training set
+    indices: Vec<usize>,
+    vectors: Vec<Vector<T>>,
+    coef: Vec<T>,
+    intercept: T,
+}
+
+impl <T: Float> Support<T> {
+    fn empty() -> Self {
+        Support {
+            indices: Vec::new(),
+            vectors: Vec::new(),
+            coef: Vec::new(),
+            intercept: T::zero(),
+        }
+    }
+
+    // Keeps the training samples with a non-zero coefficient.
+    fn from_coef(coef: Vec<T>, intercept: T, samples: Option<&[Vector<T>]>) -> Self {
+        let mut support = Support::empty();
+        support.intercept = intercept;
+        for (i,c) in coef.into_iter().enumerate() {
+            if c == T::zero() { continue; }
+            support.indices.push(i);
+            support.coef.push(c);
+            if let Some(samples) = samples {
+                support.vectors.push(samples[i].clone());
+            }
+        }
+        support
+    }
+
+    fn decision<K: Kernel<T>>(&self, kernel: &K, input: &Vector<T>) -> T {
+        self.vectors.iter().zip(self.coef.iter())
+            .map(|(v,&c)| c * kernel.apply(v, input))
+            .fold(self.intercept, |a,b| a+b)
+    }
+
+    fn decision_gram(&self, row: &Vector<T>) -> T {
+        self.indices.iter().zip(self.coef.iter())
+            .map(|(&i,&c)| c * row[i])
+            .fold(self.intercept, |a,b| a+b)
+    }
+}
+
+/// Soft-margin support vector classifier.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Svc<T, K> {
+    kernel: K,
+    c: T,
+    tol: T,
+    max_iter: usize,
+
+    support: Support<T>,
+}
+
+impl <T: Float, K> Svc<T,K> {
+    /// Creates a new classifier.
+    ///
+    /// - `kernel`: the kernel to use, or `Precomputed` to train with `train_gram`.
+    /// - `c`: penalty on margin violations. Lower values give a softer margin.
+    pub fn new(kernel: K, c: T) -> Self {
+        Svc {
+            kernel: kernel,
+            c: c,
+            tol: T::from(1e-3).unwrap(),
+            max_iter: 100000,
+            support: Support::empty(),
+        }
+    }
+
+    /// Sets the stopping tolerance on the KKT conditions violation.
+    pub fn with_tolerance(mut self, tol: T) -> Self {
+        self.tol = tol;
+        self
+    }
+
+    /// Sets the maximum number of SMO iterations.
+    pub fn with_max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    /// Returns the index of each support vector in the training set.
+    pub fn support(&self) -> &[usize] {
+        &self.support.indices
+    }
+
+    /// Returns the support vectors.
+    ///
+    /// Empty when trained from a Gram matrix.
+    pub fn support_vectors(&self) -> &[Vector<T>] {
+        &self.support.vectors
+    }
+
+    /// Returns the dual coefficient `alpha * y` of each support vector.
+    pub fn dual_coef(&self) -> &[T] {
+        &self.support.coef
+    }
+
+    /// Returns the intercept of the decision function.
+    pub fn intercept(&self) -> T {
+        self.support.intercept
+    }
+
+    /// Trains the classifier from a precomputed Gram matrix of the training samples.
+    pub fn train_gram(&mut self, gram: &Matrix<T>, labels: &[bool]) {
+        self.support = self.solve(gram, labels, None);
+    }
+
+    /// Returns the decision function value, given the kernel value between the
+    /// input and each training sample.
+    pub fn decision_function_gram(&self, row: &Vector<T>) -> T {
+        self.support.decision_gram(row)
+    }
+
+    fn solve(&self, gram: &Matrix<T>, labels: &[bool], samples: Option<&[Vector<T>]>) -> Support<T> {
+        let n = labels.len();
+        let index: Vec<usize> = (0..n).collect();
+        let y: Vec<T> = labels.iter().map(|&b| if b { T::one() } else { -T::one() }).collect();
+        let p = vec![-T::one(); n];
+
+        let (alpha, b) = smo(gram, &index, &y, &p, self.c, self.tol, self.max_iter);
+        let coef = alpha.into_iter().zip(y.into_iter()).map(|(a,y)| a*y).collect();
+        Support::from_coef(coef, b, samples)
+    }
+}
+
+impl <T: Float, K: Kernel<T>> Svc<T,K> {
+    /// Returns the signed distance-like score of the input: positive means `true`.
+    pub fn decision_function(&self, input: &Vector<T>) -> T {
+        self.support.decision(&self.kernel, input)
+    }
+}
+
+impl <T: Float, K: Kernel<T>> Classifier for Svc<T,K> {
+    type Input = Vector<T>;
+    type Label = bool;
+
+    fn train(&mut self, samples: &[Vector<T>], labels: &[bool]) {
+        let gram = gram_matrix(&self.kernel, samples);
+        self.support = self.solve(&gram, labels, Some(samples));
+    }
+
+    fn classify(&self, input: &Vector<T>) -> bool {
+        self.decision_function(input) >= T::zero()
+    }
+}
+
+/// Epsilon-insensitive support vector regression.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct Svr<T, K> {
+    kernel: K,
+    c: T,
+    epsilon: T,
+    tol: T,
+    max_iter: usize,
+
+    support: Support<T>,
+}
+
+impl <T: Float, K> Svr<T,K> {
+    /// Creates a new regressor.
+    ///
+    /// - `kernel`: the kernel to use, or `Precomputed` to train with `train_gram`.
+    /// - `c`: penalty on errors larger than `epsilon`.
+    /// - `epsilon`: width of the tube within which errors are ignored.
+    pub fn new(kernel: K, c: T, epsilon: T) -> Self {
+        Svr {
+            kernel: kernel,
+            c: c,
+            epsilon: epsilon,
+            tol: T::from(1e-3).unwrap(),
+            max_iter: 100000,
+            support: Support::empty(),
+        }
+    }
+
+    /// Sets the stopping tolerance on the KKT conditions violation.
+    pub fn with_tolerance(mut self, tol: T) -> Self {
+        self.tol = tol;
+        self
+    }
+
+    /// Sets the maximum number of SMO iterations.
+    pub fn with_max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    /// Returns the index of each support vector in the training set.
+    pub fn support(&self) -> &[usize] {
+        &self.support.indices
+    }
+
+    /// Returns the support vectors.
+    ///
+    /// Empty when trained from a Gram matrix.
+    pub fn support_vectors(&self) -> &[Vector<T>] {
+        &self.support.vectors
+    }
+
+    /// Returns the dual coefficient `alpha - alpha*` of each support vector.
+    pub fn dual_coef(&self) -> &[T] {
+        &self.support.coef
+    }
+
+    /// Returns the intercept of the decision function.
+    pub fn intercept(&self) -> T {
+        self.support.intercept
+    }
+
+    /// Trains the regressor from a precomputed Gram matrix of the training samples.
+    pub fn train_gram(&mut self, gram: &Matrix<T>, labels: &[T]) {
+        self.support = self.solve(gram, labels, None);
+    }
+
+    /// Returns the prediction, given the kernel value between the input and each training sample.
+    pub fn decision_function_gram(&self, row: &Vector<T>) -> T {
+        self.support.decision_gram(row)
+    }
+
+    fn solve(&self, gram: &Matrix<T>, labels: &[T], samples: Option<&[Vector<T>]>) -> Support<T> {
+        // Two variables per sample: alpha (y = +1) and alpha* (y = -1).
+        let n = labels.len();
+        let index: Vec<usize> = (0..2*n).map(|s| s % n).collect();
+        let y: Vec<T> = (0..2*n).map(|s| if s < n { T::one() } else { -T::one() }).collect();
+        let p: Vec<T> = (0..2*n).map(|s| if s < n { self.epsilon - labels[s] } else { self.epsilon + labels[s-n] }).collect();
+
+        let (alpha, b) = smo(gram, &index, &y, &p, self.c, self.tol, self.max_iter);
+        let coef = (0..n).map(|i| alpha[i] - alpha[i+n]).collect();
+        Support::from_coef(coef, b, samples)
+    }
+}
+
+impl <T: Float, K: Kernel<T>> Svr<T,K> {
+    /// Returns the predicted value for the input.
+    pub fn decision_function(&self, input: &Vector<T>) -> T {
+        self.support.decision(&self.kernel, input)
+    }
+}
+
+impl <T: Float, K: Kernel<T>> Classifier for Svr<T,K> {
+    type Input = Vector<T>;
+    type Label = T;
+
+    fn train(&mut self, samples: &[Vector<T>], labels: &[T]) {
+        let gram = gram_matrix(&self.kernel, samples);
+        self.support = self.solve(&gram, labels, Some(samples));
+    }
+
+    fn classify(&self, input: &Vector<T>) -> T {
+        self.decision_function(input)
+    }
+}
+
+#[test]
+fn test_svc_linear() {
+    let samples: Vec<Vector<f64>> = vec![
+        Vector::from_slice(&[0f64, 0f64]), Vector::from_slice(&[1f64, 0f64]), Vector::from_slice(&[0f64, 1f64]),
+        Vector::from_slice(&[3f64, 3f64]), Vector::from_slice(&[4f64, 3f64]), Vector::from_slice(&[3f64, 4f64]),
+    ];
+    let labels = vec![false, false, false, true, true, true];
+
+    let mut svc = Svc::new(LinearKernel, 10f64);
+    svc.train(&samples, &labels);
+
+    for (x,&y) in samples.iter().zip(labels.iter()) {
+        assert_eq!(svc.classify(x), y);
+    }
+    // Only the closest points of each class are support vectors.
+    assert!(svc.support().len() < samples.len());
+    assert_eq!(svc.support_vectors().len(), svc.dual_coef().len());
+
+    // The margin is at +/-1 on the support vectors.
+    for &i in svc.support() {
+        assert!((svc.decision_function(&samples[i]).abs() - 1f64).abs() < 1e-2);
+    }
+
+    // Training from the Gram matrix gives the same model.
+    let gram = gram_matrix(&LinearKernel, &samples);
+    let mut pre = Svc::new(Precomputed, 10f64);
+    pre.train_gram(&gram, &labels);
+    assert_eq!(pre.support(), svc.support());
+    assert!((pre.decision_function_gram(&gram.row(0)) - svc.decision_function(&samples[0])).abs() < 1e-9);
+}
+
+#[test]
+fn test_svc_rbf() {
+    // XOR is not linearly separable.
+    let samples: Vec<Vector<f64>> = vec![
+        Vector::from_slice(&[0f64, 0f64]), Vector::from_slice(&[1f64, 1f64]),
+        Vector::from_slice(&[0f64, 1f64]), Vector::from_slice(&[1f64, 0f64]),
+    ];
+    let labels = vec![false, false, true, true];
+
+    let mut svc = Svc::new(RbfKernel::new(2f64), 100f64);
+    svc.train(&samples, &labels);
+    for (x,&y) in samples.iter().zip(labels.iter()) {
+        assert_eq!(svc.classify(x), y);
+    }
+
+    let mut svc = Svc::new(PolynomialKernel::new(2, 1f64, 1f64), 100f64);
+    svc.train(&samples, &labels);
+    for (x,&y) in samples.iter().zip(labels.iter()) {
+        assert_eq!(svc.classify(x), y);
+    }
+}
+
+#[test]
+fn test_svr() {
+    let samples: Vec<Vector<f64>> = (0..10).map(|i| Vector::from_slice(&[i as f64])).collect();
+    let labels: Vec<f64> = (0..10).map(|i| 2f64 * i as f64 + 1f64).collect();
+
+    let mut svr = Svr::new(LinearKernel, 100f64, 0.1f64).with_tolerance(1e-6);
+    svr.train(&samples, &labels);
+
+    for (x,&y) in samples.iter().zip(labels.iter()) {
+        assert!((svr.classify(x) - y).abs() <= 0.1 + 1e-3);
+    }
+    assert!(svr.support().len() < samples.len());
+}
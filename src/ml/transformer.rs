@@ -0,0 +1,8 @@
+pub trait Transformer {
+    type Input;
+    type Output;
+
+    fn fit(&mut self, samples: &[Self::Input]);
+    fn transform(&self, input: &Self::Input) -> Self::Output;
+    fn inverse_transform(&self, output: &Self::Output) -> Self::Input;
+}
@@ -0,0 +1,443 @@
+use std::cmp::Ordering;
+
+use num::Float;
+use rand::{thread_rng,Rng};
+
+use ml::Classifier;
+use alg::Vector;
+
+/// A label a decision tree can learn to predict.
+///
+/// Implemented for `usize` (classification, using the Gini impurity and a
+/// majority vote) and for `f32`/`f64` (regression, using the variance and the mean).
+pub trait Target: Clone {
+    /// Running statistics over a set of labels.
+    type Stats: Clone;
+
+    /// Returns the statistics of an empty set.
+    fn empty() -> Self::Stats;
+
+    /// Adds a label to the statistics.
+    fn add(stats: &mut Self::Stats, label: &Self);
+
+    /// Removes a label previously added to the statistics.
+    fn remove(stats: &mut Self::Stats, label: &Self);
+
+    /// Returns the total impurity of the set: lower is purer.
+    ///
+    /// This is not normalized by the set size, so impurities can be summed.
+    fn impurity(stats: &Self::Stats) -> f64;
+
+    /// Returns the label best representing the set.
+    fn predict(stats: &Self::Stats) -> Self;
+
+    /// Returns the loss of predicting `predicted` when `actual` was expected.
+    fn loss(predicted: &Self, actual: &Self) -> f64;
+
+    /// Returns the label best representing the given labels.
+    fn aggregate<'a, I>(labels: I) -> Self
+        where I: Iterator<Item=&'a Self>, Self: 'a
+    {
+        let mut stats = Self::empty();
+        for label in labels {
+            Self::add(&mut stats, label);
+        }
+        Self::predict(&stats)
+    }
+}
+
+impl Target for usize {
+    // Number of samples seen for each class
+    type Stats = Vec<usize>;
+
+    fn empty() -> Vec<usize> {
+        Vec::new()
+    }
+
+    fn add(stats: &mut Vec<usize>, label: &usize) {
+        if *label >= stats.len() {
+            stats.resize(*label + 1, 0);
+        }
+        stats[*label] += 1;
+    }
+
+    fn remove(stats: &mut Vec<usize>, label: &usize) {
+        stats[*label] -= 1;
+    }
+
+    fn impurity(stats: &Vec<usize>) -> f64 {
+        // Gini impurity, multiplied by the number of samples.
+        let n = stats.iter().fold(0, |a,b| a+b) as f64;
+        if n == 0f64 { return 0f64; }
+
+        let sum_sq = stats.iter().map(|&c| (c*c) as f64).fold(0f64, |a,b| a+b);
+        n - sum_sq / n
+    }
+
+    fn predict(stats: &Vec<usize>) -> usize {
+        // Ties go to the lowest class
+        let mut best = 0;
+        for (i,&c) in stats.iter().enumerate() {
+            if c > stats[best] { best = i; }
+        }
+        best
+    }
+
+    fn loss(predicted: &usize, actual: &usize) -> f64 {
+        if predicted == actual { 0f64 } else { 1f64 }
+    }
+}
+
+macro_rules! float_target {
+    ($t:ty) => {
+        impl Target for $t {
+            // Number of samples, sum and sum of squares
+            type Stats = (usize, f64, f64);
+
+            fn empty() -> (usize, f64, f64) {
+                (0, 0f64, 0f64)
+            }
+
+            fn add(stats: &mut (usize, f64, f64), label: &$t) {
+                let v = *label as f64;
+                stats.0 += 1;
+                stats.1 += v;
+                stats.2 += v * v;
+            }
+
+            fn remove(stats: &mut (usize, f64, f64), label: &$t) {
+                let v = *label as f64;
+                stats.0 -= 1;
+                stats.1 -= v;
+                stats.2 -= v * v;
+            }
+
+            fn impurity(&(n, sum, sum_sq): &(usize, f64, f64)) -> f64 {
+                // Variance, multiplied by the number of samples.
+                if n == 0 { return 0f64; }
+                (sum_sq - sum * sum / n as f64).max(0f64)
+            }
+
+            fn predict(&(n, sum, _): &(usize, f64, f64)) -> $t {
+                if n == 0 { return 0 as $t; }
+                (sum / n as f64) as $t
+            }
+
+            fn loss(predicted: &$t, actual: &$t) -> f64 {
+                let d = (*predicted - *actual) as f64;
+                d * d
+            }
+        }
+    }
+}
+
+float_target!(f32);
+float_target!(f64);
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+enum Node<T, L> {
+    Leaf(L),
+    Split {
+        feature: usize,
+        threshold: T,
+        // Side taken by missing (NaN) values
+        missing_left: bool,
+        // Samples with `x[feature] <= threshold`
+        left: Box<Node<T,L>>,
+        // Samples with `x[feature] > threshold`
+        right: Box<Node<T,L>>,
+    },
+}
+
+fn goes_left<T: Float>(value: T, threshold: T, missing_left: bool) -> bool {
+    if value.is_nan() { missing_left } else { value <= threshold }
+}
+
+impl <T: Float, L> Node<T,L> {
+    fn leaf(&self, input: &Vector<T>) -> &L {
+        match *self {
+            Node::Leaf(ref label) => label,
+            Node::Split { feature, threshold, missing_left, ref left, ref right } => {
+                if goes_left(input[feature], threshold, missing_left) {
+                    left.leaf(input)
+                } else {
+                    right.leaf(input)
+                }
+            }
+        }
+    }
+
+    fn n_leaves(&self) -> usize {
+        match *self {
+            Node::Leaf(_) => 1,
+            Node::Split { ref left, ref right, .. } => left.n_leaves() + right.n_leaves(),
+        }
+    }
+
+    fn collect_members(&self, samples: &[Vector<T>], indices: Vec<usize>, members: &mut Vec<Vec<usize>>) {
+        match *self {
+            Node::Leaf(_) => members.push(indices),
+            Node::Split { feature, threshold, missing_left, ref left, ref right } => {
+                let (l, r): (Vec<usize>, Vec<usize>) = indices.into_iter()
+                    .partition(|&i| goes_left(samples[i][feature], threshold, missing_left));
+                left.collect_members(samples, l, members);
+                right.collect_members(samples, r, members);
+            }
+        }
+    }
+
+    fn collect_leaves<'a>(&'a mut self, leaves: &mut Vec<&'a mut L>) {
+        match *self {
+            Node::Leaf(ref mut label) => leaves.push(label),
+            Node::Split { ref mut left, ref mut right, .. } => {
+                left.collect_leaves(leaves);
+                right.collect_leaves(leaves);
+            }
+        }
+    }
+}
+
+/// CART decision tree, splitting on a single feature threshold at each node.
+///
+/// Works for classification with `usize` labels and for regression with float labels.
+/// Missing values, as NaN, are ignored when choosing a split, then sent to the side
+/// reducing the impurity the most, or to the larger side if none were seen.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize,Deserialize))]
+pub struct DecisionTree<T, L> {
+    max_depth: usize,
+    min_samples_split: usize,
+    max_features: Option<usize>,
+
+    root: Option<Node<T,L>>,
+}
+
+impl <T: Float, L: Target> DecisionTree<T,L> {
+    /// Creates a new untrained tree.
+    ///
+    /// - `max_depth`: maximum number of splits from the root to any leaf.
+    /// - `min_samples_split`: nodes with fewer samples are not split further.
+    pub fn new(max_depth: usize, min_samples_split: usize) -> Self {
+        DecisionTree {
+            max_depth: max_depth,
+            min_samples_split: min_samples_split,
+            max_features: None,
+            root: None,
+        }
+    }
+
+    /// Only considers a random subset of `n` features at each split.
+    ///
+    /// By default, every feature is considered.
+    pub fn with_max_features(mut self, n: usize) -> Self {
+        self.max_features = Some(n);
+        self
+    }
+
+    /// Trains the tree on the given subset of the samples.
+    ///
+    /// `indices` may contain duplicates, as produced by bootstrap sampling.
+    /// `rng` is used to pick the features considered at each split.
+    pub fn train_on<R: Rng>(&mut self, samples: &[Vector<T>], labels: &[L], indices: &[usize], rng: &mut R) {
+        let mut indices = Vec::from(indices);
+        let root = self.build(samples, labels, &mut indices, 0, rng);
+        self.root = Some(root);
+    }
+
+    /// Returns the number of leaves in the trained tree.
+    pub fn n_leaves(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.n_leaves())
+    }
+
+    /// Returns the indices of the given samples falling into each leaf, in a single pass down the tree.
+    ///
+    /// Leaves are in the same order as in `leaves_mut`.
+    /// Panics if the tree was not trained.
+    pub fn leaf_members(&self, samples: &[Vector<T>], indices: &[usize]) -> Vec<Vec<usize>> {
+        match self.root {
+            None => panic!("Decision tree was not trained."),
+            Some(ref root) => {
+                let mut members = Vec::new();
+                root.collect_members(samples, indices.to_vec(), &mut members);
+                members
+            }
+        }
+    }
+
+    /// Returns a mutable reference to each leaf value, depth-first.
+    pub fn leaves_mut(&mut self) -> Vec<&mut L> {
+        let mut leaves = Vec::new();
+        if let Some(ref mut root) = self.root {
+            root.collect_leaves(&mut leaves);
+        }
+        leaves
+    }
+
+    fn build<R: Rng>(&self, samples: &[Vector<T>], labels: &[L], indices: &mut [usize], depth: usize, rng: &mut R) -> Node<T,L> {
+        let mut stats = L::empty();
+        for &i in indices.iter() {
+            L::add(&mut stats, &labels[i]);
+        }
+
+        if depth >= self.max_depth || indices.len() < self.min_samples_split.max(2) {
+            return Node::Leaf(L::predict(&stats));
+        }
+
+        let (feature, threshold, missing_left) = match self.best_split(samples, labels, indices, &stats, rng) {
+            None => return Node::Leaf(L::predict(&stats)),
+            Some(split) => split,
+        };
+
+        // Partition the indices in place: left side first.
+        let mut n_left = 0;
+        for i in 0..indices.len() {
+            if goes_left(samples[indices[i]][feature], threshold, missing_left) {
+                indices.swap(i, n_left);
+                n_left += 1;
+            }
+        }
+
+        let (left, right) = indices.split_at_mut(n_left);
+        Node::Split {
+            feature: feature,
+            threshold: threshold,
+            missing_left: missing_left,
+            left: Box::new(self.build(samples, labels, left, depth + 1, rng)),
+            right: Box::new(self.build(samples, labels, right, depth + 1, rng)),
+        }
+    }
+
+    // Returns the (feature, threshold, missing_left) split reducing the impurity the most, if any.
+    fn best_split<R: Rng>(&self, samples: &[Vector<T>], labels: &[L], indices: &mut [usize], stats: &L::Stats, rng: &mut R) -> Option<(usize, T, bool)> {
+        let dim = samples[indices[0]].dim();
+        let mut features: Vec<usize> = (0..dim).collect();
+        if let Some(n) = self.max_features {
+            rng.shuffle(&mut features);
+            features.truncate(n.max(1));
+        }
+
+        let parent = L::impurity(stats);
+        let mut best = None;
+        let mut best_impurity = parent - 1e-12;
+
+        for feature in features {
+            // Missing values go last, and are left out of the scan.
+            indices.sort_by(|&a, &b| {
+                let (a, b) = (samples[a][feature], samples[b][feature]);
+                match (a.is_nan(), b.is_nan()) {
+                    (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                    (x, y) => x.cmp(&y),
+                }
+            });
+            let n_present = indices.iter().take_while(|&&i| !samples[i][feature].is_nan()).count();
+            if n_present < 2 { continue; }
+
+            // Present samples on each side, and the same with the missing ones added.
+            let mut left = L::empty();
+            let mut right = stats.clone();
+            for &i in indices[n_present..].iter() {
+                L::remove(&mut right, &labels[i]);
+            }
+            let mut left_missing = L::empty();
+            for &i in indices[n_present..].iter() {
+                L::add(&mut left_missing, &labels[i]);
+            }
+            let mut right_missing = stats.clone();
+
+            for k in 0..n_present - 1 {
+                L::add(&mut left, &labels[indices[k]]);
+                L::remove(&mut right, &labels[indices[k]]);
+                L::add(&mut left_missing, &labels[indices[k]]);
+                L::remove(&mut right_missing, &labels[indices[k]]);
+
+                let a = samples[indices[k]][feature];
+                let b = samples[indices[k+1]][feature];
+                // Can't split between identical values
+                if !(a < b) { continue; }
+
+                let impurity_left = L::impurity(&left_missing) + L::impurity(&right);
+                let impurity_right = L::impurity(&left) + L::impurity(&right_missing);
+                let missing_left = if n_present == indices.len() {
+                    2 * (k + 1) >= n_present
+                } else {
+                    impurity_left < impurity_right
+                };
+
+                let impurity = impurity_left.min(impurity_right);
+                if impurity < best_impurity {
+                    best_impurity = impurity;
+                    let two = T::one() + T::one();
+                    best = Some((feature, a + (b - a) / two, missing_left));
+                }
+            }
+        }
+
+        best
+    }
+}
+
+impl <T: Float, L: Target> Classifier for DecisionTree<T,L> {
+    type Input = Vector<T>;
+    type Label = L;
+
+    fn train(&mut self, samples: &[Vector<T>], labels: &[L]) {
+        let indices: Vec<usize> = (0..samples.len()).collect();
+        self.train_on(samples, labels, &indices, &mut thread_rng());
+    }
+
+    /// Panics if the tree was not trained.
+    fn classify(&self, input: &Vector<T>) -> L {
+        match self.root {
+            None => panic!("Decision tree was not trained."),
+            Some(ref root) => root.leaf(input).clone(),
+        }
+    }
+}
+
+#[test]
+fn test_tree_classification() {
+    // Class is 1 iff x > 2
+    let samples: Vec<Vector<f64>> = (0..6).map(|i| Vector::from_slice(&[i as f64, 0f64])).collect();
+    let labels: Vec<usize> = (0..6).map(|i| if i > 2 { 1 } else { 0 }).collect();
+
+    let mut tree = DecisionTree::new(4, 2);
+    tree.train(&samples, &labels);
+
+    for (x,&y) in samples.iter().zip(labels.iter()) {
+        assert_eq!(tree.classify(x), y);
+    }
+    assert_eq!(tree.classify(&Vector::from_slice(&[2.4f64, 0f64])), 0);
+    assert_eq!(tree.classify(&Vector::from_slice(&[2.6f64, 0f64])), 1);
+}
+
+#[test]
+fn test_tree_regression() {
+    let samples: Vec<Vector<f64>> = (0..8).map(|i| Vector::from_slice(&[i as f64])).collect();
+    let labels: Vec<f64> = (0..8).map(|i| if i < 4 { 1f64 } else { 3f64 }).collect();
+
+    // A single split is enough.
+    let mut tree = DecisionTree::new(1, 2);
+    tree.train(&samples, &labels);
+
+    assert_eq!(tree.classify(&Vector::from_slice(&[0f64])), 1f64);
+    assert_eq!(tree.classify(&Vector::from_slice(&[7f64])), 3f64);
+
+    assert_eq!(tree.n_leaves(), 2);
+    assert_eq!(tree.leaf_members(&samples, &[7, 0, 5, 2]), vec![vec![0, 2], vec![7, 5]]);
+}
+
+#[test]
+fn test_tree_missing_values() {
+    // Class is 1 iff x > 2, and missing values only occur in class 1.
+    let nan = ::std::f64::NAN;
+    let samples: Vec<Vector<f64>> = [0f64, 1f64, 2f64, 3f64, 4f64, nan, nan].iter().map(|&x| Vector::from_slice(&[x])).collect();
+    let labels = [0, 0, 0, 1, 1, 1, 1];
+
+    let mut tree = DecisionTree::new(4, 2);
+    tree.train(&samples, &labels);
+
+    assert_eq!(tree.classify(&Vector::from_slice(&[1.5f64])), 0);
+    assert_eq!(tree.classify(&Vector::from_slice(&[3.5f64])), 1);
+    assert_eq!(tree.classify(&Vector::from_slice(&[nan])), 1);
+}